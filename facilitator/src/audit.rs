@@ -0,0 +1,532 @@
+//! Append-only audit log of payment operations.
+//!
+//! Separate from the debug logs, [`AuditLog`] appends one JSON object per
+//! `/verify` call and two per `/settle` call ([`AuditEntry`]) to the file
+//! configured in `[audit]`.  Lines are only ever appended; the file is
+//! rotated by renaming it to `<path>.<timestamp>` once it would grow past
+//! `max_size_mb` or when the hour or day (`rotation`) changes.
+//!
+//! A settlement is recorded as `attempted` before it is submitted, and again
+//! with its outcome once it completes.  An `attempted` line without a
+//! matching outcome (same `request_id`) marks a settlement that was cut off,
+//! e.g. by the request timeout or a client disconnect, and may still have
+//! reached the chain.
+//!
+//! Lines are written by a dedicated thread, so request handlers never wait
+//! for the disk.  With `sync = true` (the default) each line is flushed to
+//! disk before the next is written.
+//!
+//! With `hash_chain = true` every line carries `prev_hash`, the `hash` of the
+//! line before it (64 zeros for the first), and `hash`, the hex SHA-256 of
+//! the line serialised without its `hash` field.  The chain continues across
+//! rotated files and restarts, so removing, editing or reordering lines
+//! breaks it.  A last line that cannot be parsed, e.g. one cut short by a
+//! crash, stops the log from opening rather than silently starting a new
+//! chain.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tokio::sync::oneshot;
+
+use crate::config::{AuditConfig, AuditRotation};
+use crate::error::Error;
+use crate::logging::format_timestamp;
+
+/// `prev_hash` of the first line of a chain.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Bytes read from the end of the file at a time when looking for its last
+/// line.
+const TAIL_CHUNK: u64 = 8 * 1024;
+
+/// One audited `/verify` or `/settle` call.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AuditEntry {
+    /// Request ID (`x-request-id`) of the call.
+    pub request_id: String,
+    /// IP address of the client that made the call.
+    pub client: Option<String>,
+    /// `verify` or `settle`.
+    pub endpoint: &'static str,
+    /// CAIP-2 network of the payment.
+    pub network: Option<String>,
+    /// Address that pays.
+    pub payer: Option<String>,
+    /// Address that is paid.
+    pub pay_to: Option<String>,
+    /// Token the payment is made in.
+    pub asset: Option<String>,
+    /// Amount in the asset's smallest unit.
+    pub amount: Option<String>,
+    /// `valid`/`invalid` for verification; `attempted`, then
+    /// `success`/`error` for settlement.
+    pub outcome: &'static str,
+    /// Machine-readable reason of a failure.
+    pub reason: Option<String>,
+    /// Settlement transaction hash.
+    pub transaction: Option<String>,
+}
+
+/// Work for the writer thread.
+#[derive(Debug)]
+enum Message {
+    Entry(Box<AuditEntry>, SystemTime),
+    Flush(oneshot::Sender<()>),
+}
+
+/// The file currently written to.
+#[derive(Debug)]
+struct AuditFile {
+    file: File,
+    size: u64,
+    /// Rotation period the file was started in.
+    period: u64,
+    /// `hash` of the last line written, when hash chaining.
+    last_hash: Option<String>,
+}
+
+/// Owns the audit file; runs on the writer thread.
+#[derive(Debug)]
+struct Writer {
+    path: PathBuf,
+    max_size: Option<u64>,
+    rotation: AuditRotation,
+    sync: bool,
+    current: AuditFile,
+}
+
+/// Appends [`AuditEntry`] lines to the configured file.
+#[derive(Debug)]
+pub struct AuditLog {
+    sender: mpsc::Sender<Message>,
+}
+
+/// Index of the rotation period `at` falls in.
+fn period(rotation: AuditRotation, at: SystemTime) -> u64 {
+    let secs = at
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    match rotation {
+        AuditRotation::Never => 0,
+        AuditRotation::Hourly => secs / 3600,
+        AuditRotation::Daily => secs / 86_400,
+    }
+}
+
+/// Hex-encoded SHA-256 of `data`.
+fn sha256_hex(data: &str) -> String {
+    hex_encode(&Sha256::digest(data))
+}
+
+/// Lowercase hex encoding of `bytes`.
+pub fn hex_encode(bytes: &[u8]) -> String {
+    use std::fmt::Write as _;
+
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// The last non-empty line of `path`, if it has one.
+///
+/// Reads the file backwards in chunks, so only its tail is read.
+fn last_line(path: &Path) -> io::Result<Option<String>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let len = file.metadata()?.len();
+    let mut tail: Vec<u8> = Vec::new();
+    let mut start = len;
+    loop {
+        let trimmed = tail.trim_ascii_end();
+        // A newline before the last line, or the start of the file, means
+        // the whole line has been read.
+        if let Some(newline) = trimmed.iter().rposition(|&byte| byte == b'\n') {
+            return Ok(Some(line_text(&trimmed[newline + 1..])?));
+        }
+        if start == 0 {
+            return if trimmed.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(line_text(trimmed)?))
+            };
+        }
+        let read = TAIL_CHUNK.min(start);
+        start -= read;
+        let mut chunk = vec![0; usize::try_from(read).map_err(io::Error::other)?];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+    }
+}
+
+fn line_text(line: &[u8]) -> io::Result<String> {
+    String::from_utf8(line.to_vec()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// The `hash` to continue the chain of `path` from.
+///
+/// An empty or missing file starts a new chain, as does one whose last line
+/// has no `hash` (hash chaining was just enabled).
+fn last_hash(path: &Path) -> io::Result<String> {
+    let Some(line) = last_line(path)? else {
+        return Ok(GENESIS_HASH.to_owned());
+    };
+    let line: Map<String, Value> = serde_json::from_str(&line).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("last line is not a JSON object ({e}); repair or rotate the file"),
+        )
+    })?;
+    match line.get("hash") {
+        None => Ok(GENESIS_HASH.to_owned()),
+        Some(Value::String(hash)) => Ok(hash.clone()),
+        Some(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "last line has a non-string `hash`",
+        )),
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+impl AuditLog {
+    /// Open the audit log configured by `config`, or `None` if it sets no
+    /// path, and start its writer thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or, with hash chaining,
+    /// its last line cannot be read or parsed.
+    pub fn open(config: &AuditConfig) -> Result<Option<Self>, Error> {
+        let Some(path) = config.path.clone() else {
+            return Ok(None);
+        };
+        let context = || format!("cannot open audit log '{}'", path.display());
+        let writer =
+            Writer::open(path.clone(), config).map_err(|e| Error::config_with(context(), e))?;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("audit-log".to_owned())
+            .spawn(move || writer.run(&receiver))
+            .map_err(|e| Error::config_with(context(), e))?;
+        Ok(Some(Self { sender }))
+    }
+
+    /// Queue `entry` to be appended.
+    ///
+    /// Never blocks; failures to write are logged by the writer thread.
+    pub fn record(&self, entry: AuditEntry) {
+        let request_id = entry.request_id.clone();
+        if self
+            .sender
+            .send(Message::Entry(Box::new(entry), SystemTime::now()))
+            .is_err()
+        {
+            tracing::error!(request_id, "Audit log writer has stopped, entry lost");
+        }
+    }
+
+    /// Wait until every entry recorded so far has been written.
+    pub async fn flush(&self) {
+        let (done, written) = oneshot::channel();
+        if self.sender.send(Message::Flush(done)).is_ok() {
+            let _ = written.await;
+        }
+    }
+}
+
+impl Writer {
+    /// Open `path` for appending, continuing its hash chain if `config`
+    /// enables one.
+    fn open(path: PathBuf, config: &AuditConfig) -> io::Result<Self> {
+        let last_hash = if config.hash_chain {
+            Some(last_hash(&path)?)
+        } else {
+            None
+        };
+        let file = open_append(&path)?;
+        let metadata = file.metadata()?;
+        let started = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+        Ok(Self {
+            max_size: config.max_size_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+            rotation: config.rotation,
+            sync: config.sync,
+            current: AuditFile {
+                file,
+                size: metadata.len(),
+                period: period(config.rotation, started),
+                last_hash,
+            },
+            path,
+        })
+    }
+
+    /// Write entries until every [`AuditLog`] handle is dropped.
+    fn run(mut self, receiver: &mpsc::Receiver<Message>) {
+        for message in receiver {
+            match message {
+                Message::Entry(entry, at) => {
+                    if let Err(e) = self.write(&entry, at) {
+                        tracing::error!(
+                            path = %self.path.display(),
+                            request_id = entry.request_id,
+                            error = %e,
+                            "Failed to write audit log entry"
+                        );
+                    }
+                }
+                Message::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    }
+
+    fn write(&mut self, entry: &AuditEntry, now: SystemTime) -> io::Result<()> {
+        let timestamp = format_timestamp(now);
+        let mut line = Map::new();
+        line.insert("timestamp".to_owned(), timestamp.clone().into());
+        if let Value::Object(fields) = serde_json::to_value(entry)? {
+            line.extend(fields);
+        }
+        let hash = self.current.last_hash.as_ref().map(|prev_hash| {
+            line.insert("prev_hash".to_owned(), prev_hash.clone().into());
+            let hash = sha256_hex(&Value::Object(line.clone()).to_string());
+            line.insert("hash".to_owned(), hash.clone().into());
+            hash
+        });
+        let mut text = Value::Object(line).to_string();
+        text.push('\n');
+        let len = text.len() as u64;
+
+        let period = period(self.rotation, now);
+        let too_big = self
+            .max_size
+            .is_some_and(|max| self.current.size + len > max);
+        if self.current.size == 0 {
+            self.current.period = period;
+        } else if too_big || period != self.current.period {
+            self.rotate(period, &timestamp)?;
+        }
+
+        self.current.file.write_all(text.as_bytes())?;
+        if self.sync {
+            self.current.file.sync_data()?;
+        }
+        self.current.size += len;
+        if hash.is_some() {
+            self.current.last_hash = hash;
+        }
+        Ok(())
+    }
+
+    /// Rename the current file aside, named after the `timestamp` of the
+    /// entry that caused the rotation, and start a new one.
+    fn rotate(&mut self, period: u64, timestamp: &str) -> io::Result<()> {
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(".");
+        rotated.push(timestamp.replace(':', ""));
+        fs::rename(&self.path, rotated)?;
+        self.current.file = open_append(&self.path)?;
+        self.current.size = 0;
+        self.current.period = period;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_util::ScratchDir;
+
+    fn entry(request_id: &str) -> AuditEntry {
+        AuditEntry {
+            request_id: request_id.to_owned(),
+            client: Some("127.0.0.1".to_owned()),
+            endpoint: "settle",
+            network: Some("eip155:8453".to_owned()),
+            payer: Some("0xpayer".to_owned()),
+            pay_to: Some("0xpayee".to_owned()),
+            asset: Some("0xusdc".to_owned()),
+            amount: Some("1000".to_owned()),
+            outcome: "success",
+            reason: None,
+            transaction: Some("0xtx".to_owned()),
+        }
+    }
+
+    fn lines(path: &Path) -> Vec<Map<String, Value>> {
+        fs::read_to_string(path)
+            .expect("audit file is readable")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("audit line is JSON"))
+            .collect()
+    }
+
+    /// Checks every line's hash and link; returns the last hash.
+    fn verify_chain(lines: &[Map<String, Value>], mut prev: String) -> String {
+        for line in lines {
+            let mut unhashed = line.clone();
+            let hash = unhashed.remove("hash").expect("line is hashed");
+            assert_eq!(unhashed["prev_hash"], prev.as_str());
+            assert_eq!(hash, sha256_hex(&Value::Object(unhashed).to_string()));
+            prev = hash.as_str().expect("hash is a string").to_owned();
+        }
+        prev
+    }
+
+    fn config(dir: &ScratchDir) -> AuditConfig {
+        AuditConfig {
+            path: Some(dir.path().join("audit.jsonl")),
+            ..AuditConfig::default()
+        }
+    }
+
+    #[test]
+    fn disabled_without_path() {
+        assert!(AuditLog::open(&AuditConfig::default()).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn entries_are_appended_as_json_lines() {
+        let dir = ScratchDir::new("audit-append");
+        let log = AuditLog::open(&config(&dir)).unwrap().unwrap();
+        log.record(entry("a"));
+        log.record(entry("b"));
+        log.flush().await;
+
+        let lines = lines(&dir.path().join("audit.jsonl"));
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["request_id"], "a");
+        assert_eq!(lines[1]["transaction"], "0xtx");
+        assert!(lines[0]["timestamp"].as_str().unwrap().ends_with('Z'));
+        assert!(lines[0].get("hash").is_none());
+    }
+
+    #[tokio::test]
+    async fn hash_chain_links_lines_across_restarts() {
+        let dir = ScratchDir::new("audit-chain");
+        let config = AuditConfig {
+            hash_chain: true,
+            ..config(&dir)
+        };
+        let log = AuditLog::open(&config).unwrap().unwrap();
+        log.record(entry("a"));
+        log.flush().await;
+        let reopened = AuditLog::open(&config).unwrap().unwrap();
+        reopened.record(entry("b"));
+        reopened.record(entry("c"));
+        reopened.flush().await;
+
+        let lines = lines(&dir.path().join("audit.jsonl"));
+        assert_eq!(lines.len(), 3);
+        verify_chain(&lines, GENESIS_HASH.to_owned());
+
+        let mut tampered = lines;
+        tampered[1].insert("amount".to_owned(), "1".into());
+        let mut unhashed = tampered[1].clone();
+        let hash = unhashed.remove("hash").unwrap();
+        assert_ne!(hash, sha256_hex(&Value::Object(unhashed).to_string()));
+    }
+
+    #[test]
+    fn corrupt_last_line_is_an_error() {
+        let dir = ScratchDir::new("audit-corrupt");
+        let config = AuditConfig {
+            hash_chain: true,
+            ..config(&dir)
+        };
+        dir.write("audit.jsonl", "{\"hash\":\"abc\"}\n{\"hash\":\"de");
+        let err = AuditLog::open(&config).unwrap_err();
+        assert!(err.to_string().contains("cannot open audit log"));
+
+        // Without hash chaining, nothing needs to be read back.
+        assert!(
+            AuditLog::open(&AuditConfig {
+                hash_chain: false,
+                ..config
+            })
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn last_hash_reads_only_the_tail() {
+        let dir = ScratchDir::new("audit-tail");
+        let padding = "x".repeat(usize::try_from(TAIL_CHUNK).unwrap() * 3);
+        let path = dir.write(
+            "audit.jsonl",
+            &format!("not json {padding}\n{{\"pad\":\"{padding}\",\"hash\":\"abc\"}}\n\n"),
+        );
+        assert_eq!(last_hash(&path).unwrap(), "abc");
+
+        let path = dir.write("unchained.jsonl", "{\"request_id\":\"a\"}\n");
+        assert_eq!(last_hash(&path).unwrap(), GENESIS_HASH);
+        let path = dir.write("empty.jsonl", "\n");
+        assert_eq!(last_hash(&path).unwrap(), GENESIS_HASH);
+        assert_eq!(
+            last_hash(&dir.path().join("missing.jsonl")).unwrap(),
+            GENESIS_HASH
+        );
+    }
+
+    #[test]
+    fn rotates_on_size_and_period() {
+        let dir = ScratchDir::new("audit-rotate");
+        let config = AuditConfig {
+            max_size_mb: Some(1),
+            rotation: AuditRotation::Daily,
+            hash_chain: true,
+            sync: false,
+            ..config(&dir)
+        };
+        let path = config.path.clone().unwrap();
+        let mut writer = Writer::open(path.clone(), &config).unwrap();
+        let now = SystemTime::now();
+        let tomorrow = now + Duration::from_hours(24);
+        writer.write(&entry("a"), now).unwrap();
+        writer.write(&entry("b"), tomorrow).unwrap();
+        assert_eq!(dir.files().len(), 2);
+
+        // Names and timestamps come from the entry's time, not the clock.
+        let stamp = format_timestamp(tomorrow);
+        assert_eq!(lines(&path)[0]["timestamp"], stamp.as_str());
+        let mut rotated = path.clone().into_os_string();
+        rotated.push(format!(".{}", stamp.replace(':', "")));
+        assert!(dir.files().contains(&rotated.into()));
+
+        writer.current.size = 1024 * 1024;
+        writer
+            .write(&entry("c"), tomorrow + Duration::from_millis(1))
+            .unwrap();
+        let files = dir.files();
+        assert_eq!(files.len(), 3);
+
+        // The chain continues through the rotated files, oldest first.
+        let mut prev = GENESIS_HASH.to_owned();
+        for file in files.iter().filter(|file| **file != path) {
+            prev = verify_chain(&lines(file), prev);
+        }
+        verify_chain(&lines(&path), prev);
+    }
+}
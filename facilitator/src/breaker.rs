@@ -0,0 +1,669 @@
+//! Per-chain circuit breakers for failing RPC backends.
+//!
+//! - [`CircuitBreakers`] — tracks consecutive infrastructure failures per
+//!   chain and decides whether calls may reach the chain's RPC.
+//! - [`CircuitBreakerFacilitator`] — [`Facilitator`] decorator that bounds
+//!   every `/verify` and `/settle` call with a timeout, feeds the outcome into
+//!   the chain's breaker, and fails fast while the breaker is open.
+//!
+//! A breaker opens after `circuit_failure_threshold` consecutive failures
+//! and rejects calls for `circuit_cooldown_secs`.  Once the cool-down has
+//! elapsed a single probe call is let through: success closes the breaker,
+//! failure re-opens it for another cool-down.
+//!
+//! Only timeouts and calls that failed after a chain provider reported,
+//! through [`mark_rpc_unavailable`], that every RPC endpoint failed at the
+//! transport level count as failures.  Reverted transactions, JSON-RPC error
+//! responses and payment verification failures are answers from the chain.
+//! The mark is scoped to the task running the call, so it does not depend on
+//! how the error is worded by the time it reaches the breaker.
+//!
+//! Rejected calls and state changes are reported to each [`CircuitObserver`],
+//! which the `telemetry` feature uses for metrics.
+//!
+//! A settlement that times out may already have been broadcast, so it is
+//! answered with [`SETTLEMENT_OUTCOME_UNKNOWN`] rather than a plain error.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use r402::chain::ChainId;
+use r402::facilitator::{Facilitator, FacilitatorError};
+use r402::proto;
+
+use crate::chain::ChainsConfig;
+
+/// Machine-readable reason returned when a call is rejected by an open breaker.
+pub const CIRCUIT_OPEN: &str = "circuit_open";
+
+/// Machine-readable reason returned when a settlement timed out after it may
+/// have been submitted; the transaction can still be confirmed on-chain.
+pub const SETTLEMENT_OUTCOME_UNKNOWN: &str = "settlement_outcome_unknown";
+
+/// Prefix of chain provider errors raised when every RPC endpoint of a chain
+/// failed at the transport level (connection, HTTP or timeout errors).
+pub const RPC_UNAVAILABLE: &str = "RPC unavailable";
+
+tokio::task_local! {
+    /// Whether the call guarded on this task hit an RPC transport failure.
+    static RPC_FAILED: Cell<bool>;
+}
+
+/// Record that every RPC endpoint of a chain failed at the transport level
+/// while serving the call on the current task.
+///
+/// Called by the chain providers; does nothing outside a guarded call.
+pub fn mark_rpc_unavailable() {
+    let _ = RPC_FAILED.try_with(|failed| failed.set(true));
+}
+
+/// Breaker event reported to a [`CircuitObserver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitEvent {
+    /// A call was rejected by an open breaker.
+    Rejected,
+    /// The breaker opened.
+    Opened,
+    /// The breaker closed after a successful probe.
+    Closed,
+}
+
+/// Callback receiving every [`CircuitEvent`] of a chain.
+pub type CircuitObserver = Arc<dyn Fn(&ChainId, CircuitEvent) + Send + Sync>;
+
+/// Circuit breaker settings for a single chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerSettings {
+    /// Consecutive failures that open the breaker; `0` disables it.
+    pub failure_threshold: u32,
+    /// How long an open breaker rejects calls before probing again.
+    pub cooldown: Duration,
+    /// Upper bound for a single verify or settle call.
+    pub call_timeout: Duration,
+}
+
+/// State transition caused by recording a call outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transition {
+    Opened,
+    Closed,
+}
+
+/// Breaker state for a single chain.
+#[derive(Debug)]
+struct Breaker {
+    settings: CircuitBreakerSettings,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    probe_started: Option<Instant>,
+}
+
+impl Breaker {
+    const fn new(settings: CircuitBreakerSettings) -> Self {
+        Self {
+            settings,
+            consecutive_failures: 0,
+            open_until: None,
+            probe_started: None,
+        }
+    }
+
+    /// Decide whether a call may proceed, returning the remaining cool-down
+    /// if it may not.
+    ///
+    /// After the cool-down only one probe is admitted at a time.  A probe that
+    /// never reports back (e.g. its request was dropped) is superseded once the
+    /// call timeout has passed.
+    fn acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        let Some(open_until) = self.open_until else {
+            return Ok(());
+        };
+        if now < open_until {
+            return Err(open_until - now);
+        }
+        if let Some(started) = self.probe_started
+            && now.saturating_duration_since(started) < self.settings.call_timeout
+        {
+            return Err(Duration::ZERO);
+        }
+        self.probe_started = Some(now);
+        Ok(())
+    }
+
+    fn record_success(&mut self) -> Option<Transition> {
+        self.consecutive_failures = 0;
+        self.probe_started = None;
+        self.open_until.take().map(|_| Transition::Closed)
+    }
+
+    fn record_failure(&mut self, now: Instant) -> Option<Transition> {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.probe_started = None;
+        if self.open_until.is_some() {
+            // Failed probe: stay open for another cool-down.
+            self.open_until = Some(now + self.settings.cooldown);
+            return None;
+        }
+        if self.consecutive_failures >= self.settings.failure_threshold {
+            self.open_until = Some(now + self.settings.cooldown);
+            return Some(Transition::Opened);
+        }
+        None
+    }
+}
+
+/// Tracks circuit breaker state for every configured chain.
+///
+/// Chains with a failure threshold of `0` are not tracked and always pass
+/// [`CircuitBreakers::acquire`].
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    chains: Mutex<HashMap<ChainId, Breaker>>,
+}
+
+impl CircuitBreakers {
+    /// Build breakers for every chain that has the breaker enabled.
+    #[must_use]
+    pub fn from_config(chains: &ChainsConfig) -> Self {
+        let chains = chains
+            .iter()
+            .filter_map(|chain| {
+                let settings = chain.circuit_breaker();
+                (settings.failure_threshold > 0).then(|| (chain.chain_id(), Breaker::new(settings)))
+            })
+            .collect();
+        Self {
+            chains: Mutex::new(chains),
+        }
+    }
+
+    /// Returns the breaker settings for `chain`, if it is tracked.
+    #[must_use]
+    pub fn settings(&self, chain: &ChainId) -> Option<CircuitBreakerSettings> {
+        self.lock().get(chain).map(|breaker| breaker.settings)
+    }
+
+    /// Check whether a call to `chain` may proceed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the remaining cool-down if the chain's breaker is open.
+    pub fn acquire(&self, chain: &ChainId) -> Result<(), Duration> {
+        self.acquire_at(chain, Instant::now())
+    }
+
+    fn acquire_at(&self, chain: &ChainId, now: Instant) -> Result<(), Duration> {
+        self.lock()
+            .get_mut(chain)
+            .map_or(Ok(()), |breaker| breaker.acquire(now))
+    }
+
+    /// Record the outcome of a call to `chain`.
+    fn record(&self, chain: &ChainId, failed: bool) -> Option<Transition> {
+        self.record_at(chain, failed, Instant::now())
+    }
+
+    fn record_at(&self, chain: &ChainId, failed: bool, now: Instant) -> Option<Transition> {
+        let mut chains = self.lock();
+        let breaker = chains.get_mut(chain)?;
+        if failed {
+            breaker.record_failure(now)
+        } else {
+            breaker.record_success()
+        }
+    }
+
+    /// Returns the chains whose breaker is currently open, sorted by chain ID.
+    #[must_use]
+    pub fn open_chains(&self) -> Vec<ChainId> {
+        let mut open: Vec<ChainId> = self
+            .lock()
+            .iter()
+            .filter(|(_, breaker)| breaker.open_until.is_some())
+            .map(|(chain, _)| chain.clone())
+            .collect();
+        open.sort_by_key(ToString::to_string);
+        open
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<ChainId, Breaker>> {
+        self.chains.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// [`Facilitator`] decorator enforcing [`CircuitBreakers`] on verify and settle.
+pub struct CircuitBreakerFacilitator<F> {
+    inner: F,
+    breakers: Arc<CircuitBreakers>,
+    observers: Vec<CircuitObserver>,
+}
+
+impl<F: fmt::Debug> fmt::Debug for CircuitBreakerFacilitator<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreakerFacilitator")
+            .field("inner", &self.inner)
+            .field("breakers", &self.breakers)
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
+
+impl<F> CircuitBreakerFacilitator<F> {
+    /// Wrap `inner` with the given breakers.
+    #[must_use]
+    pub const fn new(inner: F, breakers: Arc<CircuitBreakers>) -> Self {
+        Self {
+            inner,
+            breakers,
+            observers: Vec::new(),
+        }
+    }
+
+    /// Also report every breaker event to `observer`.
+    #[cfg(feature = "telemetry")]
+    #[must_use]
+    pub fn with_observer(mut self, observer: CircuitObserver) -> Self {
+        self.observers.push(observer);
+        self
+    }
+}
+
+/// Run `call` for `chain` through its breaker in `breakers`, reporting
+/// events to `observers`.
+///
+/// A call exceeding the chain's call timeout counts as a failure and is
+/// answered by `on_timeout`, as does an error after [`mark_rpc_unavailable`].
+/// Returns `Err(remaining)` without running `call` if the breaker is open.
+async fn guarded<T, Fut>(
+    breakers: &CircuitBreakers,
+    observers: &[CircuitObserver],
+    chain: Option<ChainId>,
+    call: Fut,
+    on_timeout: impl FnOnce(&ChainId, Duration) -> Result<T, FacilitatorError> + Send,
+) -> Result<Result<T, FacilitatorError>, Duration>
+where
+    Fut: Future<Output = Result<T, FacilitatorError>> + Send,
+{
+    let Some((chain, settings)) =
+        chain.and_then(|chain| breakers.settings(&chain).map(|s| (chain, s)))
+    else {
+        return Ok(call.await);
+    };
+
+    let notify = |event| {
+        for observer in observers {
+            observer(&chain, event);
+        }
+    };
+
+    breakers.acquire(&chain).inspect_err(|_| {
+        tracing::debug!(chain = %chain, "Circuit open, rejecting call");
+        notify(CircuitEvent::Rejected);
+    })?;
+
+    let (result, rpc_failed) = RPC_FAILED
+        .scope(Cell::new(false), async {
+            let result = tokio::time::timeout(settings.call_timeout, call).await;
+            (result, RPC_FAILED.with(Cell::get))
+        })
+        .await;
+    let (result, failed) = result.map_or_else(
+        |_| (on_timeout(&chain, settings.call_timeout), true),
+        |result| {
+            let failed = rpc_failed && result.is_err();
+            (result, failed)
+        },
+    );
+
+    match breakers.record(&chain, failed) {
+        Some(Transition::Opened) => {
+            tracing::warn!(
+                chain = %chain,
+                cooldown_secs = settings.cooldown.as_secs(),
+                "Circuit opened after consecutive RPC failures"
+            );
+            notify(CircuitEvent::Opened);
+        }
+        Some(Transition::Closed) => {
+            tracing::info!(chain = %chain, "Circuit closed, RPC recovered");
+            notify(CircuitEvent::Closed);
+        }
+        None => {}
+    }
+
+    Ok(result)
+}
+
+/// Human-readable message for a call rejected by an open breaker.
+fn circuit_open_message(remaining: Duration) -> String {
+    format!(
+        "chain RPC is failing, retry in {}s",
+        remaining.as_secs().max(1)
+    )
+}
+
+impl<F> Facilitator for CircuitBreakerFacilitator<F>
+where
+    F: Facilitator,
+{
+    fn verify(
+        &self,
+        request: proto::VerifyRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::VerifyResponse, FacilitatorError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let chain = request.scheme_slug().map(|slug| slug.chain_id);
+            guarded(
+                &self.breakers,
+                &self.observers,
+                chain,
+                self.inner.verify(request),
+                |chain, timeout| {
+                    Err(FacilitatorError::OnchainFailure(format!(
+                        "{chain} call timed out after {}s",
+                        timeout.as_secs()
+                    )))
+                },
+            )
+            .await
+            .unwrap_or_else(|remaining| {
+                Ok(proto::VerifyResponse::Invalid {
+                    reason: CIRCUIT_OPEN.to_owned(),
+                    message: Some(circuit_open_message(remaining)),
+                    payer: None,
+                })
+            })
+        })
+    }
+
+    fn settle(
+        &self,
+        request: proto::SettleRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::SettleResponse, FacilitatorError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let network = request.network().to_owned();
+            let chain = request.scheme_slug().map(|slug| slug.chain_id);
+            let unknown_network = network.clone();
+            guarded(
+                &self.breakers,
+                &self.observers,
+                chain,
+                self.inner.settle(request),
+                move |chain, timeout| {
+                    tracing::warn!(
+                        chain = %chain,
+                        timeout_secs = timeout.as_secs(),
+                        "Settlement timed out, outcome unknown"
+                    );
+                    Ok(proto::SettleResponse::Error {
+                        reason: SETTLEMENT_OUTCOME_UNKNOWN.to_owned(),
+                        message: Some(format!(
+                            "settlement did not complete within {}s; the transaction may still be confirmed",
+                            timeout.as_secs()
+                        )),
+                        payer: None,
+                        network: unknown_network,
+                    })
+                },
+            )
+            .await
+            .unwrap_or_else(|remaining| {
+                Ok(proto::SettleResponse::Error {
+                    reason: CIRCUIT_OPEN.to_owned(),
+                    message: Some(circuit_open_message(remaining)),
+                    payer: None,
+                    network,
+                })
+            })
+        })
+    }
+
+    fn supported(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::SupportedResponse, FacilitatorError>> + Send + '_>>
+    {
+        self.inner.supported()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: CircuitBreakerSettings = CircuitBreakerSettings {
+        failure_threshold: 3,
+        cooldown: Duration::from_secs(30),
+        call_timeout: Duration::from_secs(10),
+    };
+
+    fn breakers() -> (CircuitBreakers, ChainId) {
+        let chain = ChainId::new("eip155", "84532");
+        let breakers = CircuitBreakers {
+            chains: Mutex::new(HashMap::from([(chain.clone(), Breaker::new(SETTINGS))])),
+        };
+        (breakers, chain)
+    }
+
+    #[test]
+    fn untracked_chain_always_passes() {
+        let breakers = CircuitBreakers::default();
+        let chain = ChainId::new("eip155", "1");
+        assert!(breakers.acquire(&chain).is_ok());
+        assert!(breakers.settings(&chain).is_none());
+        assert!(breakers.open_chains().is_empty());
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let (breakers, chain) = breakers();
+        let now = Instant::now();
+        assert_eq!(breakers.record_at(&chain, true, now), None);
+        assert_eq!(breakers.record_at(&chain, true, now), None);
+        assert_eq!(
+            breakers.record_at(&chain, true, now),
+            Some(Transition::Opened)
+        );
+        assert_eq!(breakers.acquire_at(&chain, now), Err(SETTINGS.cooldown));
+        assert_eq!(breakers.open_chains(), vec![chain]);
+    }
+
+    #[test]
+    fn success_resets_failure_count() {
+        let (breakers, chain) = breakers();
+        let now = Instant::now();
+        breakers.record_at(&chain, true, now);
+        breakers.record_at(&chain, true, now);
+        assert_eq!(breakers.record_at(&chain, false, now), None);
+        assert_eq!(breakers.record_at(&chain, true, now), None);
+        assert!(breakers.acquire_at(&chain, now).is_ok());
+    }
+
+    #[test]
+    fn half_open_admits_single_probe() {
+        let (breakers, chain) = breakers();
+        let now = Instant::now();
+        for _ in 0..SETTINGS.failure_threshold {
+            breakers.record_at(&chain, true, now);
+        }
+        let later = now + SETTINGS.cooldown;
+        assert!(breakers.acquire_at(&chain, later).is_ok());
+        assert_eq!(breakers.acquire_at(&chain, later), Err(Duration::ZERO));
+        // A probe that never reports back is superseded after the call timeout.
+        let stale = later + SETTINGS.call_timeout;
+        assert!(breakers.acquire_at(&chain, stale).is_ok());
+    }
+
+    #[test]
+    fn probe_outcome_closes_or_reopens() {
+        let (breakers, chain) = breakers();
+        let now = Instant::now();
+        for _ in 0..SETTINGS.failure_threshold {
+            breakers.record_at(&chain, true, now);
+        }
+        let later = now + SETTINGS.cooldown;
+        assert!(breakers.acquire_at(&chain, later).is_ok());
+        assert_eq!(breakers.record_at(&chain, true, later), None);
+        assert_eq!(breakers.acquire_at(&chain, later), Err(SETTINGS.cooldown));
+
+        let recovered = later + SETTINGS.cooldown;
+        assert!(breakers.acquire_at(&chain, recovered).is_ok());
+        assert_eq!(
+            breakers.record_at(&chain, false, recovered),
+            Some(Transition::Closed)
+        );
+        assert!(breakers.open_chains().is_empty());
+    }
+
+    fn tracked(chain: &ChainId, settings: CircuitBreakerSettings) -> CircuitBreakers {
+        CircuitBreakers {
+            chains: Mutex::new(HashMap::from([(chain.clone(), Breaker::new(settings))])),
+        }
+    }
+
+    /// Observer collecting the events it receives.
+    fn recorder() -> (CircuitObserver, Arc<Mutex<Vec<CircuitEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        let observer: CircuitObserver = Arc::new(move |_, event| {
+            sink.lock().expect("events lock").push(event);
+        });
+        (observer, events)
+    }
+
+    #[tokio::test]
+    async fn only_marked_errors_count() {
+        let chain = ChainId::new("eip155", "84532");
+        let settings = CircuitBreakerSettings {
+            failure_threshold: 1,
+            ..SETTINGS
+        };
+        let breakers = tracked(&chain, settings);
+        let (observer, events) = recorder();
+        let observers = [observer];
+
+        // Errors are answers from the chain unless a provider marked them,
+        // whatever they say.
+        let rejected = async {
+            Err::<(), _>(FacilitatorError::OnchainFailure(format!(
+                "{RPC_UNAVAILABLE}: reverted"
+            )))
+        };
+        let result = guarded(
+            &breakers,
+            &observers,
+            Some(chain.clone()),
+            rejected,
+            |_, _| Ok(()),
+        )
+        .await;
+        assert!(matches!(result, Ok(Err(_))));
+        assert!(breakers.open_chains().is_empty());
+
+        // A marked call that still succeeds (e.g. after a retry) is fine.
+        let recovered = async {
+            mark_rpc_unavailable();
+            Ok(())
+        };
+        let result = guarded(
+            &breakers,
+            &observers,
+            Some(chain.clone()),
+            recovered,
+            |_, _| Ok(()),
+        )
+        .await;
+        assert!(matches!(result, Ok(Ok(()))));
+        assert!(breakers.open_chains().is_empty());
+
+        let unavailable = async {
+            mark_rpc_unavailable();
+            Err::<(), _>(FacilitatorError::Other("no route".into()))
+        };
+        let result = guarded(
+            &breakers,
+            &observers,
+            Some(chain.clone()),
+            unavailable,
+            |_, _| Ok(()),
+        )
+        .await;
+        assert!(matches!(result, Ok(Err(_))));
+        assert_eq!(breakers.open_chains(), vec![chain.clone()]);
+
+        let result = guarded(
+            &breakers,
+            &observers,
+            Some(chain),
+            async { Ok(()) },
+            |_, _| Ok(()),
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(
+            *events.lock().unwrap(),
+            [CircuitEvent::Opened, CircuitEvent::Rejected]
+        );
+    }
+
+    #[cfg(feature = "chain-eip155")]
+    #[tokio::test]
+    async fn unreachable_rpc_opens_the_breaker() {
+        use alloy_provider::{Provider, ProviderBuilder};
+
+        let chain = ChainId::new("eip155", "84532");
+        let breakers = tracked(
+            &chain,
+            CircuitBreakerSettings {
+                failure_threshold: 2,
+                ..SETTINGS
+            },
+        );
+        // Nothing listens on port 9.
+        let endpoint = crate::chain::Eip155RpcEndpoint {
+            http: "http://127.0.0.1:9/".to_owned(),
+            rate_limit: None,
+            headers: crate::chain::RpcHeaders::default(),
+            auth: None,
+        };
+        let client = crate::chain::rpc_client(&chain, &[endpoint]).expect("valid endpoint");
+        let provider = ProviderBuilder::new().connect_client(client);
+        for _ in 0..2 {
+            let call = async {
+                provider
+                    .get_block_number()
+                    .await
+                    .map_err(|e| FacilitatorError::OnchainFailure(e.to_string()))
+            };
+            let result = guarded(&breakers, &[], Some(chain.clone()), call, |_, _| Ok(0)).await;
+            assert!(matches!(result, Ok(Err(_))));
+        }
+        assert_eq!(breakers.open_chains(), vec![chain]);
+    }
+
+    #[tokio::test]
+    async fn timeouts_count_as_failures() {
+        let chain = ChainId::new("eip155", "84532");
+        let settings = CircuitBreakerSettings {
+            failure_threshold: 1,
+            call_timeout: Duration::from_millis(10),
+            ..SETTINGS
+        };
+        let breakers = tracked(&chain, settings);
+        let result = guarded(
+            &breakers,
+            &[],
+            Some(chain.clone()),
+            std::future::pending::<Result<&str, FacilitatorError>>(),
+            |_, _| Ok(SETTLEMENT_OUTCOME_UNKNOWN),
+        )
+        .await;
+        assert!(matches!(result, Ok(Ok(SETTLEMENT_OUTCOME_UNKNOWN))));
+        assert_eq!(breakers.open_chains(), vec![chain]);
+    }
+}
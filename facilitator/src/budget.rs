@@ -0,0 +1,799 @@
+//! Rolling 24-hour gas spend budgets per chain and per signer.
+//!
+//! - [`GasBudgets`] — tracks the native gas spent by settlements and decides
+//!   whether a chain still has budget left.
+//! - [`GasBudgetFacilitator`] — [`Facilitator`] decorator that refuses
+//!   `/settle` requests for exhausted chains, records the fee of every
+//!   successful settlement, and hides exhausted chains from `/supported`.
+//!
+//! Budgets are configured per chain with `daily_gas_budget` and
+//! `daily_signer_gas_budget`, in the chain's smallest native unit (wei for
+//! EVM chains, lamports for Solana).  Signers are picked round-robin inside
+//! the chain provider, so once any signer of a chain exhausts its budget the
+//! whole chain stops settling until older spend leaves the window.
+//!
+//! Every settlement reserves an estimated fee before it is submitted, so
+//! concurrent settlements see each other's spend: the estimate is the
+//! chain's `settlement_fee_estimate`, or the highest fee seen so far if that
+//! is larger.  Once the receipt is read the reservation is replaced by the
+//! real fee.  If the fee cannot be read, or the settlement failed after it
+//! may have been submitted, the estimate stays recorded as spend.
+//!
+//! The fee of a successful settlement is read once and also handed to every
+//! [`FeeObserver`] registered on the [`GasBudgetFacilitator`], so other layers
+//! (e.g. metrics) never query the chain for it again.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use r402::chain::ChainId;
+use r402::facilitator::{Facilitator, FacilitatorError};
+use r402::proto;
+use schemars::{JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+use crate::breaker::SETTLEMENT_OUTCOME_UNKNOWN;
+use crate::chain::{ChainProvider, ChainsConfig, LiveChains, SettlementFee};
+
+/// Length of the rolling budget window.
+const BUDGET_WINDOW: Duration = Duration::from_hours(24);
+
+/// Number of attempts made to read the fee of a settled transaction.
+const FEE_LOOKUP_ATTEMPTS: u32 = 5;
+
+/// Delay between fee lookup attempts.
+const FEE_LOOKUP_DELAY: Duration = Duration::from_secs(2);
+
+/// Machine-readable reason returned when a settlement is refused.
+pub const GAS_BUDGET_EXHAUSTED: &str = "gas_budget_exhausted";
+
+/// Callback receiving the fee of every successful settlement whose fee could
+/// be read.
+pub type FeeObserver = Arc<dyn Fn(&ChainId, &SettlementFee) + Send + Sync>;
+
+/// An amount of the native gas token in the chain's smallest unit.
+///
+/// Written as a TOML integer, or as a string of digits for amounts beyond the
+/// signed 64-bit range of TOML integers (about 9.2 ETH in wei).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GasAmount(pub u128);
+
+impl Serialize for GasAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match i64::try_from(self.0) {
+            Ok(amount) => serializer.serialize_i64(amount),
+            Err(_) => serializer.serialize_str(&self.0.to_string()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GasAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl de::Visitor<'_> for Visitor {
+            type Value = GasAmount;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a non-negative integer or a string of digits")
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<GasAmount, E> {
+                u128::try_from(value)
+                    .map(GasAmount)
+                    .map_err(|_| E::custom(format!("gas amount must not be negative, got {value}")))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<GasAmount, E> {
+                Ok(GasAmount(value.into()))
+            }
+
+            fn visit_u128<E: de::Error>(self, value: u128) -> Result<GasAmount, E> {
+                Ok(GasAmount(value))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<GasAmount, E> {
+                value
+                    .replace('_', "")
+                    .parse()
+                    .map(GasAmount)
+                    .map_err(|_| E::custom(format!("invalid gas amount '{value}'")))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+impl JsonSchema for GasAmount {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "GasAmount".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        schemars::json_schema!({
+            "description": "Amount in the chain's smallest native unit, as an integer or a string of digits.",
+            "anyOf": [
+                { "type": "integer", "minimum": 0 },
+                { "type": "string", "pattern": "^[0-9][0-9_]*$" },
+            ],
+        })
+    }
+}
+
+/// Gas budget limits for a single chain, in the chain's smallest native unit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasBudgetLimits {
+    /// Maximum spend across all signers within the window.
+    pub chain: Option<u128>,
+    /// Maximum spend per signer address within the window.
+    pub signer: Option<u128>,
+    /// Fee reserved for a settlement until its real fee is known.
+    pub fee_estimate: Option<u128>,
+}
+
+impl GasBudgetLimits {
+    /// Returns `true` if neither limit is configured.
+    #[must_use]
+    pub const fn is_unlimited(&self) -> bool {
+        self.chain.is_none() && self.signer.is_none()
+    }
+}
+
+/// Spend entries that fall within the rolling window.
+#[derive(Debug, Default)]
+struct SpendWindow {
+    entries: VecDeque<(Instant, u128)>,
+    total: u128,
+}
+
+impl SpendWindow {
+    fn record(&mut self, at: Instant, amount: u128) {
+        self.entries.push_back((at, amount));
+        self.total = self.total.saturating_add(amount);
+    }
+
+    /// Drop entries older than the window and return the remaining total.
+    fn total(&mut self, now: Instant) -> u128 {
+        while let Some(&(at, amount)) = self.entries.front() {
+            if now.saturating_duration_since(at) < BUDGET_WINDOW {
+                break;
+            }
+            self.entries.pop_front();
+            self.total = self.total.saturating_sub(amount);
+        }
+        self.total
+    }
+}
+
+/// Limits, recorded spend and pending reservations for a single chain.
+#[derive(Debug)]
+struct ChainBudget {
+    limits: GasBudgetLimits,
+    spent: SpendWindow,
+    signers: HashMap<String, SpendWindow>,
+    /// Estimated fees of settlements still in flight.
+    reserved: u128,
+    /// Highest settlement fee recorded so far.
+    highest_fee: u128,
+}
+
+impl ChainBudget {
+    fn new(limits: GasBudgetLimits) -> Self {
+        Self {
+            limits,
+            spent: SpendWindow::default(),
+            signers: HashMap::new(),
+            reserved: 0,
+            highest_fee: 0,
+        }
+    }
+
+    /// Returns a human-readable reason if any limit has been reached.
+    ///
+    /// Pending reservations count against the chain and, since the signer of
+    /// an in-flight settlement is not known yet, against every signer.
+    /// Signers with no spend left in the window are forgotten.
+    fn exhausted(&mut self, now: Instant) -> Option<String> {
+        self.signers.retain(|_, window| {
+            window.total(now);
+            !window.entries.is_empty()
+        });
+        let reserved = self.reserved;
+        let pending = |spent: u128| {
+            if reserved == 0 {
+                format!("{spent}")
+            } else {
+                format!("{spent} (plus {reserved} reserved)")
+            }
+        };
+        if let Some(limit) = self.limits.chain {
+            let spent = self.spent.total(now);
+            if spent.saturating_add(reserved) >= limit {
+                return Some(format!(
+                    "chain spent {} of its {limit} daily gas budget",
+                    pending(spent)
+                ));
+            }
+        }
+        if let Some(limit) = self.limits.signer {
+            for (signer, window) in &mut self.signers {
+                let spent = window.total(now);
+                if spent.saturating_add(reserved) >= limit {
+                    return Some(format!(
+                        "signer {signer} spent {} of its {limit} daily gas budget",
+                        pending(spent)
+                    ));
+                }
+            }
+            if reserved >= limit {
+                return Some(format!(
+                    "{reserved} reserved by pending settlements exceeds the {limit} signer budget"
+                ));
+            }
+        }
+        None
+    }
+
+    /// Fee reserved for the next settlement.
+    fn fee_estimate(&self) -> u128 {
+        self.limits
+            .fee_estimate
+            .unwrap_or_default()
+            .max(self.highest_fee)
+    }
+
+    fn record(&mut self, signer: String, amount: u128, at: Instant) {
+        self.spent.record(at, amount);
+        self.signers.entry(signer).or_default().record(at, amount);
+        self.highest_fee = self.highest_fee.max(amount);
+    }
+}
+
+/// How an in-flight settlement ended, for settling its [`Reservation`].
+#[derive(Debug)]
+enum Spend {
+    /// Nothing was submitted.
+    None,
+    /// The fee is known.
+    Fee(SettlementFee),
+    /// Something may have been spent, but the fee is not known.
+    Unknown,
+}
+
+/// An estimated fee held against a chain's budget while a settlement is in
+/// flight.
+///
+/// Dropping a reservation that was not [`release`](Self::release)d or
+/// [`settle`](Self::settle)d keeps the estimate recorded as spend, since the
+/// settlement may have been submitted before it was abandoned.
+#[derive(Debug)]
+pub struct Reservation {
+    budgets: Arc<GasBudgets>,
+    chain: ChainId,
+    amount: u128,
+    done: bool,
+}
+
+impl Reservation {
+    /// Give the reservation back: the settlement was never submitted.
+    pub fn release(mut self) {
+        self.finish(Spend::None);
+    }
+
+    /// Replace the reservation with the real `fee`, or keep the estimate as
+    /// spend if the fee is not known.
+    pub fn settle(mut self, fee: Option<SettlementFee>) {
+        self.finish(fee.map_or(Spend::Unknown, Spend::Fee));
+    }
+
+    fn finish(&mut self, spend: Spend) {
+        if std::mem::replace(&mut self.done, true) {
+            return;
+        }
+        self.budgets
+            .finish_at(&self.chain, self.amount, spend, Instant::now());
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.finish(Spend::Unknown);
+    }
+}
+
+/// Tracks settlement gas spend against per-chain budgets.
+///
+/// Only chains with at least one configured limit are tracked; every other
+/// chain always passes [`GasBudgets::check`].
+#[derive(Debug, Default)]
+pub struct GasBudgets {
+    chains: Mutex<HashMap<ChainId, ChainBudget>>,
+}
+
+impl GasBudgets {
+    /// Build budgets for every chain that configures at least one limit.
+    #[must_use]
+    pub fn from_config(chains: &ChainsConfig) -> Self {
+        let chains = chains
+            .iter()
+            .filter_map(|chain| {
+                let limits = chain.gas_budget();
+                (!limits.is_unlimited()).then(|| (chain.chain_id(), ChainBudget::new(limits)))
+            })
+            .collect();
+        Self {
+            chains: Mutex::new(chains),
+        }
+    }
+
+    #[cfg(test)]
+    fn check_at(&self, chain: &ChainId, now: Instant) -> Result<(), String> {
+        self.lock()
+            .get_mut(chain)
+            .and_then(|budget| budget.exhausted(now))
+            .map_or(Ok(()), Err)
+    }
+
+    /// Reserve the estimated fee of a settlement on `chain`, if it may still
+    /// settle.
+    ///
+    /// The check and the reservation happen under one lock, so concurrent
+    /// settlements cannot all pass before any of them is accounted for.
+    /// Returns `Ok(None)` for chains without a budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns a human-readable reason if the chain or one of its signers has
+    /// exhausted its budget.
+    pub fn reserve(self: &Arc<Self>, chain: &ChainId) -> Result<Option<Reservation>, String> {
+        self.reserve_at(chain, Instant::now())
+    }
+
+    fn reserve_at(
+        self: &Arc<Self>,
+        chain: &ChainId,
+        now: Instant,
+    ) -> Result<Option<Reservation>, String> {
+        let mut chains = self.lock();
+        let Some(budget) = chains.get_mut(chain) else {
+            return Ok(None);
+        };
+        if let Some(reason) = budget.exhausted(now) {
+            return Err(reason);
+        }
+        let amount = budget.fee_estimate();
+        budget.reserved = budget.reserved.saturating_add(amount);
+        Ok(Some(Reservation {
+            budgets: Arc::clone(self),
+            chain: chain.clone(),
+            amount,
+            done: false,
+        }))
+    }
+
+    /// Drop a reservation of `reserved` on `chain` and record what was spent.
+    fn finish_at(&self, chain: &ChainId, reserved: u128, spend: Spend, at: Instant) {
+        let mut chains = self.lock();
+        let Some(budget) = chains.get_mut(chain) else {
+            return;
+        };
+        budget.reserved = budget.reserved.saturating_sub(reserved);
+        match spend {
+            Spend::None => {}
+            Spend::Fee(fee) => budget.record(fee.signer, fee.amount, at),
+            Spend::Unknown => budget.spent.record(at, reserved),
+        }
+    }
+
+    #[cfg(test)]
+    fn record_at(&self, chain: &ChainId, signer: String, amount: u128, at: Instant) {
+        if let Some(budget) = self.lock().get_mut(chain) {
+            budget.record(signer, amount, at);
+        }
+    }
+
+    /// Returns the chains that currently have no budget left.
+    #[must_use]
+    pub fn exhausted_chains(&self) -> Vec<ChainId> {
+        let now = Instant::now();
+        self.lock()
+            .iter_mut()
+            .filter_map(|(chain, budget)| budget.exhausted(now).map(|_| chain.clone()))
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<ChainId, ChainBudget>> {
+        self.chains.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// [`Facilitator`] decorator enforcing [`GasBudgets`] on settlement.
+pub struct GasBudgetFacilitator<F> {
+    inner: F,
+    budgets: Arc<GasBudgets>,
+    chains: Arc<LiveChains>,
+    fee_observers: Vec<FeeObserver>,
+}
+
+impl<F: fmt::Debug> fmt::Debug for GasBudgetFacilitator<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GasBudgetFacilitator")
+            .field("inner", &self.inner)
+            .field("budgets", &self.budgets)
+            .field("chains", &self.chains)
+            .field("fee_observers", &self.fee_observers.len())
+            .finish()
+    }
+}
+
+impl<F> GasBudgetFacilitator<F> {
+    /// Wrap `inner`, using providers from `chains` to read settlement fees.
+    #[must_use]
+    pub const fn new(inner: F, budgets: Arc<GasBudgets>, chains: Arc<LiveChains>) -> Self {
+        Self {
+            inner,
+            budgets,
+            chains,
+            fee_observers: Vec::new(),
+        }
+    }
+
+    /// Also hand the fee of every successful settlement to `observer`.
+    ///
+    /// Fees are then read for every chain, not only those with a budget.
+    #[cfg(feature = "telemetry")]
+    #[must_use]
+    pub fn with_fee_observer(mut self, observer: FeeObserver) -> Self {
+        self.fee_observers.push(observer);
+        self
+    }
+}
+
+impl<F> Facilitator for GasBudgetFacilitator<F>
+where
+    F: Facilitator,
+{
+    fn verify(
+        &self,
+        request: proto::VerifyRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::VerifyResponse, FacilitatorError>> + Send + '_>>
+    {
+        self.inner.verify(request)
+    }
+
+    fn settle(
+        &self,
+        request: proto::SettleRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::SettleResponse, FacilitatorError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let network = request.network().to_owned();
+            let chain_id = ChainId::from_str(&network).ok();
+
+            let reservation = match chain_id.as_ref().map(|id| self.budgets.reserve(id)) {
+                Some(Err(reason)) => {
+                    tracing::warn!(%network, %reason, "Gas budget exhausted, refusing settlement");
+                    return Ok(proto::SettleResponse::Error {
+                        reason: GAS_BUDGET_EXHAUSTED.to_owned(),
+                        message: Some(reason),
+                        payer: None,
+                        network,
+                    });
+                }
+                Some(Ok(reservation)) => reservation,
+                None => None,
+            };
+
+            let result = self.inner.settle(request).await;
+
+            if let Ok(proto::SettleResponse::Success { transaction, .. }) = &result
+                && let Some(chain_id) = chain_id
+                && (reservation.is_some() || !self.fee_observers.is_empty())
+            {
+                let provider = self.chains.provider(&chain_id);
+                tokio::spawn(record_settlement_fee(
+                    chain_id,
+                    reservation,
+                    provider,
+                    transaction.clone(),
+                    self.fee_observers.clone(),
+                ));
+            } else if let Some(reservation) = reservation {
+                match &result {
+                    // The transaction may have been submitted; dropping the
+                    // reservation keeps the estimate as spend.
+                    Ok(proto::SettleResponse::Error { reason, .. })
+                        if reason == SETTLEMENT_OUTCOME_UNKNOWN =>
+                    {
+                        drop(reservation);
+                    }
+                    // Refused before anything was submitted.
+                    Ok(_)
+                    | Err(
+                        FacilitatorError::PaymentVerification(_) | FacilitatorError::Aborted { .. },
+                    ) => reservation.release(),
+                    // The transaction may have been submitted; dropping the
+                    // reservation keeps the estimate as spend.
+                    Err(_) => drop(reservation),
+                }
+            }
+
+            result
+        })
+    }
+
+    fn supported(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::SupportedResponse, FacilitatorError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let mut supported = self.inner.supported().await?;
+            let exhausted: Vec<String> = self
+                .budgets
+                .exhausted_chains()
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            if !exhausted.is_empty() {
+                supported
+                    .kinds
+                    .retain(|kind| !exhausted.contains(&kind.network));
+            }
+            Ok(supported)
+        })
+    }
+}
+
+/// Read the fee of a settled transaction once, settle `reservation` with it
+/// and hand it to `observers`.
+async fn record_settlement_fee(
+    chain: ChainId,
+    reservation: Option<Reservation>,
+    provider: Option<ChainProvider>,
+    transaction: String,
+    observers: Vec<FeeObserver>,
+) {
+    let fee = match &provider {
+        Some(provider) => lookup_settlement_fee(provider, &transaction).await,
+        None => None,
+    };
+    if let Some(fee) = &fee {
+        for observer in &observers {
+            observer(&chain, fee);
+        }
+    }
+    let Some(reservation) = reservation else {
+        return;
+    };
+    if let Some(fee) = &fee {
+        tracing::debug!(
+            chain = %chain,
+            signer = %fee.signer,
+            amount = %fee.amount,
+            transaction,
+            "Recorded settlement gas spend"
+        );
+    } else {
+        tracing::warn!(
+            chain = %chain,
+            estimate = %reservation.amount,
+            transaction,
+            "Could not read settlement fee, recording the estimated fee instead"
+        );
+    }
+    reservation.settle(fee);
+}
+
+/// Look up the fee of a settled transaction.
+///
+/// Receipts may lag behind the settlement response, so the lookup is retried
+/// a few times before giving up.
+async fn lookup_settlement_fee(
+    provider: &ChainProvider,
+    transaction: &str,
+) -> Option<SettlementFee> {
+    for _ in 0..FEE_LOOKUP_ATTEMPTS {
+        match provider.settlement_fee(transaction).await {
+            Ok(Some(fee)) => return Some(fee),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::debug!(transaction, error = %e, "Settlement fee lookup failed");
+            }
+        }
+        tokio::time::sleep(FEE_LOOKUP_DELAY).await;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budgets(limits: GasBudgetLimits) -> (Arc<GasBudgets>, ChainId) {
+        let chain = ChainId::new("eip155", "84532");
+        let budgets = GasBudgets {
+            chains: Mutex::new(HashMap::from([(chain.clone(), ChainBudget::new(limits))])),
+        };
+        (Arc::new(budgets), chain)
+    }
+
+    fn fee(signer: &str, amount: u128) -> SettlementFee {
+        SettlementFee {
+            signer: signer.to_owned(),
+            amount,
+            gas_used: 0,
+        }
+    }
+
+    #[test]
+    fn unlimited_when_no_limits() {
+        assert!(GasBudgetLimits::default().is_unlimited());
+        assert!(
+            !GasBudgetLimits {
+                chain: Some(1),
+                ..GasBudgetLimits::default()
+            }
+            .is_unlimited()
+        );
+    }
+
+    #[test]
+    fn untracked_chain_always_passes() {
+        let budgets = Arc::new(GasBudgets::default());
+        assert!(
+            budgets
+                .reserve(&ChainId::new("eip155", "1"))
+                .unwrap()
+                .is_none()
+        );
+        assert!(budgets.exhausted_chains().is_empty());
+    }
+
+    #[test]
+    fn chain_budget_exhausts() {
+        let (budgets, chain) = budgets(GasBudgetLimits {
+            chain: Some(100),
+            ..GasBudgetLimits::default()
+        });
+        let now = Instant::now();
+        budgets.record_at(&chain, "0xa".into(), 60, now);
+        assert!(budgets.check_at(&chain, now).is_ok());
+        budgets.record_at(&chain, "0xb".into(), 40, now);
+        let reason = budgets.check_at(&chain, now).unwrap_err();
+        assert!(reason.contains("chain spent 100"));
+        assert_eq!(budgets.exhausted_chains(), vec![chain]);
+    }
+
+    #[test]
+    fn signer_budget_exhausts() {
+        let (budgets, chain) = budgets(GasBudgetLimits {
+            signer: Some(50),
+            ..GasBudgetLimits::default()
+        });
+        let now = Instant::now();
+        budgets.record_at(&chain, "0xa".into(), 30, now);
+        budgets.record_at(&chain, "0xb".into(), 30, now);
+        assert!(budgets.check_at(&chain, now).is_ok());
+        budgets.record_at(&chain, "0xa".into(), 20, now);
+        let reason = budgets.check_at(&chain, now).unwrap_err();
+        assert!(reason.contains("signer 0xa"));
+    }
+
+    #[test]
+    fn spend_leaves_window() {
+        let (budgets, chain) = budgets(GasBudgetLimits {
+            chain: Some(100),
+            signer: Some(100),
+            fee_estimate: None,
+        });
+        let start = Instant::now();
+        budgets.record_at(&chain, "0xa".into(), 100, start);
+        assert!(budgets.check_at(&chain, start).is_err());
+        let later = start + BUDGET_WINDOW;
+        assert!(budgets.check_at(&chain, later).is_ok());
+    }
+
+    #[test]
+    fn idle_signers_are_forgotten() {
+        let (budgets, chain) = budgets(GasBudgetLimits {
+            chain: Some(100),
+            ..GasBudgetLimits::default()
+        });
+        let start = Instant::now();
+        budgets.record_at(&chain, "0xa".into(), 10, start);
+        budgets.record_at(&chain, "0xb".into(), 10, start + BUDGET_WINDOW / 2);
+        drop(budgets.reserve_at(&chain, start + BUDGET_WINDOW).unwrap());
+        let budget = &budgets.lock()[&chain];
+        assert_eq!(budget.signers.keys().collect::<Vec<_>>(), ["0xb"]);
+    }
+
+    #[test]
+    fn spend_window_total_saturates() {
+        let mut window = SpendWindow::default();
+        let now = Instant::now();
+        window.record(now, u128::MAX);
+        window.record(now, 1);
+        assert_eq!(window.total(now), u128::MAX);
+    }
+
+    #[test]
+    fn concurrent_settlements_reserve_the_estimate() {
+        let (budgets, chain) = budgets(GasBudgetLimits {
+            chain: Some(100),
+            fee_estimate: Some(40),
+            ..GasBudgetLimits::default()
+        });
+        let now = Instant::now();
+        let pending: Vec<_> = (0..3)
+            .map(|_| budgets.reserve_at(&chain, now).unwrap().unwrap())
+            .collect();
+        let reason = budgets.reserve_at(&chain, now).unwrap_err();
+        assert!(reason.contains("plus 120 reserved"));
+
+        // Released reservations free the budget again.
+        for reservation in pending {
+            reservation.release();
+        }
+        assert!(budgets.check_at(&chain, now).is_ok());
+    }
+
+    #[test]
+    fn reservation_is_replaced_by_the_real_fee() {
+        let (budgets, chain) = budgets(GasBudgetLimits {
+            chain: Some(100),
+            signer: Some(100),
+            fee_estimate: Some(10),
+        });
+        let reservation = budgets.reserve(&chain).unwrap().unwrap();
+        reservation.settle(Some(fee("0xa", 30)));
+        let budget = &budgets.lock()[&chain];
+        assert_eq!(budget.reserved, 0);
+        assert_eq!(budget.spent.total, 30);
+        assert_eq!(budget.signers["0xa"].total, 30);
+        // The highest fee seen becomes the new estimate.
+        assert_eq!(budget.fee_estimate(), 30);
+    }
+
+    #[test]
+    fn unknown_or_abandoned_spend_keeps_the_estimate() {
+        let (budgets, chain) = budgets(GasBudgetLimits {
+            chain: Some(100),
+            fee_estimate: Some(25),
+            ..GasBudgetLimits::default()
+        });
+        budgets.reserve(&chain).unwrap().unwrap().settle(None);
+        drop(budgets.reserve(&chain).unwrap());
+        let budget = &budgets.lock()[&chain];
+        assert_eq!(budget.reserved, 0);
+        assert_eq!(budget.spent.total, 50);
+    }
+
+    #[test]
+    fn gas_amounts_beyond_toml_integers_are_strings() {
+        #[derive(Debug, Deserialize, Serialize)]
+        struct Budget {
+            small: GasAmount,
+            large: GasAmount,
+        }
+        let budget: Budget = toml::from_str(
+            r#"
+            small = 50_000_000_000_000_000
+            large = "20_000_000_000_000_000_000"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(budget.small, GasAmount(50_000_000_000_000_000));
+        assert_eq!(budget.large, GasAmount(20_000_000_000_000_000_000));
+        assert_eq!(
+            toml::to_string(&budget).unwrap(),
+            "small = 50000000000000000\nlarge = \"20000000000000000000\"\n"
+        );
+        assert!(toml::from_str::<Budget>("small = -1\nlarge = 1").is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! Built-in catalog of well-known networks.
+//!
+//! Each [`Network`] maps a friendly name (`base`, `base-sepolia`,
+//! `solana-devnet`, …) to its CAIP-2 identifier, together with a public RPC
+//! endpoint and the gas settings that suit the chain.  Names follow the ones
+//! used by `r402-evm` and `r402-svm`.
+
+/// A well-known network and its default settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Network {
+    /// Friendly name, usable in place of the CAIP-2 identifier.
+    pub name: &'static str,
+    /// CAIP-2 chain identifier.
+    pub chain_id: &'static str,
+    /// Public RPC endpoint, fine for testing but rate-limited in production.
+    pub rpc: &'static str,
+    /// Whether the chain supports EIP-1559 gas pricing (EVM only).
+    pub eip1559: bool,
+    /// Whether the chain supports flashblocks (EVM only).
+    pub flashblocks: bool,
+}
+
+impl Network {
+    /// Returns the CAIP-2 namespace of this network.
+    #[must_use]
+    pub fn namespace(&self) -> &'static str {
+        self.chain_id
+            .split_once(':')
+            .map_or(self.chain_id, |(namespace, _)| namespace)
+    }
+}
+
+const fn evm(name: &'static str, chain_id: &'static str, rpc: &'static str) -> Network {
+    Network {
+        name,
+        chain_id,
+        rpc,
+        eip1559: true,
+        flashblocks: false,
+    }
+}
+
+const fn legacy_gas(network: Network) -> Network {
+    Network {
+        eip1559: false,
+        ..network
+    }
+}
+
+const fn with_flashblocks(network: Network) -> Network {
+    Network {
+        flashblocks: true,
+        ..network
+    }
+}
+
+const fn solana(name: &'static str, chain_id: &'static str, rpc: &'static str) -> Network {
+    Network {
+        name,
+        chain_id,
+        rpc,
+        eip1559: false,
+        flashblocks: false,
+    }
+}
+
+/// Every network in the catalog.
+pub static NETWORKS: &[Network] = &[
+    // EVM mainnets
+    evm(
+        "ethereum",
+        "eip155:1",
+        "https://ethereum-rpc.publicnode.com",
+    ),
+    with_flashblocks(evm("base", "eip155:8453", "https://mainnet.base.org")),
+    evm("optimism", "eip155:10", "https://mainnet.optimism.io"),
+    evm("arbitrum", "eip155:42161", "https://arb1.arbitrum.io/rpc"),
+    evm("polygon", "eip155:137", "https://polygon-rpc.com"),
+    evm(
+        "avalanche",
+        "eip155:43114",
+        "https://api.avax.network/ext/bc/C/rpc",
+    ),
+    evm("celo", "eip155:42220", "https://forno.celo.org"),
+    evm("sei", "eip155:1329", "https://evm-rpc.sei-apis.com"),
+    evm("sonic", "eip155:146", "https://rpc.soniclabs.com"),
+    evm("unichain", "eip155:130", "https://mainnet.unichain.org"),
+    evm(
+        "world-chain",
+        "eip155:480",
+        "https://worldchain-mainnet.g.alchemy.com/public",
+    ),
+    evm("zksync", "eip155:324", "https://mainnet.era.zksync.io"),
+    evm("linea", "eip155:59144", "https://rpc.linea.build"),
+    evm("ink", "eip155:57073", "https://rpc-gel.inkonchain.com"),
+    evm("hyperevm", "eip155:999", "https://api.hyperliquid.xyz/evm"),
+    evm("monad", "eip155:143", "https://rpc.monad.xyz"),
+    evm("plume", "eip155:98866", "https://rpc.plume.org"),
+    evm("codex", "eip155:81224", "https://rpc.codex.xyz"),
+    legacy_gas(evm("xdc", "eip155:50", "https://rpc.xinfin.network")),
+    evm(
+        "xrpl-evm",
+        "eip155:1440000",
+        "https://rpc-evm-sidechain.xrpl.org",
+    ),
+    evm(
+        "peaq",
+        "eip155:3338",
+        "https://peaq.api.onfinality.io/public",
+    ),
+    evm("iotex", "eip155:4689", "https://babel-api.mainnet.iotex.io"),
+    evm("megaeth", "eip155:4326", "https://rpc.megaeth.com"),
+    // EVM testnets
+    evm(
+        "ethereum-sepolia",
+        "eip155:11155111",
+        "https://ethereum-sepolia-rpc.publicnode.com",
+    ),
+    with_flashblocks(evm(
+        "base-sepolia",
+        "eip155:84532",
+        "https://sepolia.base.org",
+    )),
+    evm(
+        "optimism-sepolia",
+        "eip155:11155420",
+        "https://sepolia.optimism.io",
+    ),
+    evm(
+        "arbitrum-sepolia",
+        "eip155:421614",
+        "https://sepolia-rollup.arbitrum.io/rpc",
+    ),
+    evm(
+        "polygon-amoy",
+        "eip155:80002",
+        "https://rpc-amoy.polygon.technology",
+    ),
+    evm(
+        "avalanche-fuji",
+        "eip155:43113",
+        "https://api.avax-test.network/ext/bc/C/rpc",
+    ),
+    evm(
+        "celo-sepolia",
+        "eip155:11142220",
+        "https://forno.celo-sepolia.celo-testnet.org",
+    ),
+    evm(
+        "sei-testnet",
+        "eip155:1328",
+        "https://evm-rpc-testnet.sei-apis.com",
+    ),
+    evm(
+        "sonic-blaze",
+        "eip155:57054",
+        "https://rpc.blaze.soniclabs.com",
+    ),
+    evm(
+        "unichain-sepolia",
+        "eip155:1301",
+        "https://sepolia.unichain.org",
+    ),
+    evm(
+        "world-chain-sepolia",
+        "eip155:4801",
+        "https://worldchain-sepolia.g.alchemy.com/public",
+    ),
+    evm(
+        "zksync-sepolia",
+        "eip155:300",
+        "https://sepolia.era.zksync.dev",
+    ),
+    evm(
+        "linea-sepolia",
+        "eip155:59141",
+        "https://rpc.sepolia.linea.build",
+    ),
+    evm(
+        "ink-sepolia",
+        "eip155:763373",
+        "https://rpc-gel-sepolia.inkonchain.com",
+    ),
+    evm(
+        "hyperevm-testnet",
+        "eip155:998",
+        "https://api.hyperliquid-testnet.xyz/evm",
+    ),
+    evm(
+        "monad-testnet",
+        "eip155:10143",
+        "https://testnet-rpc.monad.xyz",
+    ),
+    evm(
+        "plume-testnet",
+        "eip155:98867",
+        "https://testnet-rpc.plume.org",
+    ),
+    evm(
+        "codex-testnet",
+        "eip155:812242",
+        "https://rpc.codex-stg.xyz",
+    ),
+    legacy_gas(evm(
+        "xdc-apothem",
+        "eip155:51",
+        "https://rpc.apothem.network",
+    )),
+    // Solana
+    solana(
+        "solana",
+        "solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp",
+        "https://api.mainnet-beta.solana.com",
+    ),
+    solana(
+        "solana-devnet",
+        "solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1",
+        "https://api.devnet.solana.com",
+    ),
+];
+
+/// Look up a network by its friendly name.
+#[must_use]
+pub fn network(name: &str) -> Option<&'static Network> {
+    NETWORKS.iter().find(|network| network.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use r402::chain::ChainId;
+
+    use super::*;
+
+    #[test]
+    fn names_and_chain_ids_are_unique_and_valid() {
+        let mut names = HashSet::new();
+        let mut chain_ids = HashSet::new();
+        for network in NETWORKS {
+            assert!(names.insert(network.name), "duplicate {}", network.name);
+            assert!(
+                chain_ids.insert(network.chain_id),
+                "duplicate {}",
+                network.chain_id
+            );
+            assert!(!network.name.contains(':'));
+            assert!(network.chain_id.parse::<ChainId>().is_ok());
+        }
+    }
+
+    #[test]
+    fn lookups_find_networks() {
+        assert_eq!(network("base-sepolia").unwrap().chain_id, "eip155:84532");
+        assert!(network("base").unwrap().flashblocks);
+        assert_eq!(network("solana").unwrap().namespace(), "solana");
+        assert!(network("nope").is_none());
+    }
+}
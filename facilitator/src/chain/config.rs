@@ -1,6 +1,11 @@
 //! Chain configuration types and CAIP-2 keyed TOML (de)serialisation.
 
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+use std::collections::BTreeMap;
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+use std::fmt;
 use std::ops::Deref;
+use std::time::Duration;
 
 use r402::chain::ChainId;
 #[cfg(feature = "chain-eip155")]
@@ -11,22 +16,94 @@
 use r402_svm::chain as solana;
 #[cfg(feature = "chain-solana")]
 use r402_svm::chain::SolanaChainReference;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::breaker::CircuitBreakerSettings;
+use crate::budget::{GasAmount, GasBudgetLimits};
+
+/// Extra HTTP headers sent with every request to an RPC endpoint.
+///
+/// Values may reference environment variables like any config string.  The
+/// [`Debug`] output lists header names only.
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct RpcHeaders(pub BTreeMap<String, String>);
+
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+impl RpcHeaders {
+    /// Returns `true` if no headers are configured.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+impl fmt::Debug for RpcHeaders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.keys().map(|name| (name, "<redacted>")))
+            .finish()
+    }
+}
+
+/// HTTP authentication for an RPC endpoint.
+///
+/// Credentials may reference environment variables like any config string.
+/// The [`Debug`] output never includes secrets.
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum RpcAuth {
+    /// HTTP basic authentication.
+    Basic {
+        /// User name.
+        username: String,
+        /// Password.
+        password: String,
+    },
+    /// Bearer token sent in the `Authorization` header.
+    Bearer(String),
+}
+
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+impl fmt::Debug for RpcAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"<redacted>")
+                .finish(),
+            Self::Bearer(_) => f.debug_tuple("Bearer").field(&"<redacted>").finish(),
+        }
+    }
+}
+
 /// Single RPC endpoint entry for EVM chains.
 #[cfg(feature = "chain-eip155")]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Eip155RpcEndpoint {
     /// HTTP(S) RPC URL.
     pub http: String,
     /// Optional per-endpoint rate limit (requests/second).
     #[serde(default)]
     pub rate_limit: Option<u32>,
+    /// Extra HTTP headers, e.g. an API key header.
+    #[serde(default, skip_serializing_if = "RpcHeaders::is_empty")]
+    pub headers: RpcHeaders,
+    /// Optional basic or bearer authentication.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<RpcAuth>,
 }
 
 /// Inner configuration for an EVM chain (matches TOML structure).
 #[cfg(feature = "chain-eip155")]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Eip155ChainConfigInner {
     /// RPC endpoint(s).
     pub rpc: Vec<Eip155RpcEndpoint>,
@@ -42,6 +119,46 @@ pub struct Eip155ChainConfigInner {
     /// Transaction receipt timeout in seconds (default: 30).
     #[serde(default = "default_receipt_timeout")]
     pub receipt_timeout_secs: u64,
+    /// Rolling 24h gas budget for the whole chain, in wei (default: unlimited).
+    #[serde(default)]
+    pub daily_gas_budget: Option<GasAmount>,
+    /// Rolling 24h gas budget for each signer address, in wei (default: unlimited).
+    #[serde(default)]
+    pub daily_signer_gas_budget: Option<GasAmount>,
+    /// Fee reserved against the budgets while a settlement is in flight, in
+    /// wei (default: the highest settlement fee seen so far).
+    #[serde(default)]
+    pub settlement_fee_estimate: Option<GasAmount>,
+    /// Whether startup fails if this chain cannot be initialised (default: true).
+    ///
+    /// Optional chains that fail are skipped and retried in the background.
+    #[serde(default = "default_required")]
+    pub required: bool,
+    /// Consecutive RPC failures that open the circuit breaker; 0 disables it (default: 5).
+    #[serde(default = "default_circuit_failure_threshold")]
+    pub circuit_failure_threshold: u32,
+    /// Seconds an open circuit fails fast before probing the RPC again (default: 30).
+    #[serde(default = "default_circuit_cooldown")]
+    pub circuit_cooldown_secs: u64,
+    /// Timeout for a single verify or settle call in seconds (default: 40).
+    #[serde(default = "default_call_timeout")]
+    pub call_timeout_secs: u64,
+}
+
+const fn default_required() -> bool {
+    true
+}
+
+const fn default_circuit_failure_threshold() -> u32 {
+    5
+}
+
+const fn default_circuit_cooldown() -> u64 {
+    30
+}
+
+const fn default_call_timeout() -> u64 {
+    40
 }
 
 #[cfg(feature = "chain-eip155")]
@@ -73,15 +190,89 @@ pub fn chain_id(&self) -> ChainId {
     }
 }
 
+/// Single RPC endpoint entry for Solana chains.
+#[cfg(feature = "chain-solana")]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct SolanaRpcEndpoint {
+    /// HTTP(S) RPC URL.
+    pub http: String,
+    /// Optional `WebSocket` pubsub URL used to confirm transactions sent through
+    /// this endpoint; the endpoint's headers and auth are sent with the handshake.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pubsub: Option<String>,
+    /// Optional per-endpoint rate limit (requests/second).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<u32>,
+    /// Extra HTTP headers, e.g. an API key header.
+    #[serde(default, skip_serializing_if = "RpcHeaders::is_empty")]
+    pub headers: RpcHeaders,
+    /// Optional basic or bearer authentication.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<RpcAuth>,
+}
+
+/// A Solana RPC endpoint: a URL, or an endpoint table.
+#[cfg(feature = "chain-solana")]
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum SolanaRpcEntry {
+    Url(String),
+    Table(SolanaRpcEndpoint),
+}
+
+/// Solana `rpc`: one endpoint or a list of them.
+#[cfg(feature = "chain-solana")]
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum SolanaRpc {
+    One(SolanaRpcEntry),
+    Many(Vec<SolanaRpcEntry>),
+}
+
+/// Deserialise Solana `rpc` from a URL, an endpoint table, or a list of either.
+#[cfg(feature = "chain-solana")]
+fn deserialize_solana_rpc<'de, D>(deserializer: D) -> Result<Vec<SolanaRpcEndpoint>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let endpoints = match SolanaRpc::deserialize(deserializer)? {
+        SolanaRpc::One(endpoint) => vec![endpoint],
+        SolanaRpc::Many(endpoints) => endpoints,
+    };
+    if endpoints.is_empty() {
+        return Err(serde::de::Error::custom(
+            "at least one Solana RPC endpoint is required",
+        ));
+    }
+    Ok(endpoints
+        .into_iter()
+        .map(|endpoint| match endpoint {
+            SolanaRpcEntry::Url(http) => SolanaRpcEndpoint {
+                http,
+                pubsub: None,
+                rate_limit: None,
+                headers: RpcHeaders::default(),
+                auth: None,
+            },
+            SolanaRpcEntry::Table(endpoint) => endpoint,
+        })
+        .collect())
+}
+
 /// Inner configuration for a Solana chain (matches TOML structure).
 #[cfg(feature = "chain-solana")]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct SolanaChainConfigInner {
-    /// RPC endpoint URL.
-    pub rpc: String,
-    /// Optional `WebSocket` pubsub endpoint URL.
-    #[serde(default)]
-    pub pubsub: Option<String>,
+    /// RPC endpoint(s), tried in order with failover on errors and timeouts.
+    #[serde(deserialize_with = "deserialize_solana_rpc")]
+    #[schemars(with = "SolanaRpc")]
+    pub rpc: Vec<SolanaRpcEndpoint>,
+    /// Timeout for a single RPC request before failing over to the next
+    /// endpoint, in seconds (default: 10).
+    #[serde(default = "default_rpc_timeout")]
+    pub rpc_timeout_secs: u64,
     /// Signer private key (base58, 64-byte keypair). Injected by the signers preprocessor.
     #[serde(default)]
     pub signer: Option<String>,
@@ -91,6 +282,35 @@ pub struct SolanaChainConfigInner {
     /// Maximum price per compute unit in micro-lamports (default: `1_000_000`).
     #[serde(default = "default_compute_unit_price")]
     pub max_compute_unit_price: u64,
+    /// Rolling 24h fee budget for the whole chain, in lamports (default: unlimited).
+    #[serde(default)]
+    pub daily_gas_budget: Option<GasAmount>,
+    /// Rolling 24h fee budget for the fee payer, in lamports (default: unlimited).
+    #[serde(default)]
+    pub daily_signer_gas_budget: Option<GasAmount>,
+    /// Fee reserved against the budgets while a settlement is in flight, in
+    /// lamports (default: the highest settlement fee seen so far).
+    #[serde(default)]
+    pub settlement_fee_estimate: Option<GasAmount>,
+    /// Whether startup fails if this chain cannot be initialised (default: true).
+    ///
+    /// Optional chains that fail are skipped and retried in the background.
+    #[serde(default = "default_required")]
+    pub required: bool,
+    /// Consecutive RPC failures that open the circuit breaker; 0 disables it (default: 5).
+    #[serde(default = "default_circuit_failure_threshold")]
+    pub circuit_failure_threshold: u32,
+    /// Seconds an open circuit fails fast before probing the RPC again (default: 30).
+    #[serde(default = "default_circuit_cooldown")]
+    pub circuit_cooldown_secs: u64,
+    /// Timeout for a single verify or settle call in seconds (default: 40).
+    #[serde(default = "default_call_timeout")]
+    pub call_timeout_secs: u64,
+}
+
+#[cfg(feature = "chain-solana")]
+const fn default_rpc_timeout() -> u64 {
+    10
 }
 
 #[cfg(feature = "chain-solana")]
@@ -136,6 +356,81 @@ pub enum ChainConfig {
     Solana(Box<SolanaChainConfig>),
 }
 
+impl ChainConfig {
+    /// Returns the CAIP-2 chain ID for this configuration.
+    #[must_use]
+    pub fn chain_id(&self) -> ChainId {
+        match self {
+            #[cfg(feature = "chain-eip155")]
+            Self::Eip155(config) => config.chain_id(),
+            #[cfg(feature = "chain-solana")]
+            Self::Solana(config) => config.chain_id(),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!("ChainConfig variant not enabled in this build"),
+        }
+    }
+
+    /// Returns `true` if startup must fail when this chain cannot be initialised.
+    #[must_use]
+    pub fn required(&self) -> bool {
+        match self {
+            #[cfg(feature = "chain-eip155")]
+            Self::Eip155(config) => config.inner.required,
+            #[cfg(feature = "chain-solana")]
+            Self::Solana(config) => config.inner.required,
+            #[allow(unreachable_patterns)]
+            _ => unreachable!("ChainConfig variant not enabled in this build"),
+        }
+    }
+
+    /// Returns the rolling gas budget limits configured for this chain.
+    #[must_use]
+    pub fn gas_budget(&self) -> GasBudgetLimits {
+        match self {
+            #[cfg(feature = "chain-eip155")]
+            Self::Eip155(config) => GasBudgetLimits {
+                chain: config.inner.daily_gas_budget.map(|amount| amount.0),
+                signer: config.inner.daily_signer_gas_budget.map(|amount| amount.0),
+                fee_estimate: config.inner.settlement_fee_estimate.map(|amount| amount.0),
+            },
+            #[cfg(feature = "chain-solana")]
+            Self::Solana(config) => GasBudgetLimits {
+                chain: config.inner.daily_gas_budget.map(|amount| amount.0),
+                signer: config.inner.daily_signer_gas_budget.map(|amount| amount.0),
+                fee_estimate: config.inner.settlement_fee_estimate.map(|amount| amount.0),
+            },
+            #[allow(unreachable_patterns)]
+            _ => unreachable!("ChainConfig variant not enabled in this build"),
+        }
+    }
+
+    /// Returns the circuit breaker settings configured for this chain.
+    #[must_use]
+    pub fn circuit_breaker(&self) -> CircuitBreakerSettings {
+        let (failure_threshold, cooldown_secs, call_timeout_secs) = match self {
+            #[cfg(feature = "chain-eip155")]
+            Self::Eip155(config) => (
+                config.inner.circuit_failure_threshold,
+                config.inner.circuit_cooldown_secs,
+                config.inner.call_timeout_secs,
+            ),
+            #[cfg(feature = "chain-solana")]
+            Self::Solana(config) => (
+                config.inner.circuit_failure_threshold,
+                config.inner.circuit_cooldown_secs,
+                config.inner.call_timeout_secs,
+            ),
+            #[allow(unreachable_patterns)]
+            _ => unreachable!("ChainConfig variant not enabled in this build"),
+        };
+        CircuitBreakerSettings {
+            failure_threshold,
+            cooldown: Duration::from_secs(cooldown_secs),
+            call_timeout: Duration::from_secs(call_timeout_secs),
+        }
+    }
+}
+
 /// Ordered collection of [`ChainConfig`] entries.
 ///
 /// Serialised as a TOML map keyed by CAIP-2 chain identifiers.
@@ -246,3 +541,49 @@ fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
         deserializer.deserialize_map(ChainsVisitor)
     }
 }
+
+/// Schema of a chain of the `namespace` family.
+fn chain_schema(generator: &mut schemars::SchemaGenerator, namespace: &str) -> schemars::Schema {
+    match namespace {
+        #[cfg(feature = "chain-eip155")]
+        eip155::EIP155_NAMESPACE => generator.subschema_for::<Eip155ChainConfigInner>(),
+        #[cfg(feature = "chain-solana")]
+        solana::SOLANA_NAMESPACE => generator.subschema_for::<SolanaChainConfigInner>(),
+        _ => schemars::json_schema!({
+            "type": "object",
+            "description": "A chain of a family this build does not include: skipped with a warning, or rejected with `strict = true`.",
+        }),
+    }
+}
+
+impl JsonSchema for ChainsConfig {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ChainsConfig".into()
+    }
+
+    /// Chains keyed by CAIP-2 identifier or catalog network name, with the
+    /// config variant selected by namespace.  Chains of a known family that
+    /// is not compiled into this build are accepted as any table, since they
+    /// are skipped rather than rejected.
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let mut properties = serde_json::Map::new();
+        let mut patterns = serde_json::Map::new();
+        for namespace in crate::config::known_namespaces() {
+            let schema = chain_schema(generator, namespace).to_value();
+            for network in super::catalog::NETWORKS
+                .iter()
+                .filter(|network| network.namespace() == namespace)
+            {
+                properties.insert(network.name.to_owned(), schema.clone());
+            }
+            patterns.insert(format!("^{namespace}:"), schema);
+        }
+        schemars::json_schema!({
+            "type": "object",
+            "description": "Chain provider configurations keyed by CAIP-2 identifier or network name.",
+            "properties": properties,
+            "patternProperties": patterns,
+            "additionalProperties": false,
+        })
+    }
+}
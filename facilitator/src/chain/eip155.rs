@@ -0,0 +1,324 @@
+//! EVM chain provider sending RPC requests through configured HTTP clients.
+//!
+//! [`Eip155Provider`] behaves like the upstream `Eip155ChainProvider` — round
+//! robin signers, locally tracked nonces, EIP-1559 or legacy gas pricing — but
+//! builds its JSON-RPC transports from per-endpoint HTTP clients, so the
+//! `headers` and `auth` of each endpoint are sent with every request.  With
+//! the `telemetry` feature, requests also carry the current trace context.
+//!
+//! Requests failing on every endpoint are reported to the circuit breaker
+//! ([`mark_rpc_unavailable`]) and carry the [`RPC_UNAVAILABLE`] prefix, so
+//! an unreachable backend can be told from a reverted or rejected
+//! transaction.
+
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use alloy_json_rpc::{RequestPacket, ResponsePacket};
+use alloy_network::{Ethereum, EthereumWallet, NetworkWallet, TransactionBuilder};
+use alloy_primitives::Address;
+use alloy_provider::fillers::{BlobGasFiller, ChainIdFiller, GasFiller, JoinFill, NonceFiller};
+use alloy_provider::{Provider, ProviderBuilder};
+use alloy_rpc_client::RpcClient;
+use alloy_rpc_types_eth::{BlockId, TransactionReceipt, TransactionRequest};
+use alloy_transport::layers::{FallbackLayer, ThrottleLayer};
+use alloy_transport::{TransportError, TransportErrorKind, TransportFut};
+use alloy_transport_http::Http;
+use r402::chain::{ChainId, ChainProvider};
+use r402_evm::chain::{
+    Eip155ChainReference, Eip155MetaTransactionProvider, InnerProvider, MetaTransaction,
+    MetaTransactionSendError, PendingNonceManager,
+};
+use tower::{Service, ServiceBuilder};
+use url::Url;
+
+use super::config::Eip155RpcEndpoint;
+use super::transport;
+use crate::breaker::{RPC_UNAVAILABLE, mark_rpc_unavailable};
+use crate::error::Error;
+
+/// EVM provider for one chain, with every configured RPC endpoint behind a
+/// fallback transport.
+#[derive(Debug)]
+pub struct Eip155Provider {
+    chain: Eip155ChainReference,
+    eip1559: bool,
+    flashblocks: bool,
+    receipt_timeout: Duration,
+    inner: InnerProvider,
+    signer_addresses: Vec<Address>,
+    signer_cursor: AtomicUsize,
+    nonce_manager: PendingNonceManager,
+}
+
+impl Eip155Provider {
+    /// Create a provider signing with `wallet` and sending requests to
+    /// `endpoints`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the wallet has no signers, no endpoints are given,
+    /// or an endpoint has an invalid URL, header or HTTP client.
+    pub fn new(
+        chain: Eip155ChainReference,
+        wallet: EthereumWallet,
+        endpoints: &[Eip155RpcEndpoint],
+        eip1559: bool,
+        flashblocks: bool,
+        receipt_timeout: Duration,
+    ) -> Result<Self, Error> {
+        let chain_id: ChainId = chain.into();
+        let signer_addresses: Vec<Address> =
+            NetworkWallet::<Ethereum>::signer_addresses(&wallet).collect();
+        if signer_addresses.is_empty() {
+            return Err(Error::chain(format!(
+                "no signers configured for EVM chain {chain_id}"
+            )));
+        }
+
+        let client = rpc_client(&chain_id, endpoints)?;
+        let nonce_manager = PendingNonceManager::default();
+        let filler = JoinFill::new(
+            GasFiller,
+            JoinFill::new(
+                BlobGasFiller::default(),
+                JoinFill::new(
+                    NonceFiller::new(nonce_manager.clone()),
+                    ChainIdFiller::default(),
+                ),
+            ),
+        );
+        let inner: InnerProvider = ProviderBuilder::default()
+            .filler(filler)
+            .wallet(wallet)
+            .connect_client(client);
+        tracing::info!(chain = %chain_id, signers = ?signer_addresses, "Using EVM provider");
+
+        Ok(Self {
+            chain,
+            eip1559,
+            flashblocks,
+            receipt_timeout,
+            inner,
+            signer_addresses,
+            signer_cursor: AtomicUsize::new(0),
+            nonce_manager,
+        })
+    }
+
+    /// Round-robin selection of the next signer.
+    fn next_signer_address(&self) -> Address {
+        let next = self.signer_cursor.fetch_add(1, Ordering::Relaxed) % self.signer_addresses.len();
+        self.signer_addresses[next]
+    }
+}
+
+/// Build a JSON-RPC client failing over between `endpoints`, each throttled
+/// to its rate limit and sending its own headers.
+pub fn rpc_client(chain: &ChainId, endpoints: &[Eip155RpcEndpoint]) -> Result<RpcClient, Error> {
+    let mut transports = Vec::with_capacity(endpoints.len());
+    for endpoint in endpoints {
+        let url = Url::parse(&endpoint.http)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .ok_or_else(|| Error::chain(format!("invalid HTTP RPC URL for chain {chain}")))?;
+        let headers =
+            transport::endpoint_headers(chain, &endpoint.headers, endpoint.auth.as_ref())?;
+        let client = transport::http_client(reqwest::Client::builder(), headers)?;
+        transports.push(
+            ServiceBuilder::new()
+                .layer(ThrottleLayer::new(endpoint.rate_limit.unwrap_or(u32::MAX)))
+                .service(Http::with_client(client, url)),
+        );
+    }
+    let count = NonZeroUsize::new(transports.len()).ok_or_else(|| {
+        Error::chain(format!("no RPC endpoints configured for EVM chain {chain}"))
+    })?;
+    let fallback = ServiceBuilder::new()
+        .layer(FallbackLayer::default().with_active_transport_count(count))
+        .service(transports);
+    Ok(RpcClient::new(RpcTransport(fallback), false))
+}
+
+/// Outermost transport: marks requests that failed on every endpoint as
+/// [`RPC_UNAVAILABLE`], for the message and the circuit breaker, and, with the `telemetry` feature, adds the current
+/// trace context to every JSON-RPC request.
+///
+/// JSON-RPC error responses (e.g. a reverted `eth_estimateGas`) arrive as
+/// successful responses here and are left untouched.
+#[derive(Debug, Clone)]
+struct RpcTransport<S>(S);
+
+impl<S> Service<RequestPacket> for RpcTransport<S>
+where
+    S: Service<
+            RequestPacket,
+            Response = ResponsePacket,
+            Error = TransportError,
+            Future = TransportFut<'static>,
+        >,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, packet: RequestPacket) -> Self::Future {
+        // The HTTP transport merges the headers of all requests in a batch.
+        #[cfg(feature = "telemetry")]
+        let mut packet = packet;
+        #[cfg(feature = "telemetry")]
+        if let Some(request) = packet.requests_mut().last_mut() {
+            crate::telemetry::inject_trace_context(request.headers_mut());
+        }
+        let response = self.0.call(packet);
+        Box::pin(async move {
+            response.await.map_err(|e| {
+                mark_rpc_unavailable();
+                TransportErrorKind::custom_str(&format!("{RPC_UNAVAILABLE}: {e}"))
+            })
+        })
+    }
+}
+
+impl ChainProvider for Eip155Provider {
+    fn signer_addresses(&self) -> Vec<String> {
+        self.signer_addresses
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn chain_id(&self) -> ChainId {
+        self.chain.into()
+    }
+}
+
+impl Eip155MetaTransactionProvider for Eip155Provider {
+    type Error = MetaTransactionSendError;
+    type Inner = InnerProvider;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    fn chain(&self) -> &Eip155ChainReference {
+        &self.chain
+    }
+
+    /// Send `tx` from the next signer and wait for its receipt.
+    ///
+    /// The signer's nonce is reset whenever sending or waiting fails, so the
+    /// next transaction queries it afresh.
+    async fn send_transaction(
+        &self,
+        tx: MetaTransaction,
+    ) -> Result<TransactionReceipt, Self::Error> {
+        let from = self.next_signer_address();
+        let mut request = TransactionRequest::default()
+            .with_to(tx.to)
+            .with_from(from)
+            .with_input(tx.calldata);
+
+        if !self.eip1559 {
+            request.set_gas_price(self.inner.get_gas_price().await?);
+        }
+        let block = if self.flashblocks {
+            BlockId::latest()
+        } else {
+            BlockId::pending()
+        };
+        let gas_limit = self
+            .inner
+            .estimate_gas(request.clone())
+            .block(block)
+            .await?;
+        request.set_gas_limit(gas_limit);
+
+        let pending = match self.inner.send_transaction(request).await {
+            Ok(pending) => pending,
+            Err(e) => {
+                self.nonce_manager.reset_nonce(from).await;
+                return Err(MetaTransactionSendError::Transport(e));
+            }
+        };
+        let receipt = pending
+            .with_required_confirmations(tx.confirmations)
+            .with_timeout(Some(self.receipt_timeout))
+            .get_receipt()
+            .await;
+        match receipt {
+            Ok(receipt) => Ok(receipt),
+            Err(e) => {
+                self.nonce_manager.reset_nonce(from).await;
+                Err(MetaTransactionSendError::PendingTransaction(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use alloy_json_rpc::RpcError;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::chain::config::RpcHeaders;
+
+    fn endpoint(http: String) -> Eip155RpcEndpoint {
+        Eip155RpcEndpoint {
+            http,
+            rate_limit: None,
+            headers: RpcHeaders::default(),
+            auth: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_endpoints_are_marked_unavailable() {
+        let chain = ChainId::new("eip155", "84532");
+        // Nothing listens on port 9.
+        let client = rpc_client(&chain, &[endpoint("http://127.0.0.1:9/".to_owned())]).unwrap();
+        let err = client
+            .request_noparams::<Value>("eth_blockNumber")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(RPC_UNAVAILABLE), "{err}");
+    }
+
+    #[tokio::test]
+    async fn error_responses_are_not_marked() {
+        async fn revert(Json(request): Json<Value>) -> Json<Value> {
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": 3, "message": "execution reverted" }
+            }))
+        }
+        let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(
+            async move { axum::serve(listener, Router::new().route("/", post(revert))).await },
+        );
+
+        let chain = ChainId::new("eip155", "84532");
+        let client = rpc_client(&chain, &[endpoint(url)]).unwrap();
+        let err = client
+            .request_noparams::<Value>("eth_estimateGas")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RpcError::ErrorResp(_)), "{err}");
+        assert!(!err.to_string().contains(RPC_UNAVAILABLE));
+    }
+}
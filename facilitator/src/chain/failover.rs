@@ -0,0 +1,630 @@
+//! Solana RPC failover across multiple endpoints.
+//!
+//! [`SolanaFailoverProvider`] holds one RPC client per configured endpoint and
+//! implements [`SolanaChainProviderLike`] by trying them in turn: transport
+//! errors and timeouts move on to the next endpoint, while answers from the
+//! chain (e.g. a failed simulation or an RPC error response) are returned
+//! as-is.  The endpoint that last succeeded is tried first on the next call,
+//! so a dead endpoint only costs one timeout until it fails again.  When every
+//! endpoint fails, the circuit breaker is told ([`mark_rpc_unavailable`]) and
+//! the error carries the [`RPC_UNAVAILABLE`] prefix.
+//!
+//! Re-sending a signed transaction through another endpoint is safe: Solana
+//! deduplicates transactions by signature.
+
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use axum::http::HeaderMap;
+use futures_util::StreamExt;
+use r402::chain::{ChainId, ChainProvider};
+use r402_svm::chain::{
+    Address, SolanaChainProviderError, SolanaChainProviderLike, SolanaChainReference,
+};
+use solana_account::Account;
+use solana_client::client_error::ClientErrorKind;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::pubsub_client::PubsubClientError;
+use solana_client::rpc_client::SerializableTransaction;
+use solana_client::rpc_config::{
+    RpcSendTransactionConfig, RpcSignatureSubscribeConfig, RpcSimulateTransactionConfig,
+};
+use solana_client::rpc_request::RpcError;
+use solana_client::rpc_response::{RpcSignatureResult, TransactionError, UiTransactionError};
+use solana_commitment_config::CommitmentConfig;
+use solana_keypair::{Keypair, Signer};
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_transaction::versioned::VersionedTransaction;
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::time::Instant;
+use tungstenite::client::IntoClientRequest;
+
+use crate::breaker::{RPC_UNAVAILABLE, mark_rpc_unavailable};
+use crate::error::Error;
+
+/// How long a single endpoint gets to send and confirm a transaction.
+const CONFIRM_TIMEOUT: Duration = Duration::from_mins(1);
+/// Interval between confirmation polls.
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Spaces requests to an endpoint according to its rate limit.
+#[derive(Debug)]
+struct Throttle {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl Throttle {
+    /// Create a throttle for `rate_limit` requests per second; `0` disables it.
+    fn new(rate_limit: u32) -> Option<Self> {
+        (rate_limit > 0).then(|| Self {
+            interval: Duration::from_secs(1) / rate_limit,
+            next: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Reserve the next request slot and wait for it.
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap_or_else(PoisonError::into_inner);
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// A `WebSocket` pubsub endpoint, connected on first use.
+///
+/// A failed connection or subscription drops the client so the next
+/// confirmation reconnects; until then the endpoint confirms by polling.
+pub struct Pubsub {
+    url: String,
+    headers: HeaderMap,
+    client: AsyncMutex<Option<Arc<PubsubClient>>>,
+}
+
+impl fmt::Debug for Pubsub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pubsub")
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Pubsub {
+    /// Describe a pubsub endpoint at `url`, sending `headers` with the
+    /// handshake.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `url` is not a valid `WebSocket` URL.
+    pub fn new(url: String, headers: HeaderMap) -> Result<Self, Error> {
+        url.as_str()
+            .into_client_request()
+            .map_err(|e| Error::chain_with(format!("invalid pubsub URL '{url}'"), e))?;
+        Ok(Self {
+            url,
+            headers,
+            client: AsyncMutex::new(None),
+        })
+    }
+
+    /// The connected client, connecting first if necessary.
+    async fn client(&self) -> Result<Arc<PubsubClient>, PubsubClientError> {
+        let mut client = self.client.lock().await;
+        if let Some(client) = client.as_ref() {
+            return Ok(Arc::clone(client));
+        }
+        let mut request = self
+            .url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| PubsubClientError::ConnectionError(Box::new(e)))?;
+        request.headers_mut().extend(self.headers.clone());
+        let connected = Arc::new(PubsubClient::new(request).await?);
+        *client = Some(Arc::clone(&connected));
+        Ok(connected)
+    }
+
+    /// Drop the client so the next use reconnects.
+    async fn disconnect(&self) {
+        self.client.lock().await.take();
+    }
+}
+
+/// A single RPC endpoint with its optional pubsub endpoint and rate limit.
+pub struct SolanaEndpoint {
+    rpc_client: RpcClient,
+    pubsub: Option<Pubsub>,
+    throttle: Option<Throttle>,
+}
+
+impl fmt::Debug for SolanaEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SolanaEndpoint")
+            .field("rpc_url", &self.rpc_client.url())
+            .field("pubsub", &self.pubsub)
+            .field("throttle", &self.throttle)
+            .finish()
+    }
+}
+
+impl SolanaEndpoint {
+    /// Wrap `rpc_client`, limiting it to `rate_limit` requests per second.
+    ///
+    /// Transactions sent through this endpoint are confirmed over `pubsub`
+    /// when it is given and reachable, and by polling the RPC otherwise.
+    #[must_use]
+    pub fn new(rpc_client: RpcClient, pubsub: Option<Pubsub>, rate_limit: Option<u32>) -> Self {
+        Self {
+            rpc_client,
+            pubsub,
+            throttle: rate_limit.and_then(Throttle::new),
+        }
+    }
+
+    /// The endpoint's RPC client.
+    #[must_use]
+    pub const fn rpc_client(&self) -> &RpcClient {
+        &self.rpc_client
+    }
+
+    /// Submit a signed transaction without waiting for confirmation.
+    async fn send(&self, tx: &VersionedTransaction) -> Result<Signature, SolanaChainProviderError> {
+        let config = RpcSendTransactionConfig {
+            skip_preflight: true,
+            ..RpcSendTransactionConfig::default()
+        };
+        Ok(self
+            .rpc_client
+            .send_transaction_with_config(tx, config)
+            .await?)
+    }
+
+    /// Submit a signed transaction and wait until it reaches `commitment`.
+    ///
+    /// Waits indefinitely; callers bound each attempt with a timeout.
+    async fn send_and_confirm(
+        &self,
+        tx: &VersionedTransaction,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, SolanaChainProviderError> {
+        if let Some(pubsub) = &self.pubsub {
+            match pubsub.client().await {
+                Ok(client) => {
+                    return self
+                        .confirm_over_pubsub(&client, pubsub, tx, commitment)
+                        .await;
+                }
+                Err(e) => tracing::warn!(
+                    pubsub = %pubsub.url,
+                    error = %e,
+                    "Solana pubsub unavailable, polling for confirmation"
+                ),
+            }
+        }
+        let signature = self.send(tx).await?;
+        self.poll_confirmation(&signature, commitment).await
+    }
+
+    /// Subscribe to the transaction's signature, then send it and wait for
+    /// the notification, polling instead if the subscription fails.
+    async fn confirm_over_pubsub(
+        &self,
+        client: &PubsubClient,
+        pubsub: &Pubsub,
+        tx: &VersionedTransaction,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, SolanaChainProviderError> {
+        let signature = *tx.get_signature();
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(commitment),
+            enable_received_notification: None,
+        };
+        let (mut stream, unsubscribe) =
+            match client.signature_subscribe(&signature, Some(config)).await {
+                Ok(subscription) => subscription,
+                Err(e) => {
+                    tracing::warn!(
+                        pubsub = %pubsub.url,
+                        error = %e,
+                        "Solana signature subscription failed, polling for confirmation"
+                    );
+                    pubsub.disconnect().await;
+                    self.send(tx).await?;
+                    return self.poll_confirmation(&signature, commitment).await;
+                }
+            };
+        if let Err(e) = self.send(tx).await {
+            unsubscribe().await;
+            return Err(e);
+        }
+        let Some(response) = stream.next().await else {
+            tracing::warn!(
+                pubsub = %pubsub.url,
+                "Solana signature subscription closed, polling for confirmation"
+            );
+            pubsub.disconnect().await;
+            return self.poll_confirmation(&signature, commitment).await;
+        };
+        match response.value {
+            RpcSignatureResult::ProcessedSignature(result) => {
+                result.err.map_or(Ok(signature), |e| {
+                    Err(SolanaChainProviderError::InvalidTransaction(e))
+                })
+            }
+            RpcSignatureResult::ReceivedSignature(_) => Ok(signature),
+        }
+    }
+
+    /// Poll the RPC until `signature` reaches `commitment`.
+    async fn poll_confirmation(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Result<Signature, SolanaChainProviderError> {
+        loop {
+            let confirmed = self
+                .rpc_client
+                .confirm_transaction_with_commitment(signature, commitment)
+                .await?;
+            if confirmed.value {
+                return Ok(*signature);
+            }
+            tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Solana provider failing over between several RPC endpoints.
+pub struct SolanaFailoverProvider {
+    chain: SolanaChainReference,
+    keypair: Keypair,
+    max_compute_unit_limit: u32,
+    max_compute_unit_price: u64,
+    endpoints: Vec<SolanaEndpoint>,
+    preferred: AtomicUsize,
+    request_timeout: Duration,
+}
+
+impl fmt::Debug for SolanaFailoverProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SolanaFailoverProvider")
+            .field("chain", &self.chain)
+            .field("pubkey", &self.keypair.pubkey())
+            .field("endpoints", &self.endpoints)
+            .field("request_timeout", &self.request_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SolanaFailoverProvider {
+    /// Create a provider paying fees from `keypair` over `endpoints`.
+    ///
+    /// `request_timeout` bounds simulations and account reads on a single
+    /// endpoint; sending and confirming a transaction gets a minute per
+    /// endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `endpoints` is empty.
+    pub fn new(
+        chain: SolanaChainReference,
+        keypair: Keypair,
+        max_compute_unit_limit: u32,
+        max_compute_unit_price: u64,
+        endpoints: Vec<SolanaEndpoint>,
+        request_timeout: Duration,
+    ) -> Result<Self, Error> {
+        let chain_id = ChainId::from(chain);
+        if endpoints.is_empty() {
+            return Err(Error::chain(format!(
+                "no RPC endpoints configured for Solana chain {chain_id}"
+            )));
+        }
+        tracing::info!(
+            chain = %chain_id,
+            signer = %keypair.pubkey(),
+            endpoints = endpoints.len(),
+            max_compute_unit_limit,
+            max_compute_unit_price,
+            "Using Solana provider"
+        );
+        Ok(Self {
+            chain,
+            keypair,
+            max_compute_unit_limit,
+            max_compute_unit_price,
+            endpoints,
+            preferred: AtomicUsize::new(0),
+            request_timeout,
+        })
+    }
+
+    /// Run `call` against each endpoint in turn until one succeeds or returns
+    /// an error that is not worth retrying elsewhere.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first non-retryable error, or the last error once every
+    /// endpoint has failed.
+    pub async fn with_failover<'a, T, F, Fut>(
+        &'a self,
+        timeout: Option<Duration>,
+        call: F,
+    ) -> Result<T, SolanaChainProviderError>
+    where
+        F: Fn(&'a SolanaEndpoint) -> Fut,
+        Fut: Future<Output = Result<T, SolanaChainProviderError>>,
+    {
+        let start = self.preferred.load(Ordering::Relaxed);
+        let count = self.endpoints.len();
+        let mut last_error = None;
+        for offset in 0..count {
+            let index = (start + offset) % count;
+            let endpoint = &self.endpoints[index];
+            if let Some(throttle) = &endpoint.throttle {
+                throttle.wait().await;
+            }
+            let result = match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, call(endpoint))
+                    .await
+                    .unwrap_or_else(|_| Err(timed_out(timeout))),
+                None => call(endpoint).await,
+            };
+            match result {
+                Err(e) if should_fail_over(&e) => {
+                    tracing::warn!(
+                        chain = %self.chain_id(),
+                        endpoint = index,
+                        error = %e,
+                        "Solana RPC endpoint failed"
+                    );
+                    last_error = Some(e);
+                }
+                result => {
+                    if offset > 0 {
+                        self.preferred.store(index, Ordering::Relaxed);
+                        tracing::info!(
+                            chain = %self.chain_id(),
+                            endpoint = index,
+                            "Failed over to Solana RPC endpoint"
+                        );
+                    }
+                    return result;
+                }
+            }
+        }
+        Err(last_error.map_or_else(
+            || SolanaChainProviderError::Custom("no Solana RPC endpoints configured".into()),
+            |e| {
+                mark_rpc_unavailable();
+                SolanaChainProviderError::Transport(Box::new(ClientErrorKind::Custom(format!(
+                    "{RPC_UNAVAILABLE}: {e}"
+                ))))
+            },
+        ))
+    }
+}
+
+/// Transport-level failures are retried on the next endpoint.
+fn should_fail_over(error: &SolanaChainProviderError) -> bool {
+    match error {
+        SolanaChainProviderError::Transport(kind) => matches!(
+            **kind,
+            ClientErrorKind::Io(_)
+                | ClientErrorKind::Reqwest(_)
+                | ClientErrorKind::Middleware(_)
+                | ClientErrorKind::SerdeJson(_)
+                | ClientErrorKind::Custom(_)
+                | ClientErrorKind::RpcError(RpcError::RpcRequestError(_))
+        ),
+        SolanaChainProviderError::PubsubTransport(_) => true,
+        _ => false,
+    }
+}
+
+fn timed_out(timeout: Duration) -> SolanaChainProviderError {
+    SolanaChainProviderError::Transport(Box::new(ClientErrorKind::Custom(format!(
+        "RPC request timed out after {timeout:?}"
+    ))))
+}
+
+impl ChainProvider for SolanaFailoverProvider {
+    fn signer_addresses(&self) -> Vec<String> {
+        vec![self.fee_payer().to_string()]
+    }
+
+    fn chain_id(&self) -> ChainId {
+        self.chain.into()
+    }
+}
+
+impl SolanaChainProviderLike for SolanaFailoverProvider {
+    async fn simulate_transaction_with_config(
+        &self,
+        tx: &VersionedTransaction,
+        cfg: RpcSimulateTransactionConfig,
+    ) -> Result<(), SolanaChainProviderError> {
+        self.with_failover(Some(self.request_timeout), |endpoint| {
+            let cfg = cfg.clone();
+            async move {
+                let simulation = endpoint
+                    .rpc_client
+                    .simulate_transaction_with_config(tx, cfg)
+                    .await?;
+                simulation.value.err.map_or(Ok(()), |e| {
+                    Err(SolanaChainProviderError::InvalidTransaction(e))
+                })
+            }
+        })
+        .await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, SolanaChainProviderError> {
+        self.with_failover(Some(self.request_timeout), |endpoint| async move {
+            Ok(endpoint.rpc_client.get_multiple_accounts(pubkeys).await?)
+        })
+        .await
+    }
+
+    fn max_compute_unit_limit(&self) -> u32 {
+        self.max_compute_unit_limit
+    }
+
+    fn max_compute_unit_price(&self) -> u64 {
+        self.max_compute_unit_price
+    }
+
+    fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    fn fee_payer(&self) -> Address {
+        Address::new(self.keypair.pubkey())
+    }
+
+    fn sign(
+        &self,
+        tx: VersionedTransaction,
+    ) -> Result<VersionedTransaction, SolanaChainProviderError> {
+        let mut tx = tx;
+        let signature = self
+            .keypair
+            .try_sign_message(tx.message.serialize().as_slice())?;
+        // Required signers are the first accounts of the message.
+        let required = usize::from(tx.message.header().num_required_signatures);
+        let position = tx
+            .message
+            .static_account_keys()
+            .iter()
+            .take(required)
+            .position(|key| *key == self.keypair.pubkey())
+            .ok_or_else(|| {
+                SolanaChainProviderError::InvalidTransaction(UiTransactionError::from(
+                    TransactionError::InvalidAccountIndex,
+                ))
+            })?;
+        if tx.signatures.len() < required {
+            tx.signatures.resize(required, Signature::default());
+        }
+        tx.signatures[position] = signature;
+        Ok(tx)
+    }
+
+    async fn send_and_confirm(
+        &self,
+        tx: &VersionedTransaction,
+        commitment_config: CommitmentConfig,
+    ) -> Result<Signature, SolanaChainProviderError> {
+        self.with_failover(Some(CONFIRM_TIMEOUT), |endpoint| {
+            endpoint.send_and_confirm(tx, commitment_config)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_client::rpc_request::RpcResponseErrorData;
+
+    use super::*;
+
+    #[test]
+    fn throttle_disabled_for_zero() {
+        assert!(Throttle::new(0).is_none());
+        assert_eq!(
+            Throttle::new(4).unwrap().interval,
+            Duration::from_millis(250)
+        );
+    }
+
+    #[tokio::test]
+    async fn throttle_spaces_requests() {
+        let throttle = Throttle::new(100).unwrap();
+        let start = Instant::now();
+        for _ in 0..3 {
+            throttle.wait().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn only_transport_errors_fail_over() {
+        assert!(should_fail_over(&timed_out(Duration::from_secs(1))));
+        assert!(!should_fail_over(&SolanaChainProviderError::Custom(
+            "nope".into()
+        )));
+        let response = ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: -32002,
+            message: "Transaction simulation failed".into(),
+            data: RpcResponseErrorData::Empty,
+        });
+        assert!(!should_fail_over(&SolanaChainProviderError::Transport(
+            Box::new(response)
+        )));
+    }
+
+    #[tokio::test]
+    async fn exhausted_endpoints_are_marked_unavailable() {
+        let endpoint =
+            || SolanaEndpoint::new(RpcClient::new("http://127.0.0.1:9".to_owned()), None, None);
+        let provider = SolanaFailoverProvider::new(
+            SolanaChainReference::SOLANA_DEVNET,
+            Keypair::new(),
+            200_000,
+            1_000_000,
+            vec![endpoint(), endpoint()],
+            Duration::from_secs(1),
+        )
+        .unwrap();
+        let err = provider
+            .with_failover(None, |_| async {
+                Err::<(), _>(timed_out(Duration::from_secs(1)))
+            })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(RPC_UNAVAILABLE), "{err}");
+
+        let err = provider
+            .with_failover(None, |_| async {
+                Err::<(), _>(SolanaChainProviderError::Custom("rejected".into()))
+            })
+            .await
+            .unwrap_err();
+        assert!(!err.to_string().contains(RPC_UNAVAILABLE));
+    }
+
+    #[test]
+    fn empty_endpoints_are_an_error() {
+        let result = SolanaFailoverProvider::new(
+            SolanaChainReference::SOLANA_DEVNET,
+            Keypair::new(),
+            200_000,
+            1_000_000,
+            Vec::new(),
+            Duration::from_secs(1),
+        );
+        assert!(result.unwrap_err().to_string().contains("no RPC endpoints"));
+    }
+
+    #[tokio::test]
+    async fn pubsub_connects_lazily() {
+        assert!(Pubsub::new("not a url".to_owned(), HeaderMap::new()).is_err());
+        // Nothing listens on port 9, so only connecting would fail.
+        let pubsub = Pubsub::new("ws://127.0.0.1:9".to_owned(), HeaderMap::new()).unwrap();
+        assert!(pubsub.client.lock().await.is_none());
+        assert!(pubsub.client().await.is_err());
+        assert!(pubsub.client.lock().await.is_none());
+    }
+}
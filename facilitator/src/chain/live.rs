@@ -0,0 +1,209 @@
+//! Registry of chains that are initialised and serving requests.
+//!
+//! [`LiveChains`] holds the provider and scheme handlers of every chain that
+//! came up, and implements [`Facilitator`] by routing each request to the
+//! handlers of its chain.  Optional chains that fail at startup are retried in
+//! the background by [`spawn_chain_retry`] and inserted once they come up, so
+//! `/supported` only ever lists chains that are live.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+
+use r402::chain::{ChainId, ChainProvider as ChainProviderTrait};
+use r402::facilitator::{Facilitator, FacilitatorError};
+use r402::proto;
+use r402::scheme::SchemeRegistry;
+
+use super::config::ChainConfig;
+use super::provider::{ChainProvider, build_chain_provider};
+use super::schemes::build_scheme_registry;
+use crate::config::SchemeEntry;
+
+/// Delay before the first background retry of a failed chain.
+const RETRY_INITIAL_DELAY: Duration = Duration::from_secs(5);
+
+/// Upper bound for the exponential retry back-off.
+const RETRY_MAX_DELAY: Duration = Duration::from_mins(5);
+
+/// Provider and scheme handlers of a live chain.
+#[derive(Debug)]
+struct LiveChain {
+    provider: ChainProvider,
+    schemes: Arc<SchemeRegistry>,
+}
+
+/// Chains that are initialised and serving requests.
+#[derive(Debug, Default)]
+pub struct LiveChains {
+    chains: RwLock<HashMap<ChainId, LiveChain>>,
+}
+
+impl LiveChains {
+    /// Add `provider` with its scheme handlers, replacing any previous entry.
+    pub fn insert(&self, provider: ChainProvider, schemes: SchemeRegistry) {
+        let chain_id = provider.chain_id();
+        let chain = LiveChain {
+            provider,
+            schemes: Arc::new(schemes),
+        };
+        self.chains
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(chain_id, chain);
+    }
+
+    /// Returns the provider of `chain`, if it is live.
+    #[must_use]
+    pub fn provider(&self, chain: &ChainId) -> Option<ChainProvider> {
+        self.chains
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(chain)
+            .map(|chain| chain.provider.clone())
+    }
+
+    fn schemes(&self, chain: &ChainId) -> Option<Arc<SchemeRegistry>> {
+        self.chains
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(chain)
+            .map(|chain| Arc::clone(&chain.schemes))
+    }
+
+    fn all_schemes(&self) -> Vec<Arc<SchemeRegistry>> {
+        self.chains
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .map(|chain| Arc::clone(&chain.schemes))
+            .collect()
+    }
+
+    /// Returns the scheme handlers for the chain named in a request.
+    fn require_schemes(
+        &self,
+        chain: Option<ChainId>,
+    ) -> Result<Arc<SchemeRegistry>, FacilitatorError> {
+        chain
+            .and_then(|chain| self.schemes(&chain))
+            .ok_or_else(|| FacilitatorError::Aborted {
+                reason: "no_facilitator_for_network".into(),
+                message: "no handler registered for this payment scheme".into(),
+            })
+    }
+}
+
+impl Facilitator for LiveChains {
+    fn verify(
+        &self,
+        request: proto::VerifyRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::VerifyResponse, FacilitatorError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let schemes = self.require_schemes(request.scheme_slug().map(|slug| slug.chain_id))?;
+            schemes.verify(request).await
+        })
+    }
+
+    fn settle(
+        &self,
+        request: proto::SettleRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::SettleResponse, FacilitatorError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let schemes = self.require_schemes(request.scheme_slug().map(|slug| slug.chain_id))?;
+            schemes.settle(request).await
+        })
+    }
+
+    fn supported(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::SupportedResponse, FacilitatorError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let mut kinds = Vec::new();
+            let mut signers: HashMap<String, Vec<String>> = HashMap::new();
+            for schemes in self.all_schemes() {
+                let mut supported = schemes.supported().await?;
+                kinds.append(&mut supported.kinds);
+                for (family, addresses) in supported.signers {
+                    signers.entry(family).or_default().extend(addresses);
+                }
+            }
+            kinds.sort_by(|a, b| {
+                a.network
+                    .cmp(&b.network)
+                    .then_with(|| a.scheme.cmp(&b.scheme))
+            });
+            for addresses in signers.values_mut() {
+                addresses.sort_unstable();
+                addresses.dedup();
+            }
+            Ok(proto::SupportedResponse {
+                kinds,
+                extensions: Vec::new(),
+                signers,
+            })
+        })
+    }
+}
+
+/// Keep retrying an optional chain that failed to initialise, adding it to
+/// `live` once it comes up.
+///
+/// Retries back off exponentially from 5 seconds up to 5 minutes.
+pub fn spawn_chain_retry(live: Arc<LiveChains>, config: ChainConfig, schemes: Vec<SchemeEntry>) {
+    tokio::spawn(async move {
+        let mut delay = RETRY_INITIAL_DELAY;
+        loop {
+            tokio::time::sleep(delay).await;
+            match build_chain_provider(&config).await {
+                Ok(provider) => {
+                    tracing::info!(chain = %provider.chain_id(), "Chain initialised, now serving");
+                    let scheme_registry = build_scheme_registry(&provider, &schemes);
+                    live.insert(provider, scheme_registry);
+                    return;
+                }
+                Err(e) => {
+                    delay = (delay * 2).min(RETRY_MAX_DELAY);
+                    tracing::warn!(
+                        chain = %config.chain_id(),
+                        error = %e,
+                        retry_in_secs = delay.as_secs(),
+                        "Chain initialisation failed, retrying"
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn unknown_chain_is_rejected() {
+        let live = LiveChains::default();
+        assert!(live.provider(&ChainId::new("eip155", "1")).is_none());
+
+        let error = live
+            .require_schemes(Some(ChainId::new("eip155", "1")))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            FacilitatorError::Aborted { ref reason, .. } if reason == "no_facilitator_for_network"
+        ));
+        assert!(live.require_schemes(None).is_err());
+    }
+
+    #[tokio::test]
+    async fn supported_is_empty_without_live_chains() {
+        let supported = LiveChains::default().supported().await.unwrap();
+        assert!(supported.kinds.is_empty());
+        assert!(supported.signers.is_empty());
+    }
+}
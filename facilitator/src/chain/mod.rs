@@ -1,12 +1,29 @@
 //! Blockchain chain types, configuration, and provider registry.
 //!
+//! - [`catalog`] — Built-in catalog of well-known networks and their defaults.
 //! - [`config`] — Chain configuration types and CAIP-2 keyed TOML (de)serialisation.
-//! - [`provider`] — [`ChainProvider`] enum, trait impl, and registry construction.
+//! - [`eip155`] — EVM provider sending RPC requests through the configured HTTP clients.
+//! - [`failover`] — Solana provider failing over between multiple RPC endpoints.
+//! - [`live`] — Registry of initialised chains, routing requests and retrying failed chains.
+//! - [`provider`] — [`ChainProvider`] enum, trait impl, and startup construction.
 //! - [`schemes`] — [`SchemeBuilder`] implementations bridging providers to scheme handlers.
+//! - [`transport`] — HTTP clients carrying each RPC endpoint's headers and authentication.
 
+pub mod catalog;
 mod config;
+#[cfg(feature = "chain-eip155")]
+mod eip155;
+#[cfg(feature = "chain-solana")]
+mod failover;
+mod live;
 mod provider;
 mod schemes;
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+mod transport;
 
 pub use self::config::*;
+#[cfg(all(test, feature = "chain-eip155"))]
+pub use self::eip155::rpc_client;
+pub use self::live::*;
 pub use self::provider::*;
+pub use self::schemes::build_scheme_registry;
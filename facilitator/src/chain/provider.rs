@@ -1,16 +1,20 @@
 //! Chain provider types and registry construction.
 
-use std::collections::HashMap;
-#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
 use std::sync::Arc;
+use std::time::Duration;
 
-use r402::chain::{ChainId, ChainProvider as ChainProviderTrait, ChainRegistry};
-#[cfg(feature = "chain-eip155")]
-use r402_evm::chain as eip155;
-#[cfg(feature = "chain-solana")]
-use r402_svm::chain as solana;
+use r402::chain::{ChainId, ChainProvider as ChainProviderTrait};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
 
 use super::config::{ChainConfig, ChainsConfig};
+#[cfg(feature = "chain-eip155")]
+use super::eip155::Eip155Provider;
+#[cfg(feature = "chain-solana")]
+use super::failover::{Pubsub, SolanaEndpoint, SolanaFailoverProvider};
+#[cfg(feature = "chain-solana")]
+use super::transport;
 use crate::error::Error;
 
 /// Unified blockchain provider wrapping chain-family–specific implementations.
@@ -18,10 +22,10 @@
 pub enum ChainProvider {
     /// EVM chain provider for EIP-155 compatible networks.
     #[cfg(feature = "chain-eip155")]
-    Eip155(Arc<eip155::Eip155ChainProvider>),
-    /// Solana chain provider.
+    Eip155(Arc<Eip155Provider>),
+    /// Solana chain provider failing over between RPC endpoints.
     #[cfg(feature = "chain-solana")]
-    Solana(Arc<solana::SolanaChainProvider>),
+    Solana(Arc<SolanaFailoverProvider>),
 }
 
 impl ChainProviderTrait for ChainProvider {
@@ -48,6 +52,114 @@ fn chain_id(&self) -> ChainId {
     }
 }
 
+/// Native fee paid by the facilitator for a single settlement transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettlementFee {
+    /// Address of the signer that paid the fee.
+    pub signer: String,
+    /// Fee amount in the chain's smallest native unit (wei / lamports).
+    pub amount: u128,
+    /// Gas (EVM) or compute units (Solana) consumed by the transaction.
+    pub gas_used: u64,
+}
+
+impl ChainProvider {
+    /// Look up the native fee paid for a settlement transaction.
+    ///
+    /// Returns `Ok(None)` if the RPC node has no fee information for the
+    /// transaction (e.g. the receipt is not available yet).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transaction identifier cannot be parsed or the
+    /// RPC request fails.
+    pub async fn settlement_fee(&self, transaction: &str) -> Result<Option<SettlementFee>, Error> {
+        match self {
+            #[cfg(feature = "chain-eip155")]
+            Self::Eip155(provider) => eip155_settlement_fee(provider, transaction).await,
+            #[cfg(feature = "chain-solana")]
+            Self::Solana(provider) => solana_settlement_fee(provider, transaction).await,
+            #[allow(unreachable_patterns)]
+            _ => unreachable!("ChainProvider variant not enabled in this build"),
+        }
+    }
+}
+
+/// Read `gas_used * effective_gas_price` and the sender from an EVM receipt.
+///
+/// Only the L2 execution fee is accounted for; L1 data fees charged by some
+/// rollups are not part of the receipt and are not included.
+#[cfg(feature = "chain-eip155")]
+async fn eip155_settlement_fee(
+    provider: &Eip155Provider,
+    transaction: &str,
+) -> Result<Option<SettlementFee>, Error> {
+    use alloy_primitives::TxHash;
+    use alloy_provider::Provider;
+    use r402_evm::chain::Eip155MetaTransactionProvider;
+
+    let hash: TxHash = transaction.parse().map_err(|e| {
+        Error::chain_with(format!("invalid EVM transaction hash '{transaction}'"), e)
+    })?;
+    let receipt = provider
+        .inner()
+        .get_transaction_receipt(hash)
+        .await
+        .map_err(|e| Error::chain_with("failed to fetch EVM transaction receipt", e))?;
+
+    Ok(receipt.map(|receipt| SettlementFee {
+        signer: receipt.from.to_string(),
+        amount: u128::from(receipt.gas_used).saturating_mul(receipt.effective_gas_price),
+        gas_used: receipt.gas_used,
+    }))
+}
+
+/// Read the transaction fee from a confirmed Solana transaction.
+///
+/// The facilitator keypair is always the fee payer, so the fee is attributed
+/// to the provider's single signer address.
+#[cfg(feature = "chain-solana")]
+async fn solana_settlement_fee(
+    provider: &SolanaFailoverProvider,
+    transaction: &str,
+) -> Result<Option<SettlementFee>, Error> {
+    use solana_client::rpc_config::RpcTransactionConfig;
+    use solana_commitment_config::CommitmentConfig;
+    use solana_signature::Signature;
+
+    let signature: Signature = transaction.parse().map_err(|e| {
+        Error::chain_with(
+            format!("invalid Solana transaction signature '{transaction}'"),
+            e,
+        )
+    })?;
+    let confirmed = provider
+        .with_failover(None, |endpoint| async move {
+            let config = RpcTransactionConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+                ..RpcTransactionConfig::default()
+            };
+            Ok(endpoint
+                .rpc_client()
+                .get_transaction_with_config(&signature, config)
+                .await?)
+        })
+        .await
+        .map_err(|e| Error::chain_with("failed to fetch Solana transaction", e))?;
+
+    let signer = provider
+        .signer_addresses()
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    Ok(confirmed.transaction.meta.map(|meta| SettlementFee {
+        signer,
+        amount: u128::from(meta.fee),
+        gas_used: meta.compute_units_consumed.unwrap_or(0),
+    }))
+}
+
 /// Create a [`ChainProvider`] from a single [`ChainConfig`] entry.
 ///
 /// Delegates to chain-family–specific builder functions, each gated behind
@@ -62,7 +174,7 @@ pub async fn build_chain_provider(config: &ChainConfig) -> Result<ChainProvider,
         #[cfg(feature = "chain-eip155")]
         ChainConfig::Eip155(config) => build_eip155_provider(config),
         #[cfg(feature = "chain-solana")]
-        ChainConfig::Solana(config) => build_solana_provider(config).await,
+        ChainConfig::Solana(config) => build_solana_provider(config),
         #[allow(unreachable_patterns)]
         _ => unreachable!("ChainConfig variant not enabled in this build"),
     }
@@ -80,7 +192,6 @@ fn build_eip155_provider(
 ) -> Result<ChainProvider, Error> {
     use alloy_network::EthereumWallet;
     use alloy_signer_local::PrivateKeySigner;
-    use url::Url;
 
     let signers: Vec<PrivateKeySigner> = config
         .inner
@@ -105,22 +216,14 @@ fn build_eip155_provider(
         wallet.register_signer(s);
     }
 
-    let endpoints: Vec<(Url, Option<u32>)> = config
-        .inner
-        .rpc
-        .iter()
-        .filter_map(|ep| Url::parse(&ep.http).ok().map(|url| (url, ep.rate_limit)))
-        .collect();
-
-    let provider = eip155::Eip155ChainProvider::new(
+    let provider = Eip155Provider::new(
         config.chain_reference,
         wallet,
-        &endpoints,
+        &config.inner.rpc,
         config.inner.eip1559,
         config.inner.flashblocks,
-        config.inner.receipt_timeout_secs,
-    )
-    .map_err(|e| Error::chain(format!("EVM provider init failed: {e}")))?;
+        Duration::from_secs(config.inner.receipt_timeout_secs),
+    )?;
 
     Ok(ChainProvider::Eip155(Arc::new(provider)))
 }
@@ -130,9 +233,9 @@ fn build_eip155_provider(
 /// # Errors
 ///
 /// Returns an error if the signer key is missing, cannot be base58-decoded,
-/// is too short, or the RPC connection fails.
+/// is too short, or an RPC endpoint is invalid.
 #[cfg(feature = "chain-solana")]
-async fn build_solana_provider(
+fn build_solana_provider(
     config: &super::config::SolanaChainConfig,
 ) -> Result<ChainProvider, Error> {
     use solana_keypair::Keypair;
@@ -158,34 +261,307 @@ async fn build_solana_provider(
                 keypair_bytes.len()
             ))
         })?;
-    let keypair = Keypair::new_from_array(secret_bytes);
-
-    let provider = solana::SolanaChainProvider::new(
-        keypair,
-        config.inner.rpc.clone(),
-        config.inner.pubsub.clone(),
+    let chain_id = config.chain_id();
+    let mut endpoints = Vec::with_capacity(config.inner.rpc.len());
+    for rpc in &config.inner.rpc {
+        endpoints.push(solana_endpoint(&chain_id, rpc)?);
+    }
+    let provider = SolanaFailoverProvider::new(
         config.chain_reference,
+        Keypair::new_from_array(secret_bytes),
         config.inner.max_compute_unit_limit,
         config.inner.max_compute_unit_price,
-    )
-    .await
-    .map_err(|e| Error::chain(format!("failed to create Solana provider: {e}")))?;
+        endpoints,
+        Duration::from_secs(config.inner.rpc_timeout_secs),
+    )?;
 
     Ok(ChainProvider::Solana(Arc::new(provider)))
 }
 
-/// Build a [`ChainRegistry`] from a [`ChainsConfig`].
+/// Build the client for a single Solana RPC endpoint, sending its headers and
+/// auth with every HTTP request and with the pubsub handshake.  With the
+/// `telemetry` feature, HTTP requests also carry the current trace context.
+///
+/// The pubsub endpoint is only connected when the first transaction is
+/// confirmed, so an unreachable `WebSocket` does not hold up startup.
+///
+/// # Errors
+///
+/// Returns an error if a header or the pubsub URL is invalid.
+#[cfg(feature = "chain-solana")]
+fn solana_endpoint(
+    chain_id: &ChainId,
+    rpc: &super::config::SolanaRpcEndpoint,
+) -> Result<SolanaEndpoint, Error> {
+    #[cfg(feature = "telemetry")]
+    use axum::http::HeaderMap;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_client::rpc_client::RpcClientConfig;
+    use solana_commitment_config::CommitmentConfig;
+    use solana_rpc_client::http_sender::HttpSender;
+
+    /// Request timeout of the upstream Solana RPC client.
+    const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+    let headers = transport::endpoint_headers(chain_id, &rpc.headers, rpc.auth.as_ref())?;
+    let mut http_headers = HttpSender::default_headers();
+    http_headers.extend(headers.clone());
+    let client = transport::http_client(
+        reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .pool_idle_timeout(HTTP_TIMEOUT),
+        http_headers,
+    )?;
+    let client = reqwest_middleware::ClientBuilder::new(client);
+    #[cfg(feature = "telemetry")]
+    let client = client.with_init(|request: reqwest_middleware::RequestBuilder| {
+        let mut headers = HeaderMap::new();
+        crate::telemetry::inject_trace_context(&mut headers);
+        request.headers(headers)
+    });
+    let rpc_client = RpcClient::new_sender(
+        HttpSender::new_with_client_with_middleware(&rpc.http, client.build()),
+        RpcClientConfig::with_commitment(CommitmentConfig::default()),
+    );
+    let pubsub = rpc
+        .pubsub
+        .as_ref()
+        .map(|url| Pubsub::new(url.clone(), headers))
+        .transpose()?;
+    Ok(SolanaEndpoint::new(rpc_client, pubsub, rpc.rate_limit))
+}
+
+/// How long a single chain took to initialise at startup.
+#[derive(Debug, Clone)]
+pub struct ChainTiming {
+    /// The chain that was initialised.
+    pub chain_id: ChainId,
+    /// Time spent building the provider, excluding time queued for a slot.
+    pub elapsed: Duration,
+    /// Whether the provider was built successfully.
+    pub ok: bool,
+}
+
+/// Providers built at startup, plus the optional chains that failed.
+#[derive(Debug, Default)]
+pub struct ChainStartup {
+    /// Providers that initialised successfully.
+    pub providers: Vec<ChainProvider>,
+    /// Optional chains that failed to initialise, with the cause.
+    pub failed: Vec<(ChainConfig, Error)>,
+    /// Per-chain initialisation time, in configuration order.
+    pub timings: Vec<ChainTiming>,
+}
+
+/// Build a provider for every chain in a [`ChainsConfig`].
+///
+/// Up to `concurrency` chains are initialised at once, and each chain gets at
+/// most `timeout` to come up.  Failures of chains marked `required = false`
+/// are collected in [`ChainStartup::failed`] instead of aborting startup.
 ///
 /// # Errors
 ///
-/// Returns an error if any chain provider fails to initialise.
-pub async fn build_chain_registry(
+/// Returns an error if a required chain fails or times out.
+pub async fn build_chain_providers(
     chains: &ChainsConfig,
-) -> Result<ChainRegistry<ChainProvider>, Error> {
-    let mut providers = HashMap::new();
-    for chain in chains.iter() {
-        let chain_provider = build_chain_provider(chain).await?;
-        providers.insert(chain_provider.chain_id(), chain_provider);
+    concurrency: usize,
+    timeout: Duration,
+) -> Result<ChainStartup, Error> {
+    let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for (index, chain) in chains.iter().cloned().enumerate() {
+        let permits = Arc::clone(&permits);
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let started = Instant::now();
+            let result = tokio::time::timeout(timeout, build_chain_provider(&chain))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(Error::chain(format!(
+                        "initialisation timed out after {}s",
+                        timeout.as_secs()
+                    )))
+                });
+            (index, chain, result, started.elapsed())
+        });
+    }
+
+    let mut outcomes = Vec::with_capacity(chains.len());
+    while let Some(joined) = tasks.join_next().await {
+        let (index, chain, result, elapsed) =
+            joined.map_err(|e| Error::chain_with("chain initialisation task failed", e))?;
+        let result = match result {
+            Err(e) if chain.required() => {
+                return Err(Error::chain_with(
+                    format!("required chain {} failed to initialise", chain.chain_id()),
+                    e,
+                ));
+            }
+            result => result,
+        };
+        outcomes.push((index, chain, result, elapsed));
+    }
+    outcomes.sort_by_key(|(index, ..)| *index);
+
+    let mut startup = ChainStartup::default();
+    for (_, chain, result, elapsed) in outcomes {
+        startup.timings.push(ChainTiming {
+            chain_id: chain.chain_id(),
+            elapsed,
+            ok: result.is_ok(),
+        });
+        match result {
+            Ok(provider) => startup.providers.push(provider),
+            Err(e) => startup.failed.push((chain, e)),
+        }
+    }
+    Ok(startup)
+}
+
+#[cfg(all(test, feature = "chain-eip155"))]
+mod tests {
+    use super::*;
+
+    fn chains(required: bool) -> ChainsConfig {
+        toml::from_str(&format!(
+            r#"
+            ["eip155:84532"]
+            rpc = [{{ http = "https://sepolia.base.org" }}]
+            required = {required}
+            "#
+        ))
+        .expect("valid chain config")
+    }
+
+    #[tokio::test]
+    async fn optional_chain_failure_is_collected() {
+        // No signers configured, so the provider cannot be built.
+        let startup = build_chain_providers(&chains(false), 4, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(startup.providers.is_empty());
+        assert_eq!(startup.failed.len(), 1);
+        assert_eq!(startup.timings.len(), 1);
+        assert!(!startup.timings[0].ok);
+    }
+
+    #[tokio::test]
+    async fn required_chain_failure_aborts() {
+        let error = build_chain_providers(&chains(true), 4, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("required chain eip155:84532"));
+    }
+
+    /// RPC requests carry the trace context of the span they are made in.
+    #[cfg(feature = "telemetry")]
+    mod trace_context {
+        use std::net::Ipv4Addr;
+        use std::sync::{Arc, Mutex};
+
+        use axum::extract::State;
+        use axum::http::HeaderMap;
+        use axum::routing::post;
+        use axum::{Json, Router};
+        use opentelemetry::global;
+        use opentelemetry::trace::{TraceContextExt, TracerProvider};
+        use opentelemetry_sdk::propagation::TraceContextPropagator;
+        use opentelemetry_sdk::trace::SdkTracerProvider;
+        use serde_json::{Value, json};
+        use tracing::Instrument;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        use super::*;
+
+        type Seen = Arc<Mutex<Vec<String>>>;
+
+        async fn rpc(
+            State(seen): State<Seen>,
+            headers: HeaderMap,
+            Json(request): Json<Value>,
+        ) -> Json<Value> {
+            if let Some(traceparent) = headers.get("traceparent") {
+                let traceparent = traceparent.to_str().expect("ASCII traceparent");
+                seen.lock().expect("lock").push(traceparent.to_owned());
+            }
+            Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": null }))
+        }
+
+        /// Start a JSON-RPC server answering `null` and recording the
+        /// `traceparent` of every request.
+        async fn mock_rpc() -> (String, Seen) {
+            let seen = Seen::default();
+            let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+                .await
+                .expect("bind mock RPC");
+            let url = format!("http://{}/", listener.local_addr().expect("local address"));
+            let app = Router::new()
+                .route("/", post(rpc))
+                .with_state(Arc::clone(&seen));
+            tokio::spawn(async move { axum::serve(listener, app).await });
+            (url, seen)
+        }
+
+        /// Look up a settlement fee inside a traced span and return the
+        /// span's trace ID with the `traceparent` headers the RPC received.
+        async fn traced_lookup(chain: &str, transaction: &str) -> (String, Vec<String>) {
+            global::set_text_map_propagator(TraceContextPropagator::new());
+            let tracer = SdkTracerProvider::builder().build().tracer("test");
+            let subscriber = tracing_subscriber::registry()
+                .with(tracing_opentelemetry::layer().with_tracer(tracer));
+            let _guard = tracing::subscriber::set_default(subscriber);
+
+            let (url, seen) = mock_rpc().await;
+            let config: ChainsConfig =
+                toml::from_str(&chain.replace("RPC_URL", &url)).expect("valid chain config");
+            let chain = config.iter().next().expect("one chain");
+            let provider = build_chain_provider(chain).await.expect("provider");
+            let span = tracing::info_span!("settle");
+            let trace_id = span.context().span().span_context().trace_id().to_string();
+            let _ = provider.settlement_fee(transaction).instrument(span).await;
+            let seen = seen.lock().expect("lock").clone();
+            (trace_id, seen)
+        }
+
+        #[tokio::test]
+        async fn eip155_requests_carry_trace_context() {
+            let (trace_id, seen) = traced_lookup(
+                r#"
+                ["eip155:84532"]
+                rpc = [{ http = "RPC_URL" }]
+                signers = ["0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"]
+                "#,
+                &format!("0x{}", "11".repeat(32)),
+            )
+            .await;
+            assert!(!seen.is_empty());
+            assert!(
+                seen.iter()
+                    .all(|traceparent| traceparent.contains(&trace_id))
+            );
+        }
+
+        #[cfg(feature = "chain-solana")]
+        #[tokio::test]
+        async fn solana_requests_carry_trace_context() {
+            let signer = bs58::encode(solana_keypair::Keypair::new().to_bytes()).into_string();
+            let (trace_id, seen) = traced_lookup(
+                &format!(
+                    r#"
+                    ["solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"]
+                    rpc = [{{ http = "RPC_URL" }}]
+                    signer = "{signer}"
+                    "#
+                ),
+                &solana_signature::Signature::default().to_string(),
+            )
+            .await;
+            assert!(!seen.is_empty());
+            assert!(
+                seen.iter()
+                    .all(|traceparent| traceparent.contains(&trace_id))
+            );
+        }
     }
-    Ok(ChainRegistry::new(providers))
 }
@@ -1,18 +1,68 @@
-//! [`SchemeBuilder`] implementations bridging [`ChainProvider`] to scheme handlers.
+//! [`SchemeBuilder`] implementations bridging [`ChainProvider`] to scheme handlers,
+//! and per-chain [`SchemeRegistry`] construction from the configured schemes.
 
 #[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
 use std::sync::Arc;
 
+use r402::chain::ChainProvider as ChainProviderTrait;
 #[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
 use r402::facilitator::Facilitator;
 #[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
 use r402::scheme::SchemeBuilder;
+use r402::scheme::SchemeRegistry;
 #[cfg(feature = "chain-eip155")]
 use r402_evm::Eip155Exact;
 #[cfg(feature = "chain-solana")]
 use r402_svm::SolanaExact;
 
 use super::ChainProvider;
+use crate::config::SchemeEntry;
+
+/// Build a [`SchemeRegistry`] with every scheme in `schemes` whose chain
+/// pattern matches `provider`.
+///
+/// Schemes that fail to build are skipped with a warning.
+pub fn build_scheme_registry(provider: &ChainProvider, schemes: &[SchemeEntry]) -> SchemeRegistry {
+    #[allow(unused_mut)]
+    let mut scheme_registry = SchemeRegistry::new();
+    let chain_id = provider.chain_id();
+    for scheme_entry in schemes
+        .iter()
+        .filter(|entry| entry.chains.matches(&chain_id))
+    {
+        let namespace = chain_id.namespace();
+        #[allow(unused_variables)]
+        let result: Result<(), Box<dyn std::error::Error>> = match namespace {
+            #[cfg(feature = "chain-eip155")]
+            "eip155" => {
+                scheme_registry.register(&Eip155Exact, provider, scheme_entry.config.clone())
+            }
+            #[cfg(feature = "chain-solana")]
+            "solana" => {
+                scheme_registry.register(&SolanaExact, provider, scheme_entry.config.clone())
+            }
+            _ => {
+                tracing::warn!(
+                    namespace,
+                    chain = %chain_id,
+                    scheme = %scheme_entry.id,
+                    "Skipping unsupported namespace"
+                );
+                Ok(())
+            }
+        };
+        #[allow(unreachable_code)]
+        if let Err(e) = result {
+            tracing::warn!(
+                chain = %chain_id,
+                scheme = %scheme_entry.id,
+                error = %e,
+                "Failed to register scheme handler"
+            );
+        }
+    }
+    scheme_registry
+}
 
 #[cfg(feature = "chain-eip155")]
 impl SchemeBuilder<&ChainProvider> for Eip155Exact {
@@ -25,7 +75,7 @@ fn build(
         let eip155_provider = if let ChainProvider::Eip155(provider) = provider {
             Arc::clone(provider)
         } else {
-            return Err("Eip155Exact::build: provider must be an Eip155ChainProvider".into());
+            return Err("Eip155Exact::build: provider must be an Eip155Provider".into());
         };
         self.build(eip155_provider, config)
     }
@@ -42,7 +92,7 @@ fn build(
         let solana_provider = if let ChainProvider::Solana(provider) = provider {
             Arc::clone(provider)
         } else {
-            return Err("SolanaExact::build: provider must be a SolanaChainProvider".into());
+            return Err("SolanaExact::build: provider must be a SolanaFailoverProvider".into());
         };
         self.build(solana_provider, config)
     }
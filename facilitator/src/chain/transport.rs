@@ -0,0 +1,179 @@
+//! HTTP clients for RPC endpoints with configured headers and authentication.
+//!
+//! Each endpoint that sets `headers` or `auth` gets its own
+//! [`reqwest::Client`] carrying them as default headers, and the chain
+//! providers send every JSON-RPC request through that client.  Credentials
+//! therefore never appear in an RPC URL or in the providers' log output.
+
+use axum::http::header::AUTHORIZATION;
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use r402::chain::ChainId;
+
+use super::config::{RpcAuth, RpcHeaders};
+use crate::error::Error;
+
+/// Build the headers sent with every request to an endpoint.
+///
+/// All values are marked sensitive so they are never printed by [`Debug`].
+///
+/// # Errors
+///
+/// Returns an error if a header name or value is invalid.
+pub fn endpoint_headers(
+    chain: &ChainId,
+    headers: &RpcHeaders,
+    auth: Option<&RpcAuth>,
+) -> Result<HeaderMap, Error> {
+    let mut map = HeaderMap::with_capacity(headers.0.len() + 1);
+    for (name, value) in &headers.0 {
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+            Error::chain_with(
+                format!("invalid RPC header name '{name}' for chain {chain}"),
+                e,
+            )
+        })?;
+        let header_value = sensitive(value).map_err(|e| {
+            Error::chain_with(
+                format!("invalid value for RPC header '{name}' on chain {chain}"),
+                e,
+            )
+        })?;
+        map.insert(header_name, header_value);
+    }
+    if let Some(auth) = auth {
+        let credentials = match auth {
+            RpcAuth::Basic { username, password } => {
+                format!(
+                    "Basic {}",
+                    STANDARD.encode(format!("{username}:{password}"))
+                )
+            }
+            RpcAuth::Bearer(token) => format!("Bearer {token}"),
+        };
+        let value = sensitive(&credentials)
+            .map_err(|e| Error::chain_with(format!("invalid RPC auth for chain {chain}"), e))?;
+        map.insert(AUTHORIZATION, value);
+    }
+    Ok(map)
+}
+
+fn sensitive(value: &str) -> Result<HeaderValue, axum::http::header::InvalidHeaderValue> {
+    let mut value = HeaderValue::from_str(value)?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// Build an HTTP client sending `headers` with every request.
+///
+/// # Errors
+///
+/// Returns an error if the client cannot be built.
+pub fn http_client(
+    builder: reqwest::ClientBuilder,
+    headers: HeaderMap,
+) -> Result<reqwest::Client, Error> {
+    builder
+        .default_headers(headers)
+        .build()
+        .map_err(|e| Error::chain_with("failed to build RPC HTTP client", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::net::Ipv4Addr;
+
+    use axum::Router;
+    use axum::routing::post;
+
+    use super::*;
+
+    fn chain() -> ChainId {
+        ChainId::new("eip155", "84532")
+    }
+
+    fn headers(entries: &[(&str, &str)]) -> RpcHeaders {
+        RpcHeaders(
+            entries
+                .iter()
+                .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+                .collect::<BTreeMap<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn invalid_header_name_errors() {
+        let err = endpoint_headers(&chain(), &headers(&[("bad header", "x")]), None).unwrap_err();
+        assert!(err.to_string().contains("invalid RPC header name"));
+    }
+
+    #[test]
+    fn header_values_are_sensitive() {
+        let auth = RpcAuth::Basic {
+            username: "user".into(),
+            password: "hunter2".into(),
+        };
+        let map =
+            endpoint_headers(&chain(), &headers(&[("x-api-key", "secret")]), Some(&auth)).unwrap();
+        let value = map.get("x-api-key").unwrap();
+        assert_eq!(value, "secret");
+        assert!(value.is_sensitive());
+        let authorization = map.get(AUTHORIZATION).unwrap();
+        assert_eq!(authorization, "Basic dXNlcjpodW50ZXIy");
+        assert!(authorization.is_sensitive());
+    }
+
+    #[test]
+    fn auth_debug_is_redacted() {
+        let auth = RpcAuth::Basic {
+            username: "user".into(),
+            password: "hunter2".into(),
+        };
+        let rendered = format!("{auth:?} {:?}", RpcAuth::Bearer("token".into()));
+        assert!(!rendered.contains("hunter2"));
+        assert!(!rendered.contains("\"token\""));
+        let rendered = format!("{:?}", headers(&[("x-api-key", "secret")]));
+        assert!(rendered.contains("x-api-key"));
+        assert!(!rendered.contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn client_sends_headers_and_auth() {
+        async fn echo(headers: HeaderMap) -> String {
+            let header = |name: &str| {
+                headers
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_owned()
+            };
+            format!("{}|{}", header("x-api-key"), header("authorization"))
+        }
+        let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/rpc", post(echo)))
+                .await
+                .unwrap();
+        });
+
+        let map = endpoint_headers(
+            &chain(),
+            &headers(&[("x-api-key", "secret")]),
+            Some(&RpcAuth::Bearer("token".into())),
+        )
+        .unwrap();
+        let client = http_client(reqwest::Client::builder(), map).unwrap();
+        let response = client
+            .post(format!("http://{addr}/rpc"))
+            .body("{}")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "secret|Bearer token");
+    }
+}
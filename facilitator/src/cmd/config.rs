@@ -0,0 +1,43 @@
+//! `facilitator config` — inspect the configuration.
+
+use std::io::Write as _;
+use std::path::Path;
+
+use dotenvy::dotenv;
+
+use super::ShowFormat;
+use crate::config::{config_schema, load_config_with_provenance, render_json, render_toml};
+use crate::error::Error;
+
+/// Print the effective configuration, as `serve` would load it, with the
+/// source of each value and secrets redacted.
+///
+/// # Errors
+///
+/// Returns an error if the configuration cannot be loaded or written.
+pub fn show(
+    config_path: Option<&Path>,
+    config_dir: Option<&Path>,
+    format: ShowFormat,
+) -> Result<(), Error> {
+    dotenv().ok();
+    let (config, provenance) = load_config_with_provenance(config_path, config_dir)?;
+    let rendered = match format {
+        ShowFormat::Toml => render_toml(&config, &provenance)?,
+        ShowFormat::Json => render_json(&config, &provenance)?,
+    };
+    write!(std::io::stdout().lock(), "{rendered}")
+        .map_err(|e| Error::config_with("failed to write config", e))
+}
+
+/// Print the JSON Schema of the configuration file to stdout.
+///
+/// # Errors
+///
+/// Returns an error if the schema cannot be serialised or written.
+pub fn schema() -> Result<(), Error> {
+    let schema = serde_json::to_string_pretty(&config_schema())
+        .map_err(|e| Error::config_with("failed to serialize config schema", e))?;
+    writeln!(std::io::stdout().lock(), "{schema}")
+        .map_err(|e| Error::config_with("failed to write config schema", e))
+}
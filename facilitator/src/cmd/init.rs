@@ -3,19 +3,66 @@
 use std::fs;
 use std::path::Path;
 
+use crate::chain::catalog::{self, Network};
 use crate::error::Error;
 
+/// Server settings and the start of the `[signers]` table, shared by every
+/// generated configuration.
+const HEADER: &str = r#"# x402 Facilitator Configuration
+# https://www.x402.org
+
+# Server bind address and port.
+# Can also be set via HOST / PORT environment variables.
+host = "0.0.0.0"
+port = 8080
+
+# Log level filter (RUST_LOG env var takes precedence when set).
+# Examples: "info", "debug", "facilitator=debug,r402=trace"
+log_level = "info"
+
+# Log line format: "full", "compact", "pretty", "json" or "logfmt"; the
+# last two suit log pipelines. Logs go to stdout unless log_output = "stderr".
+log_format = "full"
+log_output = "stdout"
+
+# Chains are initialised in parallel at startup; a chain that takes longer
+# than startup_timeout_secs fails (or is retried later if it is optional).
+startup_concurrency = 8
+startup_timeout_secs = 30
+
+# Unknown keys (usually typos) fail startup with a suggestion; set to "warn"
+# to log them instead.
+unknown_keys = "error"
+
+# Chains of a family this build does not include (see the chain-* cargo
+# features) are skipped with a warning; set to true to fail startup instead.
+strict = false
+
+# Global Signers
+#
+# Shared across all chains of the same type.
+# Per-chain overrides are still possible (add `signers` / `signer` to
+# the individual chain table).
+#
+# Use environment variable references ($VAR or ${VAR}) for secrets.
+# Any string in this file may reference the environment, e.g.
+# "${VAR:-default}" or "${VAR:?error message}".
+
+[signers]
+"#;
+
 /// Execute the `init` command.
 ///
-/// Writes a default TOML configuration template to `output`. Refuses to
-/// overwrite an existing file unless `force` is `true`.
+/// Writes a default TOML configuration template to `output`, or, when
+/// `networks` is given, a configuration for exactly those catalog networks.
+/// Refuses to overwrite an existing file unless `force` is `true`.
 ///
 /// # Errors
 ///
-/// Returns an error if the file already exists (without `--force`) or if
-/// writing fails.
+/// Returns an error if the file already exists (without `--force`), a
+/// network is unknown or not compiled in, or writing fails.
 #[allow(clippy::print_stderr)]
-pub fn run(output: &Path, force: bool) -> Result<(), Error> {
+pub fn run(output: &Path, force: bool, networks: Option<&[String]>) -> Result<(), Error> {
     if output.exists() && !force {
         return Err(Error::config(format!(
             "'{}' already exists, use --force to overwrite",
@@ -23,7 +70,10 @@ pub fn run(output: &Path, force: bool) -> Result<(), Error> {
         )));
     }
 
-    let content = generate_default_config();
+    let content = match networks {
+        Some(networks) => generate_network_config(networks)?,
+        None => generate_default_config(),
+    };
     fs::write(output, content)
         .map_err(|e| Error::config_with(format!("failed to write '{}'", output.display()), e))?;
 
@@ -38,30 +88,7 @@ pub fn run(output: &Path, force: bool) -> Result<(), Error> {
 /// and environment variable resolution.
 #[must_use]
 fn generate_default_config() -> String {
-    let mut config = String::from(
-        r#"# x402 Facilitator Configuration
-# https://www.x402.org
-
-# Server bind address and port.
-# Can also be set via HOST / PORT environment variables.
-host = "0.0.0.0"
-port = 8080
-
-# Log level filter (RUST_LOG env var takes precedence when set).
-# Examples: "info", "debug", "facilitator=debug,r402=trace"
-log_level = "info"
-
-# Global Signers
-#
-# Shared across all chains of the same type.
-# Per-chain overrides are still possible (add `signers` / `signer` to
-# the individual chain table).
-#
-# Use environment variable references ($VAR or ${VAR}) for secrets.
-
-[signers]
-"#,
-    );
+    let mut config = String::from(HEADER);
 
     #[cfg(feature = "chain-eip155")]
     config.push_str(
@@ -82,6 +109,13 @@ fn generate_default_config() -> String {
 #
 # Key format: "eip155:<chain_id>"
 # Only RPC config is needed; signers are injected from [signers] above.
+# Settings shared by every EVM chain can go in [defaults.eip155]; a chain's
+# own keys take precedence.
+#
+# [defaults.eip155]
+# receipt_timeout_secs = 30
+# [defaults.eip155.rpc]
+# rate_limit = 25
 
 [chains."eip155:84532"]
 rpc = [{ http = "https://sepolia.base.org" }]
@@ -94,12 +128,63 @@ fn generate_default_config() -> String {
 # Solana chains
 #
 # Key format: "solana:<genesis_hash>"
+# Settings shared by every Solana chain can go in [defaults.solana].
+#
+# [defaults.solana]
+# max_compute_unit_price = 1000000
 
 [chains."solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"]
 rpc = "https://api.devnet.solana.com"
 "#,
     );
 
+    #[cfg(feature = "telemetry")]
+    config.push_str(
+        r#"
+# OpenTelemetry export (optional)
+#
+# Setting an endpoint enables OTLP export of traces, metrics and logs. The standard
+# OTEL_* environment variables take precedence over these keys.
+#
+# [telemetry]
+# endpoint = "http://localhost:4318"
+# protocol = "http"            # or "grpc"
+# sampling_ratio = 1.0
+# export_interval_secs = 30
+# stdout_metrics = true
+"#,
+    );
+
+    config.push_str(
+        r#"
+# Audit log (optional)
+#
+# Appends one JSON line per /verify call, and an "attempted" and an outcome
+# line per /settle call. Files rotate on size and/or time; hash_chain links
+# lines with SHA-256 for tamper evidence; sync flushes each line to disk.
+#
+# [audit]
+# path = "audit.jsonl"
+# max_size_mb = 100
+# rotation = "daily"           # or "hourly", "never"
+# hash_chain = true
+# sync = true
+"#,
+    );
+
+    config.push_str(
+        r#"
+# Payer privacy (optional)
+#
+# Record payer addresses in spans, logs and the audit log as HMAC-SHA256
+# hashes keyed by payer_hash_key instead of in plain text.
+#
+# [privacy]
+# hash_payers = true
+# payer_hash_key = "${PAYER_HASH_KEY:?set a secret payer hash key}"
+"#,
+    );
+
     config.push_str(
         r#"
 # Scheme registrations (optional)
@@ -118,6 +203,83 @@ fn generate_default_config() -> String {
     config
 }
 
+/// Generate a configuration for the given catalog networks.
+///
+/// Chains are keyed by network name, so they inherit the catalog's gas
+/// settings; the public RPC endpoint is written out to make it easy to swap.
+fn generate_network_config(names: &[String]) -> Result<String, Error> {
+    let mut networks: Vec<&Network> = Vec::new();
+    for name in names {
+        let network = catalog::network(name.trim()).ok_or_else(|| {
+            Error::config(format!(
+                "unknown network '{name}'; known networks: {}",
+                catalog::NETWORKS
+                    .iter()
+                    .map(|network| network.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })?;
+        if !namespace_enabled(network.namespace()) {
+            return Err(Error::config(format!(
+                "network '{}' is not supported by this build (missing chain feature for '{}')",
+                network.name,
+                network.namespace()
+            )));
+        }
+        if !networks.contains(&network) {
+            networks.push(network);
+        }
+    }
+    if networks.is_empty() {
+        return Err(Error::config("--networks needs at least one network"));
+    }
+
+    let mut config = String::from(HEADER);
+    let has = |namespace: &str| {
+        networks
+            .iter()
+            .any(|network| network.namespace() == namespace)
+    };
+    if has("eip155") {
+        config.push_str("evm = [\"$EVM_SIGNER_PRIVATE_KEY\"]       # hex, 0x-prefixed\n");
+    }
+    if has("solana") {
+        config.push_str("solana = \"$SOLANA_SIGNER_PRIVATE_KEY\"    # base58, 64-byte keypair\n");
+    }
+    config.push_str(
+        "\n# Chains from the built-in catalog, keyed by network name.\n\
+         # The public RPC endpoints are rate-limited; replace them for production.\n",
+    );
+    for network in networks {
+        let rpc = if network.namespace() == "eip155" {
+            format!("[{{ http = \"{}\" }}]", network.rpc)
+        } else {
+            format!("\"{}\"", network.rpc)
+        };
+        for part in [
+            "\n# ",
+            network.name,
+            " (",
+            network.chain_id,
+            ")\n[chains.",
+            network.name,
+            "]\nrpc = ",
+            &rpc,
+            "\n",
+        ] {
+            config.push_str(part);
+        }
+    }
+    Ok(config)
+}
+
+/// Whether chains of `namespace` are supported by this build.
+const fn namespace_enabled(namespace: &str) -> bool {
+    matches!(namespace.as_bytes(), b"eip155" if cfg!(feature = "chain-eip155"))
+        || matches!(namespace.as_bytes(), b"solana" if cfg!(feature = "chain-solana"))
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
@@ -139,4 +301,27 @@ fn generate_default_config_has_required_fields() {
         assert!(doc.contains_key("port"));
         assert!(doc.contains_key("signers"));
     }
+
+    #[cfg(all(feature = "chain-eip155", feature = "chain-solana"))]
+    #[test]
+    fn generate_network_config_uses_catalog() {
+        let networks = vec!["base".to_owned(), "solana-devnet".to_owned()];
+        let config_str = generate_network_config(&networks).unwrap();
+        let doc: BTreeMap<String, toml::Value> = toml::from_str(&config_str).unwrap();
+        let chains = doc["chains"].as_table().unwrap();
+        assert_eq!(
+            chains["base"]["rpc"][0]["http"].as_str(),
+            Some("https://mainnet.base.org")
+        );
+        assert!(chains.contains_key("solana-devnet"));
+        assert!(doc["signers"].get("evm").is_some());
+        assert!(doc["signers"].get("solana").is_some());
+    }
+
+    #[test]
+    fn generate_network_config_rejects_unknown() {
+        let err = generate_network_config(&["basee".to_owned()]).unwrap_err();
+        assert!(err.to_string().contains("unknown network 'basee'"));
+        assert!(generate_network_config(&[]).is_err());
+    }
 }
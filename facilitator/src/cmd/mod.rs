@@ -2,8 +2,9 @@
 
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
+pub mod config;
 pub mod init;
 pub mod serve;
 
@@ -29,12 +30,66 @@ pub enum Commands {
         /// Overwrite the file if it already exists.
         #[arg(long, default_value_t = false)]
         force: bool,
+
+        /// Only configure these networks from the built-in catalog
+        /// (e.g. `base,solana-devnet`).
+        #[arg(long, value_delimiter = ',')]
+        networks: Option<Vec<String>>,
     },
 
     /// Start the facilitator HTTP server.
     Serve {
-        /// Path to the TOML configuration file.
-        #[arg(short, long, env = "CONFIG", default_value = "config.toml")]
-        config: PathBuf,
+        /// Where to read the configuration from.
+        #[command(flatten)]
+        source: ConfigSource,
+    },
+
+    /// Inspect the configuration.
+    Config {
+        /// Configuration subcommand to execute.
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+/// Where the configuration is read from.
+#[derive(Debug, Args)]
+pub struct ConfigSource {
+    /// Path to the TOML configuration file [default: config.toml, if present].
+    ///
+    /// Any key can also be set through `FACILITATOR__*` environment
+    /// variables, which take precedence over the file.
+    #[arg(short, long, env = "CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Directory of additional TOML files, merged in name order.
+    #[arg(long, env = "CONFIG_DIR")]
+    pub config_dir: Option<PathBuf>,
+}
+
+/// `config` subcommands.
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the JSON Schema of the configuration file.
+    Schema,
+
+    /// Print the effective configuration, with the source of each value.
+    Show {
+        /// Where to read the configuration from.
+        #[command(flatten)]
+        source: ConfigSource,
+
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = ShowFormat::Toml)]
+        format: ShowFormat,
     },
 }
+
+/// Output format of `config show`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ShowFormat {
+    /// TOML, with each value's source as a trailing comment.
+    Toml,
+    /// JSON, with sources in a separate map.
+    Json,
+}
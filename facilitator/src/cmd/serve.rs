@@ -6,26 +6,32 @@
 use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use axum::Router;
 use axum::extract::DefaultBodyLimit;
 use axum::http::{Method, StatusCode};
 use dotenvy::dotenv;
-use r402::chain::ChainProvider as ChainProviderTrait;
 use r402::hooks::HookedFacilitator;
-use r402::scheme::SchemeRegistry;
-#[cfg(feature = "chain-eip155")]
-use r402_evm::Eip155Exact;
-#[cfg(feature = "chain-solana")]
-use r402_svm::SolanaExact;
 use tower_http::cors;
 use tower_http::timeout::TimeoutLayer;
+#[cfg(not(feature = "telemetry"))]
+use tower_http::trace::TraceLayer;
 
-use crate::chain::build_chain_registry;
+use crate::audit::AuditLog;
+use crate::breaker::{CircuitBreakerFacilitator, CircuitBreakers};
+use crate::budget::{GasBudgetFacilitator, GasBudgets};
+use crate::chain::{LiveChains, build_chain_providers, build_scheme_registry, spawn_chain_retry};
 use crate::config::load_config;
 use crate::error::Error;
-use crate::routes::{self, FacilitatorState};
+#[cfg(not(feature = "telemetry"))]
+use crate::logging;
+#[cfg(feature = "telemetry")]
+use crate::metrics::{Metrics, MetricsFacilitator};
+#[cfg(not(feature = "telemetry"))]
+use crate::request_id::RequestSpan;
+use crate::request_id::{self, X_REQUEST_ID};
+use crate::routes::{self, AppState, PayerRecording};
 #[cfg(feature = "telemetry")]
 use crate::telemetry::Telemetry;
 
@@ -40,7 +46,7 @@
 ///
 /// Panics if the rustls crypto provider cannot be installed.
 #[allow(clippy::cognitive_complexity, clippy::future_not_send)]
-pub async fn run(config_path: &Path) -> Result<(), Error> {
+pub async fn run(config_path: Option<&Path>, config_dir: Option<&Path>) -> Result<(), Error> {
     // Initialize rustls crypto provider (ring)
     rustls::crypto::CryptoProvider::install_default(rustls::crypto::ring::default_provider())
         .expect("Failed to initialize rustls crypto provider");
@@ -48,97 +54,156 @@ pub async fn run(config_path: &Path) -> Result<(), Error> {
     // Load .env variables
     dotenv().ok();
 
-    let config = load_config(config_path)?;
+    let config = load_config(config_path, config_dir)?;
 
     #[cfg(feature = "telemetry")]
     let telemetry_guard = Telemetry::new()
         .with_name(env!("CARGO_PKG_NAME"))
         .with_version(env!("CARGO_PKG_VERSION"))
         .with_log_level(config.log_level())
-        .register();
+        .with_log_format(config.log_format(), config.log_output())
+        .with_config(config.telemetry().clone())
+        .register()?;
     #[cfg(feature = "telemetry")]
     let telemetry_layer = telemetry_guard.http_tracing();
-
-    let chain_registry = build_chain_registry(config.chains()).await?;
-
-    // Build scheme registry by registering blueprints for each configured scheme.
-    #[allow(unused_mut)]
-    let mut scheme_registry = SchemeRegistry::new();
-    for scheme_entry in config.schemes() {
-        let matching_providers = chain_registry.by_chain_id_pattern(&scheme_entry.chains);
-        for provider in matching_providers {
-            let chain_id = provider.chain_id();
-            let namespace = chain_id.namespace();
-            #[allow(unused_variables)]
-            let result: Result<(), Box<dyn std::error::Error>> = match namespace {
-                #[cfg(feature = "chain-eip155")]
-                "eip155" => {
-                    scheme_registry.register(&Eip155Exact, provider, scheme_entry.config.clone())
-                }
-                #[cfg(feature = "chain-solana")]
-                "solana" => {
-                    scheme_registry.register(&SolanaExact, provider, scheme_entry.config.clone())
-                }
-                _ => {
-                    #[cfg(feature = "telemetry")]
-                    tracing::warn!(
-                        namespace,
-                        chain = %chain_id,
-                        scheme = %scheme_entry.id,
-                        "Skipping unsupported namespace"
-                    );
-                    Ok(())
-                }
-            };
-            #[allow(unreachable_code)]
-            if let Err(e) = result {
-                #[cfg(feature = "telemetry")]
-                tracing::warn!(
-                    chain = %chain_id,
-                    scheme = %scheme_entry.id,
-                    error = %e,
-                    "Failed to register scheme handler"
-                );
-            }
+    #[cfg(not(feature = "telemetry"))]
+    {
+        logging::init(config.log_level(), config.log_format(), config.log_output());
+        if config.telemetry().endpoint.is_some() {
+            tracing::warn!(
+                "Ignoring [telemetry]: this build does not include the 'telemetry' feature"
+            );
         }
     }
 
+    for warning in config.warnings() {
+        tracing::warn!("{warning}");
+    }
+
+    let started = Instant::now();
+    let startup = build_chain_providers(
+        config.chains(),
+        config.startup_concurrency(),
+        config.startup_timeout(),
+    )
+    .await?;
+    for timing in &startup.timings {
+        tracing::info!(
+            chain = %timing.chain_id,
+            elapsed_ms = timing.elapsed.as_millis(),
+            ok = timing.ok,
+            "Chain startup"
+        );
+    }
+    tracing::info!(
+        live = startup.providers.len(),
+        failed = startup.failed.len(),
+        elapsed_ms = started.elapsed().as_millis(),
+        "Chain initialisation complete"
+    );
+
+    // Register scheme handlers for every chain that came up; optional chains
+    // that failed are retried in the background and join once they are live.
+    let live_chains = Arc::new(LiveChains::default());
+    for provider in startup.providers {
+        let scheme_registry = build_scheme_registry(&provider, config.schemes());
+        live_chains.insert(provider, scheme_registry);
+    }
+    for (chain, error) in startup.failed {
+        tracing::warn!(
+            chain = %chain.chain_id(),
+            error = %error,
+            "Optional chain failed to initialise, retrying in the background"
+        );
+        spawn_chain_retry(Arc::clone(&live_chains), chain, config.schemes().to_vec());
+    }
+
+    #[cfg(feature = "telemetry")]
+    let metrics = Metrics::new(&opentelemetry::global::meter(env!("CARGO_PKG_NAME")));
+
+    // Fail fast on chains whose RPC keeps failing.
+    let breakers = Arc::new(CircuitBreakers::from_config(config.chains()));
+    let facilitator =
+        CircuitBreakerFacilitator::new(Arc::clone(&live_chains), Arc::clone(&breakers));
+    #[cfg(feature = "telemetry")]
+    let facilitator = facilitator.with_observer(metrics.circuit_observer());
+
+    // Enforce per-chain gas budgets on settlement.  The fee of each
+    // settlement is read there once and shared with the metrics.
+    let gas_budgets = Arc::new(GasBudgets::from_config(config.chains()));
+    let facilitator = GasBudgetFacilitator::new(facilitator, gas_budgets, live_chains);
+
+    // Record verification and settlement outcomes.
+    #[cfg(feature = "telemetry")]
+    let facilitator = {
+        let facilitator = if telemetry_guard.metrics_enabled() {
+            facilitator.with_fee_observer(metrics.fee_observer())
+        } else {
+            facilitator
+        };
+        let networks = config
+            .chains()
+            .iter()
+            .map(crate::chain::ChainConfig::chain_id)
+            .collect();
+        MetricsFacilitator::new(facilitator, metrics, networks)
+    };
+
     // Wrap with HookedFacilitator to enable lifecycle hooks.
-    // SchemeRegistry implements Facilitator directly — no wrapper needed.
-    let facilitator = HookedFacilitator::new(scheme_registry);
+    let facilitator = HookedFacilitator::new(facilitator);
+
+    let audit = AuditLog::open(config.audit())?.map(Arc::new);
 
-    let axum_state: FacilitatorState = Arc::new(facilitator);
+    let axum_state = AppState {
+        facilitator: Arc::new(facilitator),
+        breakers,
+        payers: match config.privacy().payer_hash_key.as_deref() {
+            Some(key) if config.privacy().hash_payers => PayerRecording::hashed(key),
+            _ => PayerRecording::Plain,
+        },
+        audit: audit.clone(),
+    };
 
-    let http_endpoints = Router::new().merge(routes::routes().with_state(Arc::clone(&axum_state)));
+    let http_endpoints = Router::new().merge(routes::routes().with_state(axum_state));
     #[cfg(feature = "telemetry")]
     let http_endpoints = http_endpoints.layer(telemetry_layer);
+    #[cfg(not(feature = "telemetry"))]
+    let http_endpoints =
+        http_endpoints.layer(TraceLayer::new_for_http().make_span_with(RequestSpan));
     let http_endpoints = http_endpoints
         .layer(
             cors::CorsLayer::new()
                 .allow_origin(cors::Any)
                 .allow_methods([Method::GET, Method::POST])
-                .allow_headers(cors::Any),
+                .allow_headers(cors::Any)
+                .expose_headers([X_REQUEST_ID]),
         )
         .layer(DefaultBodyLimit::max(64 * 1024))
         .layer(TimeoutLayer::with_status_code(
             StatusCode::GATEWAY_TIMEOUT,
             Duration::from_secs(45),
         ));
+    let http_endpoints = request_id::layer(http_endpoints);
 
     let addr = SocketAddr::new(config.host(), config.port());
-    #[cfg(feature = "telemetry")]
     tracing::info!("Starting server at http://{}", addr);
 
-    let listener = tokio::net::TcpListener::bind(addr).await;
-    #[cfg(feature = "telemetry")]
-    let listener = listener.inspect_err(|e| tracing::error!("Failed to bind to {}: {}", addr, e));
-    let listener = listener.map_err(|e| Error::server_with("failed to bind", e))?;
-
-    axum::serve(listener, http_endpoints)
-        .with_graceful_shutdown(shutdown_signal())
+    let listener = tokio::net::TcpListener::bind(addr)
         .await
-        .map_err(|e| Error::server_with("server error", e))?;
-
+        .inspect_err(|e| tracing::error!("Failed to bind to {}: {}", addr, e))
+        .map_err(|e| Error::server_with("failed to bind", e))?;
+
+    axum::serve(
+        listener,
+        http_endpoints.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .map_err(|e| Error::server_with("server error", e))?;
+
+    if let Some(audit) = audit {
+        audit.flush().await;
+    }
     Ok(())
 }
 
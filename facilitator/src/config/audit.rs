@@ -0,0 +1,92 @@
+//! The `[audit]` section: the append-only audit log of payment operations.
+//!
+//! Setting `path` enables the log; see [`crate::audit`] for the line format,
+//! rotation and hash chaining.
+
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How often the audit log starts a new file regardless of its size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditRotation {
+    /// Only rotate on size (default).
+    #[default]
+    Never,
+    /// Start a new file every hour (UTC).
+    Hourly,
+    /// Start a new file every day (UTC).
+    Daily,
+}
+
+/// Audit log settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct AuditConfig {
+    /// File the audit log is appended to, e.g. `"/var/log/facilitator/audit.jsonl"`;
+    /// setting it enables the audit log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Rotate the file once it would grow past this many megabytes
+    /// (default: no size limit).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 1))]
+    pub max_size_mb: Option<u64>,
+    /// Time-based rotation: never, hourly or daily (default: never).
+    #[serde(default)]
+    pub rotation: AuditRotation,
+    /// Chain lines together with SHA-256 hashes so that edits, deletions
+    /// and reordering can be detected (default: false).
+    #[serde(default)]
+    pub hash_chain: bool,
+    /// Flush every line to disk (`fdatasync`) before writing the next one,
+    /// so a crash cannot lose lines already recorded (default: true).
+    #[serde(default = "default_sync")]
+    pub sync: bool,
+}
+
+const fn default_sync() -> bool {
+    true
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            max_size_mb: None,
+            rotation: AuditRotation::default(),
+            hash_chain: false,
+            sync: default_sync(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn section_is_parsed() {
+        let config: AuditConfig = toml::from_str(
+            r#"
+            path = "audit.jsonl"
+            max_size_mb = 100
+            rotation = "daily"
+            hash_chain = true
+            sync = false
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.path, Some(PathBuf::from("audit.jsonl")));
+        assert_eq!(config.max_size_mb, Some(100));
+        assert_eq!(config.rotation, AuditRotation::Daily);
+        assert!(config.hash_chain);
+        assert!(!config.sync);
+
+        let config: AuditConfig = toml::from_str("").unwrap();
+        assert_eq!(config, AuditConfig::default());
+        assert!(config.sync);
+    }
+}
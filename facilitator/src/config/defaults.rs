@@ -0,0 +1,306 @@
+//! Per-family chain defaults.
+//!
+//! `[defaults.eip155]` and `[defaults.solana]` hold chain settings shared by
+//! every chain of that family, so `receipt_timeout_secs` or the compute-unit
+//! caps need not be repeated per chain.  Each key is copied into a chain
+//! table that does not set it itself.  `[defaults.<family>.rpc]` holds
+//! endpoint settings such as `rate_limit` or `headers`, copied the same way
+//! into each RPC endpoint of the family's chains; it never supplies endpoints
+//! itself.
+//!
+//! # Priority
+//!
+//! 1. Keys set on the chain (or its endpoint) — highest.
+//! 2. Values the network catalog fills in for a named chain.
+//! 3. `[defaults.<family>]`.
+//! 4. The global `[signers]` table, for `signers` / `signer`.
+//! 5. Built-in defaults — lowest.
+
+use std::collections::BTreeMap;
+
+use schemars::{JsonSchema, Schema, SchemaGenerator};
+
+use crate::error::Error;
+
+/// Remove the `[defaults]` table from `doc` and merge each family's defaults
+/// into the chains of that family, returning the path of each value it
+/// filled in with the namespace it came from.
+///
+/// Expects network names to be resolved already, so every chain key carries
+/// its namespace.  Defaults of families without chains are dropped.
+///
+/// # Errors
+///
+/// Returns an error if `[defaults]`, one of its family tables or a family's
+/// `rpc` is not a table.
+pub fn apply_chain_defaults(
+    doc: &mut BTreeMap<String, toml::Value>,
+) -> Result<Vec<(Vec<String>, String)>, Error> {
+    let mut filled = Vec::new();
+    let Some(defaults) = doc.remove("defaults") else {
+        return Ok(filled);
+    };
+    let toml::Value::Table(defaults) = defaults else {
+        return Err(Error::config("[defaults] must be a table"));
+    };
+    let mut families = BTreeMap::new();
+    for (namespace, family) in &defaults {
+        let toml::Value::Table(family) = family else {
+            return Err(Error::config(format!(
+                "[defaults.{namespace}] must be a table"
+            )));
+        };
+        let endpoint = match family.get("rpc") {
+            None => None,
+            Some(toml::Value::Table(endpoint)) => Some(endpoint),
+            Some(_) => {
+                return Err(Error::config(format!(
+                    "defaults.{namespace}.rpc must be a table of endpoint settings"
+                )));
+            }
+        };
+        families.insert(namespace.as_str(), (family, endpoint));
+    }
+
+    let Some(toml::Value::Table(chains)) = doc.get_mut("chains") else {
+        return Ok(filled);
+    };
+    for (chain_id, chain) in chains.iter_mut() {
+        let Some((namespace, _)) = chain_id.split_once(':') else {
+            continue;
+        };
+        if let (Some((family, endpoint)), toml::Value::Table(chain)) =
+            (families.get(namespace), chain)
+        {
+            let path = vec!["chains".to_owned(), chain_id.clone()];
+            for key in merge_chain(chain, family, *endpoint) {
+                let mut key_path = path.clone();
+                key_path.extend(key);
+                filled.push((key_path, namespace.to_owned()));
+            }
+        }
+    }
+    Ok(filled)
+}
+
+/// Copy the keys of `family` that `chain` lacks, and the endpoint defaults
+/// into each of its endpoints, returning the paths filled in below `chain`.
+fn merge_chain(
+    chain: &mut toml::Table,
+    family: &toml::Table,
+    endpoint: Option<&toml::Table>,
+) -> Vec<Vec<String>> {
+    let mut filled = Vec::new();
+    for (key, value) in family.iter().filter(|(key, _)| *key != "rpc") {
+        if !chain.contains_key(key) {
+            chain.insert(key.clone(), value.clone());
+            filled.push(vec![key.clone()]);
+        }
+    }
+    let (Some(defaults), Some(rpc)) = (endpoint, chain.get_mut("rpc")) else {
+        return filled;
+    };
+    let rpc_path = vec!["rpc".to_owned()];
+    match rpc {
+        toml::Value::Array(endpoints) => {
+            for (index, endpoint) in endpoints.iter_mut().enumerate() {
+                let mut path = rpc_path.clone();
+                path.push(index.to_string());
+                merge_endpoint(endpoint, defaults, &path, &mut filled);
+            }
+        }
+        single => merge_endpoint(single, defaults, &rpc_path, &mut filled),
+    }
+    filled
+}
+
+/// Copy the keys of `defaults` that `endpoint` lacks; a bare URL becomes an
+/// endpoint table first.
+fn merge_endpoint(
+    endpoint: &mut toml::Value,
+    defaults: &toml::Table,
+    path: &[String],
+    filled: &mut Vec<Vec<String>>,
+) {
+    if let toml::Value::String(url) = endpoint {
+        let mut table = toml::Table::new();
+        table.insert("http".to_owned(), toml::Value::String(std::mem::take(url)));
+        *endpoint = toml::Value::Table(table);
+    }
+    if let toml::Value::Table(endpoint) = endpoint {
+        for (key, value) in defaults {
+            if !endpoint.contains_key(key) {
+                endpoint.insert(key.clone(), value.clone());
+                let mut key_path = path.to_vec();
+                key_path.push(key.clone());
+                filled.push(key_path);
+            }
+        }
+    }
+}
+
+/// Chain and RPC endpoint schemas of the `namespace` family, if it is
+/// compiled into this build.
+fn family_schemas(generator: &mut SchemaGenerator, namespace: &str) -> Option<(Schema, Schema)> {
+    match namespace {
+        #[cfg(feature = "chain-eip155")]
+        "eip155" => Some((
+            crate::chain::Eip155ChainConfigInner::json_schema(generator),
+            crate::chain::Eip155RpcEndpoint::json_schema(generator),
+        )),
+        #[cfg(feature = "chain-solana")]
+        "solana" => Some((
+            crate::chain::SolanaChainConfigInner::json_schema(generator),
+            crate::chain::SolanaRpcEndpoint::json_schema(generator),
+        )),
+        _ => None,
+    }
+}
+
+/// The `[defaults]` table, for the config schema.
+#[derive(Debug)]
+pub struct ChainDefaults;
+
+impl JsonSchema for ChainDefaults {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ChainDefaults".into()
+    }
+
+    /// Each family's chain config with every key optional, and `rpc` as a
+    /// single table of endpoint settings without `http`.  Families not
+    /// compiled into this build accept any table, as their chains are
+    /// skipped.
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let mut properties = serde_json::Map::new();
+        for namespace in super::known_namespaces() {
+            let schema = family_schemas(generator, namespace).map_or_else(
+                || {
+                    schemars::json_schema!({
+                        "type": "object",
+                        "description": "Defaults of a family this build does not include; unused.",
+                    })
+                },
+                |(mut chain, mut endpoint)| {
+                    endpoint.remove("required");
+                    if let Some(serde_json::Value::Object(fields)) = endpoint.get_mut("properties")
+                    {
+                        fields.remove("http");
+                    }
+                    chain.remove("required");
+                    if let Some(serde_json::Value::Object(fields)) = chain.get_mut("properties") {
+                        fields.insert("rpc".to_owned(), endpoint.to_value());
+                    }
+                    chain
+                },
+            );
+            properties.insert(namespace.to_owned(), schema.to_value());
+        }
+        schemars::json_schema!({
+            "type": "object",
+            "description": "Settings merged into every chain of a family unless the chain sets them.",
+            "properties": properties,
+            "additionalProperties": false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(doc: &str) -> BTreeMap<String, toml::Value> {
+        let mut doc: BTreeMap<String, toml::Value> = toml::from_str(doc).unwrap();
+        apply_chain_defaults(&mut doc).unwrap();
+        doc
+    }
+
+    #[test]
+    fn defaults_fill_missing_keys_only() {
+        let doc = apply(
+            r#"
+            [defaults.eip155]
+            receipt_timeout_secs = 60
+            eip1559 = false
+            [defaults.eip155.rpc]
+            rate_limit = 25
+            [defaults.solana]
+            max_compute_unit_price = 5000
+            [chains."eip155:8453"]
+            rpc = [{ http = "https://a" }, { http = "https://b", rate_limit = 5 }]
+            eip1559 = true
+            [chains."solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"]
+            rpc = "https://api.devnet.solana.com"
+            "#,
+        );
+        assert!(!doc.contains_key("defaults"));
+        let chains = doc["chains"].as_table().unwrap();
+        let evm = chains["eip155:8453"].as_table().unwrap();
+        assert_eq!(evm["receipt_timeout_secs"].as_integer(), Some(60));
+        assert_eq!(evm["eip1559"].as_bool(), Some(true));
+        let rpc = evm["rpc"].as_array().unwrap();
+        assert_eq!(rpc[0]["rate_limit"].as_integer(), Some(25));
+        assert_eq!(rpc[1]["rate_limit"].as_integer(), Some(5));
+
+        let solana = chains["solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"]
+            .as_table()
+            .unwrap();
+        assert_eq!(solana["max_compute_unit_price"].as_integer(), Some(5000));
+        assert_eq!(
+            solana["rpc"].as_str(),
+            Some("https://api.devnet.solana.com")
+        );
+    }
+
+    #[test]
+    fn endpoint_defaults_expand_bare_urls() {
+        let doc = apply(
+            r#"
+            [defaults.solana.rpc]
+            rate_limit = 10
+            [chains."solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"]
+            rpc = "https://api.devnet.solana.com"
+            "#,
+        );
+        let rpc = &doc["chains"]["solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"]["rpc"];
+        assert_eq!(rpc["http"].as_str(), Some("https://api.devnet.solana.com"));
+        assert_eq!(rpc["rate_limit"].as_integer(), Some(10));
+    }
+
+    #[test]
+    fn filled_paths_are_reported() {
+        let mut doc: BTreeMap<String, toml::Value> = toml::from_str(
+            r#"
+            [defaults.eip155]
+            required = false
+            [defaults.eip155.rpc]
+            rate_limit = 25
+            [chains."eip155:8453"]
+            rpc = [{ http = "https://a" }]
+            "#,
+        )
+        .unwrap();
+        let filled: Vec<String> = apply_chain_defaults(&mut doc)
+            .unwrap()
+            .into_iter()
+            .map(|(path, namespace)| format!("{}@{namespace}", path.join(".")))
+            .collect();
+        assert_eq!(
+            filled,
+            [
+                "chains.eip155:8453.required@eip155",
+                "chains.eip155:8453.rpc.0.rate_limit@eip155",
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_defaults_are_rejected() {
+        let mut doc: BTreeMap<String, toml::Value> =
+            toml::from_str("[defaults.eip155]\nrpc = [{ http = \"https://a\" }]\n").unwrap();
+        let err = apply_chain_defaults(&mut doc).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("defaults.eip155.rpc must be a table")
+        );
+    }
+}
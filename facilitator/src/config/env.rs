@@ -0,0 +1,346 @@
+//! Environment-variable overrides for any configuration key.
+//!
+//! Every variable named `FACILITATOR__<KEY>__<KEY>...` overrides the key at
+//! that path, after the config file has been read.  Path segments are matched
+//! case-insensitively against existing keys, with any character that cannot
+//! appear in a variable name (`:`, `-`, `.`) written as `_`; numeric segments
+//! index into arrays.  For example:
+//!
+//! ```text
+//! FACILITATOR__LOG_LEVEL=debug
+//! FACILITATOR__CHAINS__EIP155_8453__RPC__0__HTTP=https://base.example
+//! ```
+//!
+//! Keys that do not exist yet are created in lowercase; under `chains` a
+//! network name is spelled with `_` for `-` (`BASE_SEPOLIA`), and otherwise
+//! the first `_` becomes the CAIP-2 separator, so `EIP155_8453` adds
+//! `"eip155:8453"`.  The CAIP-2 reference keeps the case it is written in,
+//! as Solana's base58 references are case-sensitive.  Values are parsed as
+//! TOML when possible (`8080`, `true`, `["a", "b"]`) and taken as plain
+//! strings otherwise; `0x`-prefixed values, such as keys and addresses, are
+//! always strings.  Quote a value (`'"123"'`) to force a string that would
+//! otherwise parse as a number.
+
+use std::collections::BTreeMap;
+
+use crate::chain::catalog;
+use crate::error::Error;
+
+/// Prefix of variables that override configuration keys.
+pub const ENV_PREFIX: &str = "FACILITATOR__";
+
+/// Separator between path segments in an override variable.
+const SEPARATOR: &str = "__";
+
+/// Collect the override variables from the process environment.
+///
+/// Variables whose name or value is not valid UTF-8 are skipped.
+pub fn env_overrides() -> Vec<(String, String)> {
+    std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect()
+}
+
+/// Apply `FACILITATOR__*` overrides from `vars` to `doc`, returning the path
+/// each variable set, in the order they were applied.
+///
+/// Variables without the prefix are ignored.  Overrides are applied in name
+/// order, so the result does not depend on the order of the environment.
+///
+/// # Errors
+///
+/// Returns an error if a variable names an empty path, indexes past the end
+/// of an array, or descends into a key that holds a plain value.
+pub fn apply_env_overrides(
+    doc: &mut BTreeMap<String, toml::Value>,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Vec<(Vec<String>, String)>, Error> {
+    let mut overrides: Vec<_> = vars
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let path = name.strip_prefix(ENV_PREFIX)?.to_owned();
+            Some((name, path, value))
+        })
+        .collect();
+    overrides.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+    let mut root = toml::Value::Table(std::mem::take(doc).into_iter().collect());
+    let mut applied = Vec::with_capacity(overrides.len());
+    for (name, path, value) in overrides {
+        let segments: Vec<&str> = path.split(SEPARATOR).collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return Err(Error::config(format!(
+                "invalid override '{name}': empty key in path"
+            )));
+        }
+        let path = set_path(&mut root, &[], &segments, parse_value(&value))
+            .map_err(|reason| Error::config(format!("invalid override '{name}': {reason}")))?;
+        applied.push((path, name));
+    }
+    if let toml::Value::Table(table) = root {
+        doc.extend(table);
+    }
+    Ok(applied)
+}
+
+/// Set `value` at `segments` below `node`, returning the full path of the
+/// key it set; `parents` are the keys already walked.
+fn set_path(
+    node: &mut toml::Value,
+    parents: &[String],
+    segments: &[&str],
+    value: toml::Value,
+) -> Result<Vec<String>, String> {
+    let Some((segment, rest)) = segments.split_first() else {
+        *node = value;
+        return Ok(parents.to_vec());
+    };
+    let mut path = parents.to_vec();
+    let child = match node {
+        toml::Value::Table(table) => {
+            let key = table
+                .keys()
+                .find(|key| env_key(key) == *segment)
+                .cloned()
+                .unwrap_or_else(|| new_key(parents, segment));
+            path.push(key.clone());
+            table
+                .entry(key)
+                .or_insert_with(|| empty_container(rest.first().copied()))
+        }
+        toml::Value::Array(array) => {
+            let index: usize = segment
+                .parse()
+                .map_err(|_| format!("'{}' is an array, expected an index", parents.join(".")))?;
+            path.push(index.to_string());
+            if index == array.len() {
+                array.push(empty_container(rest.first().copied()));
+            }
+            let len = array.len();
+            array.get_mut(index).ok_or_else(|| {
+                format!(
+                    "index {index} is out of range for '{}' ({len} entries)",
+                    parents.join(".")
+                )
+            })?
+        }
+        _ => {
+            return Err(format!(
+                "'{}' holds a plain value, not a table",
+                parents.join(".")
+            ));
+        }
+    };
+
+    set_path(child, &path, rest, value)
+}
+
+/// The form a config key takes in a variable name.
+fn env_key(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The config key created for `segment` when no existing key matches it.
+///
+/// Under `chains`, a segment naming a catalog network (`BASE_SEPOLIA`)
+/// becomes that name, and anything else a CAIP-2 identifier whose reference
+/// keeps its case.
+fn new_key(parents: &[String], segment: &str) -> String {
+    let key = segment.to_ascii_lowercase();
+    if parents != ["chains"] {
+        return key;
+    }
+    let name = key.replace('_', "-");
+    if catalog::network(&name).is_some() {
+        return name;
+    }
+    segment
+        .split_once('_')
+        .map_or(key, |(namespace, reference)| {
+            format!("{}:{reference}", namespace.to_ascii_lowercase())
+        })
+}
+
+/// A placeholder for a missing intermediate key: an array when the next
+/// segment is an index, a table otherwise.
+fn empty_container(next: Option<&str>) -> toml::Value {
+    match next {
+        Some(next) if next.bytes().all(|b| b.is_ascii_digit()) => toml::Value::Array(Vec::new()),
+        _ => toml::Value::Table(toml::map::Map::new()),
+    }
+}
+
+/// Parse an override value as a TOML value, falling back to a plain string.
+///
+/// `0x`-prefixed values stay strings: TOML would read `0x1234` as an
+/// integer, but here it is an address or key.
+fn parse_value(raw: &str) -> toml::Value {
+    if raw.starts_with("0x") {
+        return toml::Value::String(raw.to_owned());
+    }
+    toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .filter(|table| table.len() == 1)
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(doc: &str, vars: &[(&str, &str)]) -> Result<BTreeMap<String, toml::Value>, Error> {
+        let mut doc: BTreeMap<String, toml::Value> =
+            toml::from_str(doc).map_err(|e| Error::config_with("invalid test document", e))?;
+        let vars = vars
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()));
+        apply_env_overrides(&mut doc, vars)?;
+        Ok(doc)
+    }
+
+    #[test]
+    fn overrides_top_level_keys() {
+        let doc = apply(
+            "log_level = \"info\"\nport = 8080\n",
+            &[
+                ("FACILITATOR__LOG_LEVEL", "debug"),
+                ("FACILITATOR__PORT", "9090"),
+                ("OTHER__PORT", "1"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(doc["log_level"].as_str(), Some("debug"));
+        assert_eq!(doc["port"].as_integer(), Some(9090));
+    }
+
+    #[test]
+    fn overrides_existing_chain_rpc() {
+        let doc = apply(
+            "[chains.\"eip155:8453\"]\nrpc = [{ http = \"https://a.example\" }]\n",
+            &[(
+                "FACILITATOR__CHAINS__EIP155_8453__RPC__0__HTTP",
+                "https://b.example",
+            )],
+        )
+        .unwrap();
+        assert_eq!(
+            doc["chains"]["eip155:8453"]["rpc"][0]["http"].as_str(),
+            Some("https://b.example")
+        );
+    }
+
+    #[test]
+    fn reports_the_paths_it_set() {
+        let mut doc: BTreeMap<String, toml::Value> =
+            toml::from_str("[chains.\"eip155:8453\"]\nrpc = []\n").unwrap();
+        let applied = apply_env_overrides(
+            &mut doc,
+            [(
+                "FACILITATOR__CHAINS__EIP155_8453__RPC__0__HTTP".to_owned(),
+                "https://b.example".to_owned(),
+            )],
+        )
+        .unwrap();
+        assert_eq!(
+            applied,
+            [(
+                vec![
+                    "chains".to_owned(),
+                    "eip155:8453".to_owned(),
+                    "rpc".to_owned(),
+                    "0".to_owned(),
+                    "http".to_owned()
+                ],
+                "FACILITATOR__CHAINS__EIP155_8453__RPC__0__HTTP".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn creates_missing_chain() {
+        let doc = apply(
+            "",
+            &[
+                (
+                    "FACILITATOR__CHAINS__EIP155_84532__RPC__0__HTTP",
+                    "https://x",
+                ),
+                ("FACILITATOR__CHAINS__EIP155_84532__REQUIRED", "false"),
+            ],
+        )
+        .unwrap();
+        let chain = &doc["chains"]["eip155:84532"];
+        assert_eq!(chain["rpc"][0]["http"].as_str(), Some("https://x"));
+        assert_eq!(chain["required"].as_bool(), Some(false));
+
+        let doc = apply(
+            "",
+            &[("FACILITATOR__CHAINS__BASE_SEPOLIA__REQUIRED", "false")],
+        )
+        .unwrap();
+        assert!(doc["chains"].get("base-sepolia").is_some());
+
+        let doc = apply(
+            "",
+            &[(
+                "FACILITATOR__CHAINS__SOLANA_EtWTRABZaYq6iMfeYKouRu166VU2xqa1__REQUIRED",
+                "false",
+            )],
+        )
+        .unwrap();
+        assert!(
+            doc["chains"]
+                .get("solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        let out_of_range = apply("rpc = []\n", &[("FACILITATOR__RPC__3", "x")]).unwrap_err();
+        assert!(out_of_range.to_string().contains("out of range"));
+
+        let scalar = apply("port = 1\n", &[("FACILITATOR__PORT__X", "2")]).unwrap_err();
+        assert!(scalar.to_string().contains("plain value"));
+
+        assert!(apply("", &[("FACILITATOR__A____B", "1")]).is_err());
+    }
+
+    #[test]
+    fn values_fall_back_to_strings() {
+        assert_eq!(parse_value("true"), toml::Value::Boolean(true));
+        assert_eq!(
+            parse_value("https://x"),
+            toml::Value::String("https://x".to_owned())
+        );
+        assert_eq!(
+            parse_value("1\nextra = 2"),
+            toml::Value::String("1\nextra = 2".to_owned())
+        );
+    }
+
+    #[test]
+    fn hex_values_stay_strings() {
+        assert_eq!(
+            parse_value("0x1234"),
+            toml::Value::String("0x1234".to_owned())
+        );
+        let doc = apply(
+            "[signers]\nevm = []\n",
+            &[("FACILITATOR__SIGNERS__EVM__0", "0x00ff")],
+        )
+        .unwrap();
+        assert_eq!(doc["signers"]["evm"][0].as_str(), Some("0x00ff"));
+        assert_eq!(parse_value("1234"), toml::Value::Integer(1234));
+    }
+}
@@ -0,0 +1,198 @@
+//! Chain families that are not compiled into this build.
+//!
+//! Each chain family is behind a cargo feature (`chain-eip155`,
+//! `chain-solana`), but one config file is often shared between slim and full
+//! builds.  Chains and schemes of a family this binary was built without are
+//! skipped with a warning, unless `strict = true` asks for them to be
+//! rejected.  The family's `[signers]` key and `[defaults]` table are dropped
+//! silently.  Skipping happens before environment references are expanded,
+//! so variables only those sections use need not be set.  Namespaces no build
+//! knows about are always an error, raised when the chains are deserialised.
+
+use std::collections::BTreeMap;
+
+use crate::error::Error;
+
+/// Chain families known to this crate, with their `[signers]` key and the
+/// feature that compiles each in.
+const FAMILIES: &[(&str, &str, &str, bool)] = &[
+    (
+        "eip155",
+        "evm",
+        "chain-eip155",
+        cfg!(feature = "chain-eip155"),
+    ),
+    (
+        "solana",
+        "solana",
+        "chain-solana",
+        cfg!(feature = "chain-solana"),
+    ),
+];
+
+/// Namespaces of the chain families known to this crate, compiled in or not.
+pub fn known_namespaces() -> Vec<&'static str> {
+    FAMILIES.iter().map(|(family, _, _, _)| *family).collect()
+}
+
+/// Returns the feature a chain namespace needs, if that feature is known but
+/// not enabled in this build.
+fn missing_feature(namespace: &str) -> Option<&'static str> {
+    FAMILIES
+        .iter()
+        .find(|(family, _, _, _)| *family == namespace)
+        .and_then(|(_, _, feature, enabled)| (!enabled).then_some(*feature))
+}
+
+/// Returns the namespace of a CAIP-2 identifier or chain pattern.
+fn namespace(chain: &str) -> &str {
+    chain
+        .split_once(':')
+        .map_or(chain, |(namespace, _)| namespace)
+}
+
+/// Remove `[chains]` entries and `[[schemes]]` whose family is not compiled
+/// in, returning a warning for each, along with the family's `[signers]` key
+/// and `[defaults]` table.
+///
+/// Expects network names to be resolved already, so every chain key and
+/// scheme pattern carries its namespace.
+///
+/// # Errors
+///
+/// Returns an error for the first such entry when `strict` is set.
+pub fn skip_disabled_families(
+    doc: &mut BTreeMap<String, toml::Value>,
+    strict: bool,
+) -> Result<Vec<String>, Error> {
+    skip_families(doc, strict, missing_feature)
+}
+
+/// [`skip_disabled_families`], with `missing` naming the feature a namespace
+/// lacks.
+fn skip_families(
+    doc: &mut BTreeMap<String, toml::Value>,
+    strict: bool,
+    missing: fn(&str) -> Option<&'static str>,
+) -> Result<Vec<String>, Error> {
+    let mut warnings = Vec::new();
+    let mut skip = |what: String, feature: &str| {
+        if strict {
+            Err(Error::config(format!(
+                "{what} needs the '{feature}' feature, which this build does not include"
+            )))
+        } else {
+            warnings.push(format!(
+                "skipping {what}: this build does not include the '{feature}' feature"
+            ));
+            Ok(())
+        }
+    };
+
+    if let Some(toml::Value::Table(chains)) = doc.get_mut("chains") {
+        let disabled: Vec<(String, &str)> = chains
+            .keys()
+            .filter_map(|key| missing(namespace(key)).map(|feature| (key.clone(), feature)))
+            .collect();
+        for (key, feature) in disabled {
+            skip(format!("chain '{key}'"), feature)?;
+            chains.remove(&key);
+        }
+    }
+
+    if let Some(toml::Value::Array(schemes)) = doc.get_mut("schemes") {
+        let mut kept = Vec::with_capacity(schemes.len());
+        for scheme in schemes.drain(..) {
+            let feature = scheme
+                .get("chains")
+                .and_then(toml::Value::as_str)
+                .and_then(|chains| missing(namespace(chains)));
+            if let Some(feature) = feature {
+                let id = scheme
+                    .get("id")
+                    .and_then(toml::Value::as_str)
+                    .unwrap_or("?");
+                skip(format!("scheme '{id}'"), feature)?;
+            } else {
+                kept.push(scheme);
+            }
+        }
+        *schemes = kept;
+    }
+
+    for (family, signer, _, _) in FAMILIES {
+        if missing(family).is_none() {
+            continue;
+        }
+        for (table, key) in [("signers", signer), ("defaults", family)] {
+            if let Some(toml::Value::Table(table)) = doc.get_mut(table) {
+                table.remove(*key);
+            }
+        }
+    }
+
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIXED: &str = r#"
+        [chains."eip155:8453"]
+        rpc = [{ http = "https://mainnet.base.org" }]
+        [chains."solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"]
+        rpc = "https://api.devnet.solana.com"
+        [[schemes]]
+        id = "v2-eip155-exact"
+        chains = "eip155:*"
+        [[schemes]]
+        id = "v2-solana-exact"
+        chains = "solana:*"
+        [signers]
+        evm = ["$EVM_SIGNER_PRIVATE_KEY"]
+        solana = "$SOLANA_SIGNER_PRIVATE_KEY"
+        [defaults.solana]
+        max_compute_unit_limit = 200000
+    "#;
+
+    fn doc() -> BTreeMap<String, toml::Value> {
+        toml::from_str(MIXED).unwrap()
+    }
+
+    /// Pretend this build was compiled without `chain-solana`.
+    fn without_solana(namespace: &str) -> Option<&'static str> {
+        (namespace == "solana").then_some("chain-solana")
+    }
+
+    #[test]
+    fn compiled_families_are_kept() {
+        let mut doc = doc();
+        let warnings = skip_families(&mut doc, true, |_| None).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(doc["chains"].as_table().unwrap().len(), 2);
+        assert_eq!(doc["schemes"].as_array().unwrap().len(), 2);
+        assert!(missing_feature("cosmos").is_none());
+    }
+
+    #[test]
+    fn missing_families_are_skipped() {
+        let mut doc = doc();
+        let warnings = skip_families(&mut doc, false, without_solana).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("chain 'solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1'"));
+        assert!(warnings[1].contains("scheme 'v2-solana-exact'"));
+        let chains = doc["chains"].as_table().unwrap();
+        assert_eq!(chains.keys().collect::<Vec<_>>(), ["eip155:8453"]);
+        assert_eq!(doc["schemes"].as_array().unwrap().len(), 1);
+        let signers = doc["signers"].as_table().unwrap();
+        assert_eq!(signers.keys().collect::<Vec<_>>(), ["evm"]);
+        assert!(doc["defaults"].get("solana").is_none());
+    }
+
+    #[test]
+    fn missing_families_are_rejected_when_strict() {
+        let err = skip_families(&mut doc(), true, without_solana).unwrap_err();
+        assert!(err.to_string().contains("needs the 'chain-solana' feature"));
+    }
+}
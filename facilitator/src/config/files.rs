@@ -0,0 +1,435 @@
+//! Reading and merging config files.
+//!
+//! A configuration can be split across several TOML files in two ways:
+//!
+//! - an `include` key listing further files, relative to the including file;
+//!   file names may use `*` and `?` wildcards (`include = ["chains/*.toml"]`);
+//! - a config directory, whose `*.toml` files are merged in name order.
+//!
+//! Files are deep-merged: tables with the same key are combined, and any
+//! other value defined by two files is a conflict.  Conflicts are reported
+//! with the files both values came from, so splitting a config never silently
+//! changes its meaning.  [`Origins`] records the file and line that set
+//! each key.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::error::Error;
+
+/// Key listing the files a config file includes.
+const INCLUDE_KEY: &str = "include";
+
+/// The `include` key: one file pattern or a list of them.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Include {
+    /// A single file, or a wildcard pattern.
+    One(String),
+    /// Several files or wildcard patterns, merged in order.
+    Many(Vec<String>),
+}
+
+/// Where a key of the merged document was set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    /// The file that set the key.
+    pub file: PathBuf,
+    /// The 1-based line of the key within `file`.
+    pub line: usize,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.line)
+    }
+}
+
+/// The origin of each key of a merged document.
+#[derive(Debug, Default)]
+pub struct Origins(BTreeMap<Vec<String>, Origin>);
+
+impl Origins {
+    /// Returns where the value at `path` was set, falling back to the nearest
+    /// enclosing table when the key itself was not set by a file.
+    #[must_use]
+    pub fn get(&self, path: &[String]) -> Option<&Origin> {
+        (0..=path.len())
+            .rev()
+            .find_map(|len| self.0.get(&path[..len]))
+    }
+
+    /// Iterates over every recorded key and its origin.
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<String>, &Origin)> {
+        self.0.iter()
+    }
+
+    /// Record the line of every key in `raw`, read from `file`, keeping the
+    /// first origin of keys (tables) that several files share.
+    fn record_file(&mut self, file: &Path, raw: &str) {
+        let Ok(table) = toml::de::DeTable::parse(raw) else {
+            return;
+        };
+        let mut path = Vec::new();
+        self.record_table(file, raw, table.get_ref(), &mut path);
+    }
+
+    fn record_table(
+        &mut self,
+        file: &Path,
+        raw: &str,
+        table: &toml::de::DeTable<'_>,
+        path: &mut Vec<String>,
+    ) {
+        for (key, value) in table {
+            path.push(key.get_ref().to_string());
+            self.record_key(file, raw, key.span().start, path);
+            self.record_value(file, raw, value.get_ref(), path);
+            path.pop();
+        }
+    }
+
+    fn record_value(
+        &mut self,
+        file: &Path,
+        raw: &str,
+        value: &toml::de::DeValue<'_>,
+        path: &mut Vec<String>,
+    ) {
+        match value {
+            toml::de::DeValue::Table(table) => self.record_table(file, raw, table, path),
+            toml::de::DeValue::Array(array) => {
+                for (index, item) in array.iter().enumerate() {
+                    path.push(index.to_string());
+                    self.record_key(file, raw, item.span().start, path);
+                    self.record_value(file, raw, item.get_ref(), path);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn record_key(&mut self, file: &Path, raw: &str, offset: usize, path: &[String]) {
+        let line = raw
+            .get(..offset)
+            .map_or(1, |before| before.matches('\n').count() + 1);
+        self.0.entry(path.to_vec()).or_insert_with(|| Origin {
+            file: file.to_path_buf(),
+            line,
+        });
+    }
+}
+
+/// Merges config files into a single document.
+#[derive(Debug, Default)]
+pub struct ConfigFiles {
+    doc: toml::Table,
+    origins: Origins,
+    /// Files currently being read, outermost first, to detect include cycles.
+    stack: Vec<PathBuf>,
+    /// Files already merged, so overlapping includes read each file once.
+    loaded: BTreeSet<PathBuf>,
+}
+
+impl ConfigFiles {
+    /// Merge the file at `path` and everything it includes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read or parsed, an include is
+    /// invalid or cyclic, or two files set the same key.
+    pub fn add_file(&mut self, path: &Path) -> Result<(), Error> {
+        let path = path.canonicalize().map_err(|e| {
+            Error::config_with(format!("failed to resolve '{}'", path.display()), e)
+        })?;
+        if self.stack.contains(&path) {
+            return Err(Error::config(format!(
+                "'{}' includes itself (via {})",
+                path.display(),
+                self.stack
+                    .iter()
+                    .map(|file| format!("'{}'", file.display()))
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            )));
+        }
+        if !self.loaded.insert(path.clone()) {
+            return Ok(());
+        }
+
+        let raw_content = std::fs::read_to_string(&path)
+            .map_err(|e| Error::config_with(format!("failed to read '{}'", path.display()), e))?;
+        let mut table: toml::Table = toml::from_str(&raw_content)
+            .map_err(|e| Error::config_with(format!("failed to parse '{}'", path.display()), e))?;
+
+        let includes = take_includes(&mut table, &path)?;
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        self.stack.push(path.clone());
+        for pattern in includes {
+            for included in expand_pattern(base, &pattern)? {
+                self.add_file(&included)?;
+            }
+        }
+        self.stack.pop();
+
+        let mut file_origins = Origins::default();
+        file_origins.record_file(&path, &raw_content);
+        merge_table(
+            &mut self.doc,
+            table,
+            &mut Vec::new(),
+            &self.origins,
+            &file_origins,
+        )?;
+        for (key, origin) in file_origins.0 {
+            self.origins.0.entry(key).or_insert(origin);
+        }
+        Ok(())
+    }
+
+    /// Merge every `*.toml` file in `dir`, in name order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be listed, or merging one of
+    /// its files fails.
+    pub fn add_dir(&mut self, dir: &Path) -> Result<(), Error> {
+        for file in expand_pattern(dir, "*.toml")? {
+            self.add_file(&file)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the merged document and the origin of each key.
+    #[must_use]
+    pub fn finish(self) -> (BTreeMap<String, toml::Value>, Origins) {
+        (self.doc.into_iter().collect(), self.origins)
+    }
+}
+
+/// Remove the `include` key from `table`, returning its patterns.
+fn take_includes(table: &mut toml::Table, file: &Path) -> Result<Vec<String>, Error> {
+    let Some(value) = table.remove(INCLUDE_KEY) else {
+        return Ok(Vec::new());
+    };
+    match value.try_into() {
+        Ok(Include::One(pattern)) => Ok(vec![pattern]),
+        Ok(Include::Many(patterns)) => Ok(patterns),
+        Err(_) => Err(Error::config(format!(
+            "'{INCLUDE_KEY}' in '{}' must be a string or an array of strings",
+            file.display()
+        ))),
+    }
+}
+
+/// Resolve `pattern` relative to `base`, expanding wildcards in the file name.
+///
+/// A pattern without wildcards names a single file, which must exist; a
+/// wildcard pattern may match nothing.
+fn expand_pattern(base: &Path, pattern: &str) -> Result<Vec<PathBuf>, Error> {
+    let path = base.join(pattern);
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Err(Error::config(format!(
+            "invalid include '{pattern}' in '{}'",
+            base.display()
+        )));
+    };
+    if !has_wildcard(name) {
+        return Ok(vec![path]);
+    }
+    let dir = path.parent().unwrap_or(base);
+    if has_wildcard(&dir.to_string_lossy()) {
+        return Err(Error::config(format!(
+            "invalid include '{pattern}': wildcards are only supported in the file name"
+        )));
+    }
+
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| Error::config_with(format!("failed to list '{}'", dir.display()), e))?;
+    let mut matches = Vec::new();
+    for entry in entries {
+        let entry = entry
+            .map_err(|e| Error::config_with(format!("failed to list '{}'", dir.display()), e))?;
+        let is_file = entry.file_type().is_ok_and(|kind| !kind.is_dir());
+        if is_file
+            && entry
+                .file_name()
+                .to_str()
+                .is_some_and(|file| wildcard_match(name, file))
+        {
+            matches.push(entry.path());
+        }
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+fn has_wildcard(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// Match `name` against `pattern`, where `*` matches any run of characters
+/// and `?` matches exactly one.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name index it was tried at.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Deep-merge `from` into `into`; `origins` locate the keys of `into` and
+/// `from_origins` those of `from`.
+fn merge_table(
+    into: &mut toml::Table,
+    from: toml::Table,
+    path: &mut Vec<String>,
+    origins: &Origins,
+    from_origins: &Origins,
+) -> Result<(), Error> {
+    for (key, value) in from {
+        path.push(key.clone());
+        match (into.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge_table(existing, table, path, origins, from_origins)?;
+            }
+            (Some(_), _) => {
+                let locate = |origins: &Origins| {
+                    origins
+                        .get(path)
+                        .map_or_else(|| "another file".to_owned(), |origin| format!("'{origin}'"))
+                };
+                return Err(Error::config(format!(
+                    "conflicting values for '{}': set in {} and in {}",
+                    path.join("."),
+                    locate(origins),
+                    locate(from_origins)
+                )));
+            }
+            (None, value) => {
+                into.insert(key, value);
+            }
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::ScratchDir;
+
+    fn key(path: &str) -> Vec<String> {
+        path.split('.').map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn wildcards_match_file_names() {
+        assert!(wildcard_match("*.toml", "mainnet.toml"));
+        assert!(wildcard_match("*.toml", ".toml"));
+        assert!(!wildcard_match("*.toml", "mainnet.toml.bak"));
+        assert!(wildcard_match("chain-??.toml", "chain-01.toml"));
+        assert!(!wildcard_match("chain-??.toml", "chain-1.toml"));
+        assert!(wildcard_match("a*b*c", "aXXbYYbc"));
+    }
+
+    #[test]
+    fn includes_are_merged_with_origins() {
+        let dir = ScratchDir::new("include_merge");
+        dir.write(
+            "chains/b-testnet.toml",
+            "[chains.\"eip155:84532\"]\nrpc = [{ http = \"https://sepolia\" }]\n",
+        );
+        dir.write(
+            "chains/a-mainnet.toml",
+            "[chains.\"eip155:8453\"]\nrpc = [{ http = \"https://mainnet\" }]\n",
+        );
+        let main = dir.write(
+            "config.toml",
+            "include = [\"chains/*.toml\"]\nport = 9090\n[chains]\n",
+        );
+
+        let mut files = ConfigFiles::default();
+        files.add_file(&main).unwrap();
+        let (doc, origins) = files.finish();
+
+        assert!(!doc.contains_key(INCLUDE_KEY));
+        let chains = doc["chains"].as_table().unwrap();
+        assert_eq!(chains.len(), 2);
+        let origin = origins.get(&key("chains.eip155:8453.rpc")).unwrap();
+        assert!(origin.file.ends_with("chains/a-mainnet.toml"));
+        assert_eq!(origin.line, 2);
+        let origin = origins.get(&key("port")).unwrap();
+        assert!(origin.file.ends_with("config.toml"));
+        assert_eq!(origin.line, 2);
+    }
+
+    #[test]
+    fn conflicting_values_name_both_files() {
+        let dir = ScratchDir::new("include_conflict");
+        dir.write("conf.d/10-base.toml", "log_level = \"info\"\n");
+        dir.write("conf.d/20-debug.toml", "log_level = \"debug\"\n");
+
+        let mut files = ConfigFiles::default();
+        let err = files
+            .add_dir(&dir.path().join("conf.d"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("'log_level'"));
+        assert!(err.contains("10-base.toml"));
+        assert!(err.contains("20-debug.toml"));
+    }
+
+    #[test]
+    fn include_cycles_are_rejected() {
+        let dir = ScratchDir::new("include_cycle");
+        dir.write("b.toml", "include = \"a.toml\"\n");
+        let a = dir.write("a.toml", "include = \"b.toml\"\n");
+
+        let err = ConfigFiles::default().add_file(&a).unwrap_err();
+        assert!(err.to_string().contains("includes itself"));
+    }
+
+    #[test]
+    fn missing_literal_include_errors() {
+        let dir = ScratchDir::new("include_missing");
+        let main = dir.write(
+            "config.toml",
+            "include = [\"secrets.toml\", \"none/*.toml\"]\n",
+        );
+
+        let err = ConfigFiles::default().add_file(&main).unwrap_err();
+        assert!(err.to_string().contains("secrets.toml"));
+
+        dir.write("secrets.toml", "");
+        std::fs::create_dir_all(dir.path().join("none")).unwrap();
+        assert!(ConfigFiles::default().add_file(&main).is_ok());
+    }
+}
@@ -0,0 +1,280 @@
+//! Environment-variable interpolation in config string values.
+//!
+//! Every string value in the document is expanded before deserialisation,
+//! so secrets and deployment-specific values can live in the environment:
+//!
+//! | Form                | Expands to                                              |
+//! |---------------------|---------------------------------------------------------|
+//! | `$VAR`              | the value of `VAR`; only when it is the entire string    |
+//! | `${VAR}`            | the value of `VAR`, anywhere in the string              |
+//! | `${VAR:-default}`   | the value of `VAR`, or `default` if it is unset or empty |
+//! | `${VAR:?message}`   | the value of `VAR`, or an error with `message`          |
+//! | `$${`               | a literal `${`                                          |
+//!
+//! A `$` that does not start one of these forms is kept as-is, so literal
+//! dollar signs in passwords or URLs need no escaping.  Keys are never
+//! interpolated.
+
+use std::collections::BTreeMap;
+
+use crate::error::Error;
+
+/// Expand env-var references in every string value of `doc`, returning the
+/// path and original text of each value that changed.
+///
+/// # Errors
+///
+/// Returns an error naming the offending key if a reference is malformed,
+/// or refers to a variable that is unset and has no default.
+pub fn interpolate_doc(
+    doc: &mut BTreeMap<String, toml::Value>,
+) -> Result<Vec<(Vec<String>, String)>, Error> {
+    let lookup = |name: &str| std::env::var(name).ok();
+    let mut templates = Vec::new();
+    for (key, value) in doc.iter_mut() {
+        interpolate_value(value, &mut vec![key.clone()], &lookup, &mut templates)?;
+    }
+    Ok(templates)
+}
+
+/// Recursively expand string values below `value`, located at `path`,
+/// recording the original text of changed values in `templates`.
+fn interpolate_value(
+    value: &mut toml::Value,
+    path: &mut Vec<String>,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    templates: &mut Vec<(Vec<String>, String)>,
+) -> Result<(), Error> {
+    match value {
+        toml::Value::String(s) => {
+            let expanded = interpolate_with(s, lookup)
+                .map_err(|reason| Error::config(format!("in '{}': {reason}", path.join("."))))?;
+            if expanded != *s {
+                templates.push((path.clone(), std::mem::replace(s, expanded)));
+            }
+        }
+        toml::Value::Array(array) => {
+            for (index, item) in array.iter_mut().enumerate() {
+                path.push(index.to_string());
+                interpolate_value(item, path, lookup, templates)?;
+                path.pop();
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                path.push(key.clone());
+                interpolate_value(item, path, lookup, templates)?;
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Expand `value`, looking variables up through `lookup`.
+fn interpolate_with(
+    value: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    // Bare $VAR — only when it is the whole value, so that a `$` inside a
+    // literal password or URL is left alone.
+    if let Some(name) = value.strip_prefix('$')
+        && is_identifier(name)
+    {
+        return lookup(name).ok_or_else(|| not_found(name, value));
+    }
+
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some((literal, after)) = rest.split_once('$') {
+        expanded.push_str(literal);
+        if let Some(after) = after.strip_prefix("${") {
+            expanded.push_str("${");
+            rest = after;
+        } else if let Some(after) = after.strip_prefix('{') {
+            let (expr, after) = after
+                .split_once('}')
+                .ok_or_else(|| format!("unterminated '${{' in '{value}'"))?;
+            expanded.push_str(&expand(expr, value, lookup)?);
+            rest = after;
+        } else {
+            expanded.push('$');
+            rest = after;
+        }
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Expand the body of a single `${...}` reference.
+fn expand(
+    expr: &str,
+    original: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let (name, modifier) = expr
+        .split_once(':')
+        .map_or((expr, None), |(name, modifier)| (name, Some(modifier)));
+    if !is_identifier(name) {
+        return Err(format!(
+            "invalid env var reference '${{{expr}}}' in '{original}'"
+        ));
+    }
+    let Some(modifier) = modifier else {
+        return lookup(name).ok_or_else(|| not_found(name, original));
+    };
+
+    let set = lookup(name).filter(|value| !value.is_empty());
+    if let Some(default) = modifier.strip_prefix('-') {
+        Ok(set.unwrap_or_else(|| default.to_owned()))
+    } else if let Some(message) = modifier.strip_prefix('?') {
+        set.ok_or_else(|| {
+            if message.is_empty() {
+                format!("env var '{name}' is required but not set")
+            } else {
+                format!("env var '{name}' is required but not set: {message}")
+            }
+        })
+    } else {
+        Err(format!(
+            "unsupported modifier in '${{{expr}}}' in '{original}' (expected ':-' or ':?')"
+        ))
+    }
+}
+
+fn not_found(name: &str, original: &str) -> String {
+    format!("env var '{name}' not found (referenced as '{original}')")
+}
+
+/// Whether `name` is a well-formed environment variable name.
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "KEY" => Some("abc123".to_owned()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn expand(value: &str) -> Result<String, String> {
+        interpolate_with(value, &lookup)
+    }
+
+    #[test]
+    fn literal_value_unchanged() {
+        assert_eq!(expand("0x1234abcd").unwrap(), "0x1234abcd");
+        assert_eq!(expand("plain-text").unwrap(), "plain-text");
+        assert_eq!(expand("").unwrap(), "");
+    }
+
+    #[test]
+    fn bare_dollar_is_literal() {
+        assert_eq!(expand("$").unwrap(), "$");
+        assert_eq!(expand("pa$$word").unwrap(), "pa$$word");
+        assert_eq!(expand("cost: 5$").unwrap(), "cost: 5$");
+    }
+
+    #[test]
+    fn dollar_with_special_chars_is_literal() {
+        assert_eq!(expand("$not-a-var!").unwrap(), "$not-a-var!");
+        assert_eq!(expand("$has spaces").unwrap(), "$has spaces");
+    }
+
+    #[test]
+    fn whole_value_references_resolve() {
+        assert_eq!(expand("$KEY").unwrap(), "abc123");
+        assert_eq!(expand("${KEY}").unwrap(), "abc123");
+        assert_eq!(expand("${EMPTY}").unwrap(), "");
+    }
+
+    #[test]
+    fn embedded_references_resolve() {
+        assert_eq!(
+            expand("https://rpc.example/v2/${KEY}?x=1").unwrap(),
+            "https://rpc.example/v2/abc123?x=1"
+        );
+        assert_eq!(expand("${KEY}-${KEY}").unwrap(), "abc123-abc123");
+    }
+
+    #[test]
+    fn default_applies_when_unset_or_empty() {
+        assert_eq!(expand("${MISSING:-info}").unwrap(), "info");
+        assert_eq!(expand("${EMPTY:-fallback}").unwrap(), "fallback");
+        assert_eq!(expand("${KEY:-fallback}").unwrap(), "abc123");
+        assert_eq!(expand("${MISSING:-}").unwrap(), "");
+        assert_eq!(
+            expand("${MISSING:-http://localhost:8545}").unwrap(),
+            "http://localhost:8545"
+        );
+    }
+
+    #[test]
+    fn required_reports_message() {
+        let err = expand("${MISSING:?set it to your RPC key}").unwrap_err();
+        assert!(err.contains("MISSING"));
+        assert!(err.contains("set it to your RPC key"));
+        assert!(expand("${EMPTY:?}").is_err());
+        assert_eq!(expand("${KEY:?unused}").unwrap(), "abc123");
+    }
+
+    #[test]
+    fn missing_env_var_returns_error() {
+        assert!(expand("${_FACILITATOR_NONEXISTENT}").is_err());
+        assert!(expand("$_FACILITATOR_NONEXISTENT").is_err());
+    }
+
+    #[test]
+    fn malformed_references_error() {
+        assert!(expand("${KEY").unwrap_err().contains("unterminated"));
+        assert!(expand("${not valid}").unwrap_err().contains("invalid"));
+        assert!(expand("${KEY:+x}").unwrap_err().contains("unsupported"));
+    }
+
+    #[test]
+    fn escaped_reference_is_literal() {
+        assert_eq!(expand("$${KEY}").unwrap(), "${KEY}");
+    }
+
+    #[test]
+    fn doc_values_are_interpolated_recursively() {
+        let mut doc: BTreeMap<String, toml::Value> = toml::from_str(
+            r#"
+            port = 8080
+            [chains."eip155:1"]
+            rpc = [{ http = "https://rpc/${KEY}" }]
+            "#,
+        )
+        .unwrap();
+        let mut templates = Vec::new();
+        for (key, value) in &mut doc {
+            interpolate_value(value, &mut vec![key.clone()], &lookup, &mut templates).unwrap();
+        }
+        assert_eq!(
+            doc["chains"]["eip155:1"]["rpc"][0]["http"].as_str(),
+            Some("https://rpc/abc123")
+        );
+        assert_eq!(doc["port"].as_integer(), Some(8080));
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].0.join("."), "chains.eip155:1.rpc.0.http");
+        assert_eq!(templates[0].1, "https://rpc/${KEY}");
+
+        let mut doc: BTreeMap<String, toml::Value> =
+            toml::from_str("[signers]\nevm = [\"${MISSING}\"]\n").unwrap();
+        let err = interpolate_value(
+            doc.get_mut("signers").unwrap(),
+            &mut vec!["signers".to_owned()],
+            &lookup,
+            &mut templates,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("signers.evm.0"));
+    }
+}
@@ -0,0 +1,395 @@
+//! Detection of unknown config keys.
+//!
+//! Serde silently ignores keys it does not recognise, so a typo such as
+//! `receipt_timeout_sec` falls back to the default without any hint.  Before
+//! deserialisation, every key of the server, `[signers]`, `[defaults]`,
+//! `[telemetry]`, `[audit]`, `[privacy]`, chain and RPC endpoint tables is
+//! checked against the fields of the struct it ends up in.  Unknown keys are
+//! reported with their file and line and, where one is close enough, the key
+//! that was probably meant.
+//!
+//! Unknown keys are errors unless `unknown_keys = "warn"` is set, which
+//! downgrades them to warnings logged at startup.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::{Deserialize as DeriveDeserialize, Serialize};
+
+use super::families;
+use super::files::Origins;
+use super::{AuditConfig, Config, PrivacyConfig, TelemetryConfig};
+use crate::chain::catalog;
+#[cfg(feature = "chain-eip155")]
+use crate::chain::{Eip155ChainConfigInner, Eip155RpcEndpoint};
+#[cfg(feature = "chain-solana")]
+use crate::chain::{SolanaChainConfigInner, SolanaRpcEndpoint};
+use crate::signers::GlobalSigners;
+
+/// How unknown config keys are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, DeriveDeserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownKeys {
+    /// Refuse to start (default).
+    #[default]
+    Error,
+    /// Log a warning and carry on with the default values.
+    Warn,
+}
+
+impl UnknownKeys {
+    /// Read the mode from the raw document, before it is deserialised.
+    ///
+    /// Invalid values fall back to [`UnknownKeys::Error`]; deserialisation
+    /// reports them afterwards.
+    pub fn from_doc(doc: &BTreeMap<String, toml::Value>) -> Self {
+        match doc.get("unknown_keys").and_then(toml::Value::as_str) {
+            Some("warn") => Self::Warn,
+            _ => Self::Error,
+        }
+    }
+}
+
+/// A key that no config struct recognises.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    /// Full path of the key, e.g. `chains.eip155:8453.receipt_timeout_sec`.
+    pub path: String,
+    /// Where the key was set, as `file:line`, unless it came from the environment.
+    pub origin: Option<String>,
+    /// The known key closest to the unknown one, if any is close enough.
+    pub suggestion: Option<&'static str>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown key '{}'", self.path)?;
+        if let Some(origin) = &self.origin {
+            write!(f, " at {origin}")?;
+        }
+        if let Some(suggestion) = self.suggestion {
+            write!(f, "; did you mean '{suggestion}'?")?;
+        }
+        Ok(())
+    }
+}
+
+/// Find every unknown key in `doc`, in document order.
+///
+/// `doc` is the merged document before network names are resolved and
+/// signers are injected; `origins` locate its keys.
+pub fn find_unknown_keys(
+    doc: &BTreeMap<String, toml::Value>,
+    origins: &Origins,
+) -> Vec<UnknownKey> {
+    let mut checker = Checker {
+        origins,
+        unknown: Vec::new(),
+    };
+    let mut top_level: Vec<&'static str> = struct_fields::<Config>().to_vec();
+    top_level.extend(["signers", "defaults"]);
+    for (key, value) in doc {
+        let path = vec![key.clone()];
+        if !top_level.contains(&key.as_str()) {
+            checker.report(&path, &top_level);
+            continue;
+        }
+        match (key.as_str(), value) {
+            ("signers", toml::Value::Table(signers)) => {
+                checker.check(&path, signers, struct_fields::<GlobalSigners>());
+            }
+            ("telemetry", toml::Value::Table(telemetry)) => {
+                checker.check(&path, telemetry, struct_fields::<TelemetryConfig>());
+            }
+            ("audit", toml::Value::Table(audit)) => {
+                checker.check(&path, audit, struct_fields::<AuditConfig>());
+            }
+            ("privacy", toml::Value::Table(privacy)) => {
+                checker.check(&path, privacy, struct_fields::<PrivacyConfig>());
+            }
+            ("defaults", toml::Value::Table(defaults)) => {
+                checker.check_defaults(&path, defaults);
+            }
+            ("chains", toml::Value::Table(chains)) => {
+                for (chain, config) in chains {
+                    if let toml::Value::Table(config) = config {
+                        checker.check_chain(&[key.clone(), chain.clone()], chain, config);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    checker.unknown
+}
+
+struct Checker<'a> {
+    origins: &'a Origins,
+    unknown: Vec<UnknownKey>,
+}
+
+impl Checker<'_> {
+    /// Report the keys of `table` at `path` that are not in `known`.
+    fn check(&mut self, path: &[String], table: &toml::Table, known: &[&'static str]) {
+        for key in table.keys() {
+            if !known.contains(&key.as_str()) {
+                let mut key_path = path.to_vec();
+                key_path.push(key.clone());
+                self.report(&key_path, known);
+            }
+        }
+    }
+
+    fn report(&mut self, path: &[String], known: &[&'static str]) {
+        let key = path.last().map_or("", String::as_str);
+        self.unknown.push(UnknownKey {
+            path: path.join("."),
+            origin: self.origins.get(path).map(ToString::to_string),
+            suggestion: closest(key, known),
+        });
+    }
+
+    /// Check a chain table against the config struct of its namespace.
+    fn check_chain(&mut self, path: &[String], key: &str, table: &toml::Table) {
+        let namespace = key
+            .split_once(':')
+            .map(|(namespace, _)| namespace)
+            .or_else(|| catalog::network(key).map(catalog::Network::namespace));
+        // Unknown namespaces and names are reported when the chain is
+        // resolved or deserialised.
+        let Some((chain_fields, endpoint_fields)) = namespace.and_then(family_fields) else {
+            return;
+        };
+        self.check(path, table, chain_fields);
+
+        let mut rpc_path = path.to_vec();
+        rpc_path.push("rpc".to_owned());
+        match table.get("rpc") {
+            Some(toml::Value::Table(endpoint)) => self.check(&rpc_path, endpoint, endpoint_fields),
+            Some(toml::Value::Array(endpoints)) => {
+                for (index, endpoint) in endpoints.iter().enumerate() {
+                    if let toml::Value::Table(endpoint) = endpoint {
+                        let mut endpoint_path = rpc_path.clone();
+                        endpoint_path.push(index.to_string());
+                        self.check(&endpoint_path, endpoint, endpoint_fields);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check `[defaults]`: one table per known family, holding chain keys
+    /// and an `rpc` table of endpoint keys other than `http`.
+    fn check_defaults(&mut self, path: &[String], defaults: &toml::Table) {
+        let namespaces = families::known_namespaces();
+        for (namespace, table) in defaults {
+            let mut family_path = path.to_vec();
+            family_path.push(namespace.clone());
+            if !namespaces.contains(&namespace.as_str()) {
+                self.report(&family_path, &namespaces);
+                continue;
+            }
+            // Families this build does not include have no fields to check.
+            let (Some((chain_fields, endpoint_fields)), toml::Value::Table(table)) =
+                (family_fields(namespace), table)
+            else {
+                continue;
+            };
+            self.check(&family_path, table, chain_fields);
+            if let Some(toml::Value::Table(endpoint)) = table.get("rpc") {
+                let endpoint_fields: Vec<&'static str> = endpoint_fields
+                    .iter()
+                    .copied()
+                    .filter(|field| *field != "http")
+                    .collect();
+                family_path.push("rpc".to_owned());
+                self.check(&family_path, endpoint, &endpoint_fields);
+            }
+        }
+    }
+}
+
+/// The chain and RPC endpoint fields of a namespace compiled into this build.
+fn family_fields(namespace: &str) -> Option<(&'static [&'static str], &'static [&'static str])> {
+    match namespace {
+        #[cfg(feature = "chain-eip155")]
+        "eip155" => Some((
+            struct_fields::<Eip155ChainConfigInner>(),
+            struct_fields::<Eip155RpcEndpoint>(),
+        )),
+        #[cfg(feature = "chain-solana")]
+        "solana" => Some((
+            struct_fields::<SolanaChainConfigInner>(),
+            struct_fields::<SolanaRpcEndpoint>(),
+        )),
+        _ => None,
+    }
+}
+
+/// The known key closest to `key`, if it is within a few edits.
+fn closest(key: &str, known: &[&'static str]) -> Option<&'static str> {
+    let max_distance = (key.chars().count() / 3).max(1);
+    known
+        .iter()
+        .map(|candidate| (strsim::damerau_levenshtein(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The field names of the struct `T` deserialises from.
+fn struct_fields<T: for<'de> Deserialize<'de>>() -> &'static [&'static str] {
+    let mut fields = None;
+    // The capturing deserializer always fails once it has seen the fields.
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields.unwrap_or_default()
+}
+
+/// Deserializer that records the fields a derived struct asks for.
+struct FieldNames<'a>(&'a mut Option<&'static [&'static str]>);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("expected a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = Some(fields);
+        Err(de::Error::custom("fields captured"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unknown(doc: &str) -> Vec<UnknownKey> {
+        let doc: BTreeMap<String, toml::Value> = toml::from_str(doc).unwrap();
+        find_unknown_keys(&doc, &Origins::default())
+    }
+
+    #[test]
+    fn struct_fields_are_captured() {
+        let fields = struct_fields::<Config>();
+        assert!(fields.contains(&"log_level"));
+        assert!(fields.contains(&"chains"));
+    }
+
+    #[test]
+    fn top_level_typos_are_reported() {
+        let found = unknown(
+            "log_levle = \"debug\"\nport = 8080\n[signers]\nevn = []\n\
+             [telemetry]\nsampling_rate = 0.5\n",
+        );
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].path, "log_levle");
+        assert_eq!(found[0].suggestion, Some("log_level"));
+        assert_eq!(found[1].path, "signers.evn");
+        assert_eq!(found[1].suggestion, Some("evm"));
+        assert_eq!(found[2].suggestion, Some("sampling_ratio"));
+    }
+
+    #[cfg(feature = "chain-eip155")]
+    #[test]
+    fn evm_chain_typos_are_reported() {
+        let found = unknown(
+            r#"
+            [chains."eip155:84532"]
+            rpc = [{ http = "https://x", rate_limt = 5 }]
+            receipt_timeout_sec = 10
+            [chains.base]
+            flashblock = true
+            "#,
+        );
+        let paths: Vec<_> = found.iter().map(|key| key.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "chains.base.flashblock",
+                "chains.eip155:84532.receipt_timeout_sec",
+                "chains.eip155:84532.rpc.0.rate_limt",
+            ]
+        );
+        assert_eq!(found[1].suggestion, Some("receipt_timeout_secs"));
+        assert_eq!(found[2].suggestion, Some("rate_limit"));
+    }
+
+    #[cfg(feature = "chain-solana")]
+    #[test]
+    fn solana_chain_typos_are_reported() {
+        let found = unknown(
+            "
+            [chains.solana-devnet]
+            max_compute_unit_prices = 1
+            ",
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].suggestion, Some("max_compute_unit_price"));
+        assert!(
+            found[0]
+                .to_string()
+                .ends_with("did you mean 'max_compute_unit_price'?")
+        );
+    }
+
+    #[cfg(feature = "chain-solana")]
+    #[test]
+    fn chain_level_pubsub_is_reported() {
+        let found = unknown(
+            r#"
+            [chains.solana-devnet]
+            rpc = [{ http = "https://x" }]
+            pubsub = "wss://x"
+            "#,
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, "chains.solana-devnet.pubsub");
+    }
+
+    #[cfg(feature = "chain-eip155")]
+    #[test]
+    fn defaults_typos_are_reported() {
+        let found = unknown(
+            "
+            [defaults.evm]
+            eip1559 = false
+            [defaults.eip155]
+            receipt_timeout = 30
+            [defaults.eip155.rpc]
+            http = \"https://x\"
+            rate_limit = 5
+            ",
+        );
+        let paths: Vec<_> = found.iter().map(|key| key.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "defaults.eip155.receipt_timeout",
+                "defaults.eip155.rpc.http",
+                "defaults.evm",
+            ]
+        );
+        assert_eq!(found[0].suggestion, Some("receipt_timeout_secs"));
+    }
+
+    #[test]
+    fn distant_keys_get_no_suggestion() {
+        assert_eq!(closest("completely_different", &["port", "host"]), None);
+    }
+}
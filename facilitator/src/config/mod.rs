@@ -0,0 +1,803 @@
+//! Configuration loading and default template generation.
+//!
+//! This module provides:
+//!
+//! - [`Config`] — Type alias combining the base [`r402::config::Config`] with
+//!   chain-specific [`ChainsConfig`](crate::chain::ChainsConfig).
+//! - [`load_config`] — Reads and parses a TOML configuration file, applies
+//!   `FACILITATOR__*` environment overrides (see [`env`]), expands env-var
+//!   references in string values (see [`interpolate`]), rejects unknown keys
+//!   (see [`keys`]), resolves network names (see [`networks`]), skips chain
+//!   families this build does not include (see [`families`]), merges
+//!   per-family chain defaults (see [`defaults`]), and performs automatic
+//!   global-signer injection and scheme auto-generation.
+//!
+//! # Configuration File Format
+//!
+//! ```toml
+//! host = "0.0.0.0"
+//! port = 8080
+//!
+//! [signers]
+//! evm = ["$EVM_SIGNER_PRIVATE_KEY"]
+//! solana = "$SOLANA_SIGNER_PRIVATE_KEY"
+//!
+//! [chains."eip155:84532"]
+//! rpc = [{ http = "https://sepolia.base.org" }]
+//!
+//! # [[schemes]] is optional — auto-generated from configured chains.
+//! ```
+
+mod audit;
+mod defaults;
+mod env;
+mod families;
+mod files;
+mod interpolate;
+mod keys;
+mod networks;
+mod privacy;
+mod provenance;
+mod schema;
+mod show;
+mod telemetry;
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::Duration;
+
+use r402::chain::ChainIdPattern;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::chain::ChainsConfig;
+use crate::error::Error;
+use crate::logging::{LogFormat, LogOutput};
+use crate::signers;
+
+pub use self::audit::{AuditConfig, AuditRotation};
+pub use self::families::known_namespaces;
+pub use self::keys::UnknownKeys;
+pub use self::privacy::PrivacyConfig;
+pub use self::provenance::{Provenance, Source};
+pub use self::schema::config_schema;
+pub use self::show::{render_json, render_toml};
+#[cfg(feature = "telemetry")]
+pub use self::telemetry::OtlpProtocol;
+pub use self::telemetry::TelemetryConfig;
+
+/// Scheme registration entry from the TOML config.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SchemeEntry {
+    /// Scheme identifier (e.g. "v2-eip155-exact").
+    pub id: String,
+    /// Chain pattern (e.g. "eip155:*"), network name, or list of network names.
+    #[schemars(with = "networks::SchemeChains")]
+    pub chains: ChainIdPattern,
+    /// Optional scheme-specific configuration.
+    #[serde(flatten)]
+    pub config: Option<serde_json::Value>,
+}
+
+/// Server configuration combining host/port, chain configs, and scheme registrations.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Config {
+    /// Bind address (default: 0.0.0.0).
+    #[serde(default = "default_host")]
+    host: IpAddr,
+    /// Listen port (default: 8080).
+    #[serde(default = "default_port")]
+    port: u16,
+    /// Log level filter (default: "info").
+    ///
+    /// Accepts any valid `tracing` [`EnvFilter`](tracing_subscriber::EnvFilter)
+    /// directive, e.g. `"debug"`, `"facilitator=debug,r402=trace"`.
+    /// The `RUST_LOG` environment variable takes precedence when set.
+    #[serde(default = "default_log_level")]
+    log_level: String,
+    /// Format of log lines: full, compact, pretty, json or logfmt (default: full).
+    #[serde(default)]
+    log_format: LogFormat,
+    /// Stream logs are written to: stdout or stderr (default: stdout).
+    #[serde(default)]
+    log_output: LogOutput,
+    /// Maximum number of chains initialised concurrently at startup (default: 8).
+    #[serde(default = "default_startup_concurrency")]
+    startup_concurrency: usize,
+    /// Time a single chain may take to initialise at startup, in seconds (default: 30).
+    #[serde(default = "default_startup_timeout")]
+    startup_timeout_secs: u64,
+    /// Whether unknown keys are an error or only a warning (default: error).
+    #[serde(default)]
+    unknown_keys: UnknownKeys,
+    /// Reject chains and schemes of families this build does not include,
+    /// instead of skipping them with a warning (default: false).
+    #[serde(default)]
+    strict: bool,
+    /// OTLP export settings; `OTEL_*` environment variables take precedence.
+    #[serde(default)]
+    telemetry: TelemetryConfig,
+    /// Append-only audit log of verify and settle operations.
+    #[serde(default)]
+    audit: AuditConfig,
+    /// How payer addresses are recorded in spans, logs and the audit log.
+    #[serde(default)]
+    privacy: PrivacyConfig,
+    /// Chain provider configurations keyed by CAIP-2 identifier.
+    #[serde(default)]
+    chains: ChainsConfig,
+    /// Scheme registrations (optional, auto-generated if absent).
+    #[serde(default)]
+    schemes: Vec<SchemeEntry>,
+    /// Problems found while loading that were not fatal.
+    #[serde(skip)]
+    warnings: Vec<String>,
+}
+
+fn default_host() -> IpAddr {
+    host_from_env().unwrap_or(IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED))
+}
+
+/// The bind address from the `HOST` environment variable, if set and valid.
+fn host_from_env() -> Option<IpAddr> {
+    std::env::var("HOST").ok().and_then(|v| v.parse().ok())
+}
+
+fn default_port() -> u16 {
+    port_from_env().unwrap_or(8080)
+}
+
+/// The listen port from the `PORT` environment variable, if set and valid.
+fn port_from_env() -> Option<u16> {
+    std::env::var("PORT").ok().and_then(|v| v.parse().ok())
+}
+
+fn default_log_level() -> String {
+    "info".to_owned()
+}
+
+const fn default_startup_concurrency() -> usize {
+    8
+}
+
+const fn default_startup_timeout() -> u64 {
+    30
+}
+
+impl Config {
+    /// Returns the configured bind address.
+    #[must_use]
+    pub const fn host(&self) -> IpAddr {
+        self.host
+    }
+
+    /// Returns the configured listen port.
+    #[must_use]
+    pub const fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns the configured log level filter string.
+    #[must_use]
+    pub fn log_level(&self) -> &str {
+        &self.log_level
+    }
+
+    /// Returns the configured log line format.
+    #[must_use]
+    pub const fn log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    /// Returns the stream logs are written to.
+    #[must_use]
+    pub const fn log_output(&self) -> LogOutput {
+        self.log_output
+    }
+
+    /// Returns the OTLP export settings.
+    #[must_use]
+    pub const fn telemetry(&self) -> &TelemetryConfig {
+        &self.telemetry
+    }
+
+    /// Returns the audit log settings.
+    #[must_use]
+    pub const fn audit(&self) -> &AuditConfig {
+        &self.audit
+    }
+
+    /// Returns the payer recording settings.
+    #[must_use]
+    pub const fn privacy(&self) -> &PrivacyConfig {
+        &self.privacy
+    }
+
+    /// Returns the maximum number of chains initialised concurrently.
+    #[must_use]
+    pub const fn startup_concurrency(&self) -> usize {
+        self.startup_concurrency
+    }
+
+    /// Returns the time a single chain may take to initialise at startup.
+    #[must_use]
+    pub const fn startup_timeout(&self) -> Duration {
+        Duration::from_secs(self.startup_timeout_secs)
+    }
+
+    /// Returns a reference to the chain configurations.
+    #[must_use]
+    pub const fn chains(&self) -> &ChainsConfig {
+        &self.chains
+    }
+
+    /// Returns a reference to the scheme registrations.
+    #[must_use]
+    pub fn schemes(&self) -> &[SchemeEntry] {
+        &self.schemes
+    }
+
+    /// Returns the problems found while loading that were not fatal, such as
+    /// unknown keys when `unknown_keys = "warn"`.
+    #[must_use]
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+}
+
+/// Default config file, read when present and no path is given.
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Load configuration in layers: the TOML file and the files it includes,
+/// then the files in `dir`, then `FACILITATOR__*` environment overrides.
+///
+/// When `path` is `None`, `config.toml` is read if it exists; otherwise the
+/// configuration comes from `dir` and the environment alone.  Files are
+/// deep-merged and may not set the same key twice (see [`files`]).  Values
+/// set by no layer fall back to environment variables (`PORT`, `HOST`) and
+/// then to hardcoded defaults.
+///
+/// # Errors
+///
+/// Returns an error if an explicit `path` cannot be resolved, if a file
+/// cannot be read or parsed, if two files conflict, or if an override is
+/// invalid, if a key is unknown and `unknown_keys` is not `"warn"`, if
+/// `strict` is set and a chain's family is not compiled in, or if
+/// `[defaults]` is malformed.
+pub fn load_config(path: Option<&Path>, dir: Option<&Path>) -> Result<Config, Error> {
+    load_config_with_provenance(path, dir).map(|(config, _)| config)
+}
+
+/// Load configuration like [`load_config`], also returning where each value
+/// came from.
+///
+/// # Errors
+///
+/// Fails exactly when [`load_config`] does.
+pub fn load_config_with_provenance(
+    path: Option<&Path>,
+    dir: Option<&Path>,
+) -> Result<(Config, Provenance), Error> {
+    let path = path.or_else(|| {
+        let default = Path::new(DEFAULT_CONFIG_PATH);
+        default.exists().then_some(default)
+    });
+    let mut files = files::ConfigFiles::default();
+    if let Some(path) = path {
+        files.add_file(path)?;
+    }
+    if let Some(dir) = dir {
+        files.add_dir(dir)?;
+    }
+    let (mut doc, origins) = files.finish();
+    let mut provenance = Provenance::from_files(&origins, &doc);
+
+    for (path, name) in env::apply_env_overrides(&mut doc, env::env_overrides())? {
+        provenance.set(path, Source::Override(name));
+    }
+    let mut warnings = check_unknown_keys(&doc, &origins)?;
+    let renamed = networks::resolve_network_names(&mut doc)?;
+    provenance.rename_chains(&renamed);
+    provenance.attribute_new(&doc, |path| {
+        renamed
+            .iter()
+            .find(|(_, network)| path.get(1).is_some_and(|key| key == network.chain_id))
+            .map_or(Source::Default, |(_, network)| {
+                Source::Catalog(network.name)
+            })
+    });
+    let strict = doc
+        .get("strict")
+        .and_then(toml::Value::as_bool)
+        .unwrap_or(false);
+    warnings.extend(families::skip_disabled_families(&mut doc, strict)?);
+
+    // Expand environment references once sections this build skips are gone.
+    for (path, template) in interpolate::interpolate_doc(&mut doc)? {
+        provenance.set_template(path, template);
+    }
+
+    // Step 1: merge per-family defaults into chain entries
+    for (path, namespace) in defaults::apply_chain_defaults(&mut doc)? {
+        provenance.set(path, Source::Defaults(namespace));
+    }
+
+    // Step 2: inject global signers into chain entries
+    signers::preprocess_signers(&mut doc)?;
+    provenance.attribute_new(&doc, |_| Source::Signers);
+
+    // Step 3: auto-generate [[schemes]] if absent
+    auto_generate_schemes(&mut doc);
+    provenance.attribute_new(&doc, |_| Source::Generated);
+
+    let port = ["port".to_owned()];
+    if provenance.is_unset(&port) && port_from_env().is_some() {
+        provenance.set(port.to_vec(), Source::EnvDefault("PORT"));
+    }
+    let host = ["host".to_owned()];
+    if provenance.is_unset(&host) && host_from_env().is_some() {
+        provenance.set(host.to_vec(), Source::EnvDefault("HOST"));
+    }
+
+    let processed =
+        toml::to_string(&doc).map_err(|e| Error::config_with("failed to serialize config", e))?;
+    let mut config: Config =
+        toml::from_str(&processed).map_err(|e| Error::config_with("failed to parse config", e))?;
+    config.privacy.validate()?;
+    config.telemetry.validate()?;
+    config.warnings = warnings;
+    Ok((config, provenance))
+}
+
+/// Report unknown keys as an error, or return them as warnings when
+/// `unknown_keys = "warn"`.
+fn check_unknown_keys(
+    doc: &BTreeMap<String, toml::Value>,
+    origins: &files::Origins,
+) -> Result<Vec<String>, Error> {
+    let unknown: Vec<String> = keys::find_unknown_keys(doc, origins)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    match UnknownKeys::from_doc(doc) {
+        UnknownKeys::Warn => Ok(unknown),
+        UnknownKeys::Error if unknown.is_empty() => Ok(unknown),
+        UnknownKeys::Error => Err(Error::config(format!(
+            "{} (set unknown_keys = \"warn\" to ignore)",
+            unknown.join("; ")
+        ))),
+    }
+}
+
+/// Auto-generate `[[schemes]]` entries from configured chains when the section
+/// is absent or empty.
+fn auto_generate_schemes(doc: &mut BTreeMap<String, toml::Value>) {
+    let needs_schemes = match doc.get("schemes") {
+        None => true,
+        Some(toml::Value::Array(arr)) => arr.is_empty(),
+        _ => false,
+    };
+    if !needs_schemes {
+        return;
+    }
+
+    // Only families compiled into this build get a scheme.
+    let mut schemes = Vec::new();
+    if cfg!(feature = "chain-eip155") && has_chain_namespace(doc, "eip155:") {
+        schemes.push(scheme_entry("eip155-exact", "eip155:*"));
+    }
+    if cfg!(feature = "chain-solana") && has_chain_namespace(doc, "solana:") {
+        schemes.push(scheme_entry("solana-exact", "solana:*"));
+    }
+
+    if !schemes.is_empty() {
+        doc.insert("schemes".to_owned(), toml::Value::Array(schemes));
+    }
+}
+
+/// Check if any chain key starts with the given namespace prefix.
+fn has_chain_namespace(doc: &BTreeMap<String, toml::Value>, prefix: &str) -> bool {
+    doc.get("chains")
+        .and_then(|v| v.as_table())
+        .is_some_and(|chains| chains.keys().any(|k| k.starts_with(prefix)))
+}
+
+/// Build a single `[[schemes]]` TOML table entry.
+fn scheme_entry(id: &str, chains: &str) -> toml::Value {
+    let mut entry = toml::map::Map::new();
+    entry.insert("id".to_owned(), toml::Value::String(id.to_owned()));
+    entry.insert("chains".to_owned(), toml::Value::String(chains.to_owned()));
+    toml::Value::Table(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn has_chain_namespace_matches_eip155() {
+        let mut doc: BTreeMap<String, toml::Value> = BTreeMap::new();
+        let mut chains = toml::map::Map::new();
+        chains.insert(
+            "eip155:84532".into(),
+            toml::Value::Table(toml::map::Map::new()),
+        );
+        doc.insert("chains".into(), toml::Value::Table(chains));
+
+        assert!(has_chain_namespace(&doc, "eip155:"));
+        assert!(!has_chain_namespace(&doc, "solana:"));
+    }
+
+    #[test]
+    fn has_chain_namespace_no_chains() {
+        let doc: BTreeMap<String, toml::Value> = BTreeMap::new();
+        assert!(!has_chain_namespace(&doc, "eip155:"));
+    }
+
+    #[test]
+    fn scheme_entry_builds_correct_table() {
+        let entry = scheme_entry("eip155-exact", "eip155:*");
+        let table = entry.as_table().unwrap();
+        assert_eq!(table["id"].as_str(), Some("eip155-exact"));
+        assert_eq!(table["chains"].as_str(), Some("eip155:*"));
+    }
+
+    #[test]
+    fn auto_generate_schemes_creates_entries() {
+        let mut doc: BTreeMap<String, toml::Value> = BTreeMap::new();
+        let mut chains = toml::map::Map::new();
+        chains.insert(
+            "eip155:84532".into(),
+            toml::Value::Table(toml::map::Map::new()),
+        );
+        doc.insert("chains".into(), toml::Value::Table(chains));
+
+        auto_generate_schemes(&mut doc);
+
+        #[cfg(feature = "chain-eip155")]
+        {
+            let schemes = doc["schemes"].as_array().unwrap();
+            assert!(!schemes.is_empty());
+            let first = schemes[0].as_table().unwrap();
+            assert_eq!(first["id"].as_str(), Some("eip155-exact"));
+            assert_eq!(first["chains"].as_str(), Some("eip155:*"));
+        }
+    }
+
+    #[test]
+    fn auto_generate_schemes_skips_when_present() {
+        let mut doc: BTreeMap<String, toml::Value> = BTreeMap::new();
+        let mut chains = toml::map::Map::new();
+        chains.insert(
+            "eip155:84532".into(),
+            toml::Value::Table(toml::map::Map::new()),
+        );
+        doc.insert("chains".into(), toml::Value::Table(chains));
+
+        // Pre-populate with a custom scheme
+        let existing = vec![scheme_entry("custom-scheme", "eip155:1")];
+        doc.insert("schemes".into(), toml::Value::Array(existing));
+
+        auto_generate_schemes(&mut doc);
+
+        let schemes = doc["schemes"].as_array().unwrap();
+        assert_eq!(schemes.len(), 1);
+        assert_eq!(
+            schemes[0].as_table().unwrap()["id"].as_str(),
+            Some("custom-scheme")
+        );
+    }
+
+    #[test]
+    fn auto_generate_schemes_fills_empty_array() {
+        let mut doc: BTreeMap<String, toml::Value> = BTreeMap::new();
+        let mut chains = toml::map::Map::new();
+        chains.insert(
+            "eip155:84532".into(),
+            toml::Value::Table(toml::map::Map::new()),
+        );
+        doc.insert("chains".into(), toml::Value::Table(chains));
+        doc.insert("schemes".into(), toml::Value::Array(vec![]));
+
+        auto_generate_schemes(&mut doc);
+
+        #[cfg(feature = "chain-eip155")]
+        {
+            let schemes = doc["schemes"].as_array().unwrap();
+            assert!(!schemes.is_empty());
+        }
+    }
+
+    #[cfg(all(feature = "chain-eip155", feature = "chain-solana"))]
+    #[test]
+    fn chains_accept_rpc_headers_and_auth() {
+        use crate::chain::{ChainConfig, RpcAuth};
+
+        let chains: ChainsConfig = toml::from_str(
+            r#"
+            ["eip155:84532"]
+            rpc = [{ http = "https://rpc.example", headers = { "x-api-key" = "$KEY" }, auth = { bearer = "$TOKEN" } }]
+
+            ["solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"]
+            rpc = { http = "https://sol.example", auth = { basic = { username = "u", password = "$PW" } } }
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            &chains[0],
+            ChainConfig::Eip155(evm)
+                if evm.inner.rpc[0].headers.0["x-api-key"] == "$KEY"
+                    && matches!(&evm.inner.rpc[0].auth, Some(RpcAuth::Bearer(token)) if token == "$TOKEN")
+        ));
+        assert!(matches!(
+            &chains[1],
+            ChainConfig::Solana(solana)
+                if solana.inner.rpc[0].http == "https://sol.example"
+                    && matches!(solana.inner.rpc[0].auth, Some(RpcAuth::Basic { .. }))
+        ));
+    }
+
+    #[cfg(feature = "chain-solana")]
+    #[test]
+    fn solana_rpc_accepts_endpoint_list() {
+        use crate::chain::ChainConfig;
+
+        let chains: ChainsConfig = toml::from_str(
+            r#"
+            ["solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"]
+            rpc = [
+                "https://a.example",
+                { http = "https://b.example", pubsub = "wss://b.example", rate_limit = 20 },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            &chains[0],
+            ChainConfig::Solana(solana)
+                if solana.inner.rpc.len() == 2
+                    && solana.inner.rpc[0].http == "https://a.example"
+                    && solana.inner.rpc[1].pubsub.as_deref() == Some("wss://b.example")
+                    && solana.inner.rpc[1].rate_limit == Some(20)
+        ));
+
+        let empty: Result<ChainsConfig, _> =
+            toml::from_str("[\"solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1\"]\nrpc = []\n");
+        assert!(empty.is_err());
+    }
+
+    #[test]
+    fn load_config_minimal_file() {
+        let config_content = "host = \"127.0.0.1\"\nport = 9090\n";
+        let dir = std::env::temp_dir().join("facilitator_test_load");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("minimal.toml");
+        std::fs::write(&path, config_content).unwrap();
+
+        let config = load_config(Some(&path), None).unwrap();
+        assert_eq!(config.port(), 9090);
+        assert_eq!(config.host(), "127.0.0.1".parse::<IpAddr>().unwrap());
+        assert!(config.schemes().is_empty());
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn load_config_nonexistent_file_errors() {
+        let result = load_config(
+            Some(Path::new("/tmp/does_not_exist_facilitator.toml")),
+            None,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn load_config_invalid_toml_errors() {
+        let dir = std::env::temp_dir().join("facilitator_test_invalid");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("invalid.toml");
+        std::fs::write(&path, "this is [[[not valid toml").unwrap();
+
+        let result = load_config(Some(&path), None);
+        assert!(result.is_err());
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn load_config_rejects_unknown_keys() {
+        let dir = std::env::temp_dir().join("facilitator_test_unknown_keys");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("typo.toml");
+        std::fs::write(&path, "host = \"127.0.0.1\"\nprot = 9090\n").unwrap();
+
+        let err = load_config(Some(&path), None).unwrap_err().to_string();
+        assert!(err.contains("unknown key 'prot'"), "{err}");
+        assert!(err.contains("typo.toml:2"), "{err}");
+        assert!(err.contains("did you mean 'port'?"), "{err}");
+
+        std::fs::write(&path, "unknown_keys = \"warn\"\nprot = 9090\n").unwrap();
+        let config = load_config(Some(&path), None).unwrap();
+        assert_eq!(config.warnings().len(), 1);
+        assert!(config.warnings()[0].contains("'prot'"));
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_dir(&dir);
+    }
+
+    #[test]
+    fn provenance_tracks_each_step() {
+        let dir = std::env::temp_dir().join("facilitator_test_provenance");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(
+            &path,
+            "port = 9090\n[signers]\nevm = [\"0xkey\"]\n[chains.base]\nflashblocks = false\n",
+        )
+        .unwrap();
+
+        let (config, provenance) = load_config_with_provenance(Some(&path), None).unwrap();
+        let source = |dotted: &str| {
+            let path: Vec<String> = dotted.split('.').map(str::to_owned).collect();
+            provenance.source(&path).to_string()
+        };
+        assert!(source("port").ends_with("config.toml:1"));
+        assert!(source("chains.eip155:8453.flashblocks").ends_with("config.toml:5"));
+        assert_eq!(
+            source("chains.eip155:8453.rpc.0.http"),
+            "network catalog (base)"
+        );
+        assert_eq!(source("chains.eip155:8453.receipt_timeout_secs"), "default");
+        if cfg!(feature = "chain-eip155") {
+            assert_eq!(source("chains.eip155:8453.signers"), "[signers]");
+            assert_eq!(source("schemes.0.id"), "auto-generated");
+        }
+
+        let rendered = render_toml(&config, &provenance).unwrap();
+        assert!(!rendered.contains("0xkey"));
+        let reparsed: toml::Table = toml::from_str(&rendered).unwrap();
+        assert_eq!(reparsed["port"].as_integer(), Some(9090));
+        let json: serde_json::Value =
+            serde_json::from_str(&render_json(&config, &provenance).unwrap()).unwrap();
+        assert_eq!(json["sources"]["log_level"], "default");
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_dir(&dir);
+    }
+
+    #[cfg(all(feature = "chain-eip155", not(feature = "chain-solana")))]
+    #[test]
+    fn skipped_families_need_no_environment() {
+        let dir = crate::test_util::ScratchDir::new("skipped-family-env");
+        let path = dir.write(
+            "config.toml",
+            "[signers]\nevm = [\"0xkey\"]\nsolana = \"$FACILITATOR_TEST_UNSET_SOLANA_KEY\"\n\
+             [defaults.solana]\nmax_compute_unit_price = \"${FACILITATOR_TEST_UNSET_PRICE}\"\n\
+             [chains.\"eip155:84532\"]\nrpc = [{ http = \"https://sepolia.base.org\" }]\n\
+             [chains.solana-devnet]\nrpc = \"${FACILITATOR_TEST_UNSET_SOLANA_RPC}\"\n",
+        );
+
+        let config = load_config(Some(&path), None).unwrap();
+        assert_eq!(config.chains().len(), 1);
+        assert_eq!(config.warnings().len(), 1);
+        assert!(config.warnings()[0].contains("solana:"));
+    }
+
+    #[cfg(all(feature = "chain-eip155", feature = "chain-solana"))]
+    #[test]
+    fn signer_keys_resolve_from_the_environment() {
+        use crate::chain::ChainConfig;
+        use crate::test_util::{remove_test_env, set_test_env};
+
+        let dir = crate::test_util::ScratchDir::new("signer-env");
+        let path = dir.write(
+            "config.toml",
+            "[signers]\nevm = [\"${_FACILITATOR_TEST_EVM_A}\", \"$_FACILITATOR_TEST_EVM_B\", \"0xliteral\"]\n\
+             solana = \"$_FACILITATOR_TEST_SOLANA\"\n\
+             [chains.\"eip155:84532\"]\nrpc = [{ http = \"https://sepolia.base.org\" }]\n\
+             [chains.solana-devnet]\n",
+        );
+
+        set_test_env("_FACILITATOR_TEST_EVM_A", "0xaaa");
+        set_test_env("_FACILITATOR_TEST_EVM_B", "0xbbb");
+        set_test_env("_FACILITATOR_TEST_SOLANA", "solkey");
+        let loaded = load_config(Some(&path), None);
+        remove_test_env("_FACILITATOR_TEST_EVM_A");
+        remove_test_env("_FACILITATOR_TEST_EVM_B");
+        remove_test_env("_FACILITATOR_TEST_SOLANA");
+
+        let config = loaded.unwrap();
+        assert_eq!(config.chains().len(), 2);
+        for chain in config.chains().iter() {
+            match chain {
+                ChainConfig::Eip155(evm) => {
+                    assert_eq!(evm.inner.signers, ["0xaaa", "0xbbb", "0xliteral"]);
+                }
+                ChainConfig::Solana(solana) => {
+                    assert_eq!(solana.inner.signer.as_deref(), Some("solkey"));
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "chain-eip155")]
+    #[test]
+    fn missing_signer_variable_is_a_config_error() {
+        let dir = crate::test_util::ScratchDir::new("signer-env-missing");
+        let path = dir.write(
+            "config.toml",
+            "[signers]\nevm = [\"${_FACILITATOR_TEST_NONEXISTENT}\"]\n",
+        );
+
+        let err = load_config(Some(&path), None).unwrap_err();
+        assert!(matches!(err, Error::Config { .. }));
+        let message = err.to_string();
+        assert!(message.contains("signers.evm.0"), "{message}");
+        assert!(
+            message.contains("_FACILITATOR_TEST_NONEXISTENT"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn host_falls_back_to_the_environment() {
+        let dir = crate::test_util::ScratchDir::new("host-env");
+        let path = dir.write("config.toml", "port = 9090\n");
+
+        crate::test_util::set_test_env("HOST", "127.0.0.1");
+        let loaded = load_config_with_provenance(Some(&path), None);
+        crate::test_util::remove_test_env("HOST");
+
+        let (config, provenance) = loaded.unwrap();
+        assert_eq!(config.host(), IpAddr::from([127, 0, 0, 1]));
+        assert_eq!(
+            provenance.source(&["host".to_owned()]),
+            &Source::EnvDefault("HOST")
+        );
+    }
+
+    #[cfg(feature = "chain-eip155")]
+    #[test]
+    fn chain_defaults_are_merged_on_load() {
+        let dir = std::env::temp_dir().join("facilitator_test_chain_defaults");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(
+            &path,
+            "[defaults.eip155]\nreceipt_timeout_secs = 60\ncall_timeout_secs = 5\n\
+             [defaults.eip155.rpc]\nrate_limit = 25\n\
+             [chains.\"eip155:84532\"]\nrpc = [{ http = \"https://sepolia.base.org\" }]\n\
+             call_timeout_secs = 9\n",
+        )
+        .unwrap();
+
+        let (config, provenance) = load_config_with_provenance(Some(&path), None).unwrap();
+        let source = |dotted: &str| {
+            let path: Vec<String> = dotted.split('.').map(str::to_owned).collect();
+            provenance.source(&path).to_string()
+        };
+        assert_eq!(
+            source("chains.eip155:84532.receipt_timeout_secs"),
+            "[defaults.eip155]"
+        );
+        assert_eq!(
+            source("chains.eip155:84532.rpc.0.rate_limit"),
+            "[defaults.eip155]"
+        );
+        assert!(source("chains.eip155:84532.call_timeout_secs").ends_with("config.toml:8"));
+        let rendered: toml::Table =
+            toml::from_str(&render_toml(&config, &provenance).unwrap()).unwrap();
+        let chain = &rendered["chains"]["eip155:84532"];
+        assert_eq!(chain["receipt_timeout_secs"].as_integer(), Some(60));
+        assert_eq!(chain["call_timeout_secs"].as_integer(), Some(9));
+        assert_eq!(chain["rpc"][0]["rate_limit"].as_integer(), Some(25));
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_dir(&dir);
+    }
+}
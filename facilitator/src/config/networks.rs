@@ -0,0 +1,240 @@
+//! Network names in `[chains]` keys and `[[schemes]].chains`.
+//!
+//! Anywhere a CAIP-2 identifier is expected, a name from the built-in
+//! [`catalog`] can be used instead:
+//!
+//! ```toml
+//! [chains.base-sepolia]          # same as [chains."eip155:84532"]
+//!
+//! [[schemes]]
+//! id = "v2-eip155-exact"
+//! chains = ["base", "optimism"]   # same as "eip155:{8453,10}"
+//! ```
+//!
+//! A chain named through the catalog also picks up the catalog's public RPC
+//! endpoint and gas settings for any key it leaves unset.  Chains keyed by a
+//! CAIP-2 identifier are left untouched.
+
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::chain::catalog::{self, Network};
+use crate::error::Error;
+
+/// A scheme's `chains`, as written in the config file.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum SchemeChains {
+    /// A CAIP-2 chain pattern (`eip155:*`, `eip155:{8453,10}`) or a network name.
+    Pattern(String),
+    /// Network names of one namespace.
+    Names(Vec<String>),
+}
+
+/// Replace network names with CAIP-2 identifiers in `[chains]` keys and
+/// `[[schemes]].chains`, filling in catalog defaults for named chains.
+///
+/// Returns the name and network of each re-keyed chain.
+///
+/// # Errors
+///
+/// Returns an error if a name is not in the catalog, if a chain is configured
+/// under both its name and its identifier, or if a scheme lists networks from
+/// different namespaces.
+pub fn resolve_network_names(
+    doc: &mut BTreeMap<String, toml::Value>,
+) -> Result<Vec<(String, &'static Network)>, Error> {
+    let renamed = match doc.get_mut("chains") {
+        Some(toml::Value::Table(chains)) => resolve_chain_keys(chains)?,
+        _ => Vec::new(),
+    };
+    if let Some(toml::Value::Array(schemes)) = doc.get_mut("schemes") {
+        for scheme in schemes.iter_mut().filter_map(toml::Value::as_table_mut) {
+            if let Some(chains) = scheme.get_mut("chains") {
+                *chains = toml::Value::String(resolve_scheme_chains(chains)?);
+            }
+        }
+    }
+    Ok(renamed)
+}
+
+/// Re-key named chains by their CAIP-2 identifier.
+fn resolve_chain_keys(chains: &mut toml::Table) -> Result<Vec<(String, &'static Network)>, Error> {
+    let names: Vec<String> = chains
+        .keys()
+        .filter(|key| !key.contains(':'))
+        .cloned()
+        .collect();
+    let mut renamed = Vec::with_capacity(names.len());
+    for name in names {
+        let network = lookup(&name, "[chains]")?;
+        if chains.contains_key(network.chain_id) {
+            return Err(Error::config(format!(
+                "chain '{}' is configured twice, as '{name}' and as '{}'",
+                network.chain_id, network.chain_id
+            )));
+        }
+        let mut value = chains.remove(&name).unwrap_or_else(empty_table);
+        if let toml::Value::Table(table) = &mut value {
+            apply_defaults(table, network);
+        }
+        chains.insert(network.chain_id.to_owned(), value);
+        renamed.push((name, network));
+    }
+    Ok(renamed)
+}
+
+/// Fill in the catalog defaults for keys the chain table leaves unset.
+fn apply_defaults(table: &mut toml::Table, network: &Network) {
+    match network.namespace() {
+        "eip155" => {
+            table.entry("rpc").or_insert_with(|| {
+                let mut endpoint = toml::Table::new();
+                endpoint.insert("http".to_owned(), network.rpc.into());
+                toml::Value::Array(vec![toml::Value::Table(endpoint)])
+            });
+            table
+                .entry("eip1559")
+                .or_insert_with(|| network.eip1559.into());
+            table
+                .entry("flashblocks")
+                .or_insert_with(|| network.flashblocks.into());
+        }
+        _ => {
+            table.entry("rpc").or_insert_with(|| network.rpc.into());
+        }
+    }
+}
+
+/// Resolve a scheme's `chains`, given as a pattern, a network name, or a
+/// list of network names, into a chain pattern string.
+fn resolve_scheme_chains(chains: &toml::Value) -> Result<String, Error> {
+    let invalid = || Error::config("[[schemes]].chains must be a string or a list of strings");
+    let names = match chains.clone().try_into().map_err(|_| invalid())? {
+        SchemeChains::Pattern(pattern) if pattern.contains(':') => return Ok(pattern),
+        SchemeChains::Pattern(name) => vec![name],
+        SchemeChains::Names(names) => names,
+    };
+
+    let networks = names
+        .iter()
+        .map(|name| lookup(name, "[[schemes]].chains"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (first, rest) = networks
+        .split_first()
+        .ok_or_else(|| Error::config("[[schemes]].chains must not be empty"))?;
+    if rest.is_empty() {
+        return Ok(first.chain_id.to_owned());
+    }
+    let namespace = first.namespace();
+    if let Some(other) = rest.iter().find(|network| network.namespace() != namespace) {
+        return Err(Error::config(format!(
+            "[[schemes]].chains mixes '{}' and '{}'; list networks of one namespace per scheme",
+            first.name, other.name
+        )));
+    }
+    let references: Vec<&str> = networks
+        .iter()
+        .filter_map(|network| {
+            network
+                .chain_id
+                .split_once(':')
+                .map(|(_, reference)| reference)
+        })
+        .collect();
+    Ok(format!("{namespace}:{{{}}}", references.join(",")))
+}
+
+fn lookup(name: &str, location: &str) -> Result<&'static Network, Error> {
+    catalog::network(name).ok_or_else(|| {
+        Error::config(format!(
+            "unknown network '{name}' in {location}; use a CAIP-2 identifier or one of: {}",
+            catalog::NETWORKS
+                .iter()
+                .map(|network| network.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    })
+}
+
+fn empty_table() -> toml::Value {
+    toml::Value::Table(toml::Table::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(doc: &str) -> Result<BTreeMap<String, toml::Value>, Error> {
+        let mut doc: BTreeMap<String, toml::Value> =
+            toml::from_str(doc).map_err(|e| Error::config_with("invalid test document", e))?;
+        resolve_network_names(&mut doc)?;
+        Ok(doc)
+    }
+
+    #[test]
+    fn named_chains_get_catalog_defaults() {
+        let doc = resolve(
+            r#"
+            [chains.base-sepolia]
+            flashblocks = false
+            [chains.solana-devnet]
+            [chains."eip155:1"]
+            rpc = [{ http = "https://custom" }]
+            "#,
+        )
+        .unwrap();
+        let chains = doc["chains"].as_table().unwrap();
+        let base = &chains["eip155:84532"];
+        assert_eq!(
+            base["rpc"][0]["http"].as_str(),
+            Some("https://sepolia.base.org")
+        );
+        assert_eq!(base["eip1559"].as_bool(), Some(true));
+        assert_eq!(base["flashblocks"].as_bool(), Some(false));
+        assert_eq!(
+            chains["solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1"]["rpc"].as_str(),
+            Some("https://api.devnet.solana.com")
+        );
+        // CAIP-2 keys are not touched.
+        assert!(chains["eip155:1"].get("eip1559").is_none());
+    }
+
+    #[test]
+    fn unknown_or_duplicate_names_error() {
+        let err = resolve("[chains.basee]\n").unwrap_err();
+        assert!(err.to_string().contains("unknown network 'basee'"));
+
+        let err = resolve("[chains.base]\n[chains.\"eip155:8453\"]\n").unwrap_err();
+        assert!(err.to_string().contains("configured twice"));
+    }
+
+    #[test]
+    fn scheme_chains_accept_names() {
+        let doc = resolve(
+            r#"
+            [[schemes]]
+            id = "a"
+            chains = "base"
+            [[schemes]]
+            id = "b"
+            chains = ["base", "optimism"]
+            [[schemes]]
+            id = "c"
+            chains = "eip155:*"
+            "#,
+        )
+        .unwrap();
+        let schemes = doc["schemes"].as_array().unwrap();
+        assert_eq!(schemes[0]["chains"].as_str(), Some("eip155:8453"));
+        assert_eq!(schemes[1]["chains"].as_str(), Some("eip155:{8453,10}"));
+        assert_eq!(schemes[2]["chains"].as_str(), Some("eip155:*"));
+
+        let err =
+            resolve("[[schemes]]\nid = \"x\"\nchains = [\"base\", \"solana\"]\n").unwrap_err();
+        assert!(err.to_string().contains("mixes"));
+    }
+}
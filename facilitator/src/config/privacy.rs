@@ -0,0 +1,76 @@
+//! The `[privacy]` section: how payer addresses are recorded.
+//!
+//! The setting covers everything the facilitator writes about a request:
+//! span attributes (and so console logs and exported traces) and the audit
+//! log.
+
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// Payer recording settings.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct PrivacyConfig {
+    /// Record payer addresses as HMAC-SHA256 hashes instead of in plain text,
+    /// on request spans, in logs and in the audit log (default: false).
+    #[serde(default)]
+    pub hash_payers: bool,
+    /// Secret key of the payer hashes, e.g. `"${PAYER_HASH_KEY}"`; required
+    /// when `hash_payers` is set.  Changing it changes every hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payer_hash_key: Option<String>,
+}
+
+impl PrivacyConfig {
+    /// Check that a key is configured whenever payers are hashed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `hash_payers` is set without a non-empty
+    /// `payer_hash_key`.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.hash_payers && self.payer_hash_key.as_deref().is_none_or(str::is_empty) {
+            return Err(Error::config(
+                "privacy.hash_payers requires privacy.payer_hash_key",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PrivacyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivacyConfig")
+            .field("hash_payers", &self.hash_payers)
+            .field(
+                "payer_hash_key",
+                &self.payer_hash_key.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashing_requires_a_key() {
+        let config: PrivacyConfig = toml::from_str("hash_payers = true").unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("payer_hash_key"));
+
+        let config: PrivacyConfig =
+            toml::from_str("hash_payers = true\npayer_hash_key = \"secret\"").unwrap();
+        assert!(config.validate().is_ok());
+        assert!(!format!("{config:?}").contains("secret"));
+
+        let config: PrivacyConfig = toml::from_str("").unwrap();
+        assert_eq!(config, PrivacyConfig::default());
+        assert!(config.validate().is_ok());
+    }
+}
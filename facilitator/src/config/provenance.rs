@@ -0,0 +1,320 @@
+//! Where each value of the effective configuration came from.
+//!
+//! Every loading step records the keys it sets: the config files (with file
+//! and line), `FACILITATOR__*` overrides, catalog defaults for named chains,
+//! signers copied from `[signers]`, and auto-generated schemes.  A key no
+//! step set holds its built-in default.  Values expanded from env-var
+//! references also keep their original text, so they can be shown without
+//! revealing what they expanded to.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::files::{Origin, Origins};
+use crate::chain::catalog::Network;
+
+/// Where a value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Set in a config file.
+    File(Origin),
+    /// Set by a `FACILITATOR__*` environment override.
+    Override(String),
+    /// Filled in from the network catalog for a named chain.
+    Catalog(&'static str),
+    /// Copied from the `[defaults.<namespace>]` table of the chain's family.
+    Defaults(String),
+    /// Copied from the global `[signers]` table.
+    Signers,
+    /// Generated from the configured chains.
+    Generated,
+    /// Default read from an environment variable.
+    EnvDefault(&'static str),
+    /// Built-in default.
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(origin) => write!(f, "{origin}"),
+            Self::Override(name) => write!(f, "env {name}"),
+            Self::Catalog(network) => write!(f, "network catalog ({network})"),
+            Self::Defaults(namespace) => write!(f, "[defaults.{namespace}]"),
+            Self::Signers => f.write_str("[signers]"),
+            Self::Generated => f.write_str("auto-generated"),
+            Self::EnvDefault(name) => write!(f, "env {name} (default)"),
+            Self::Default => f.write_str("default"),
+        }
+    }
+}
+
+static DEFAULT: Source = Source::Default;
+
+/// A recorded source, and whether it also covers the keys below it.
+#[derive(Debug)]
+struct Entry {
+    source: Source,
+    subtree: bool,
+}
+
+/// The source of each key of the effective configuration.
+#[derive(Debug, Default)]
+pub struct Provenance {
+    sources: BTreeMap<Vec<String>, Entry>,
+    templates: BTreeMap<Vec<String>, String>,
+}
+
+impl Provenance {
+    /// Start from the origins of the merged config files.
+    ///
+    /// A file key covers the keys below it unless it holds a table, whose
+    /// keys have origins of their own; this way a value that deserialises
+    /// into a table (a Solana `rpc` URL) is still attributed to its file.
+    pub fn from_files(origins: &Origins, doc: &BTreeMap<String, toml::Value>) -> Self {
+        let sources = origins
+            .iter()
+            .map(|(path, origin)| {
+                let entry = Entry {
+                    source: Source::File(origin.clone()),
+                    subtree: !matches!(value_at(doc, path), Some(toml::Value::Table(_))),
+                };
+                (path.clone(), entry)
+            })
+            .collect();
+        Self {
+            sources,
+            templates: BTreeMap::new(),
+        }
+    }
+
+    /// Record that `source` set the whole value at `path`.
+    pub fn set(&mut self, path: Vec<String>, source: Source) {
+        let below: Vec<_> = self.descendants(&path).cloned().collect();
+        for key in below {
+            self.sources.remove(&key);
+        }
+        self.sources.insert(
+            path,
+            Entry {
+                source,
+                subtree: true,
+            },
+        );
+    }
+
+    /// Record the text of a value before env-var references were expanded.
+    pub fn set_template(&mut self, path: Vec<String>, template: String) {
+        self.templates.insert(path, template);
+    }
+
+    /// Record the chains re-keyed from a network name to their CAIP-2 identifier.
+    pub fn rename_chains(&mut self, renamed: &[(String, &'static Network)]) {
+        for (name, network) in renamed {
+            let from = ["chains".to_owned(), name.clone()];
+            let to = ["chains".to_owned(), network.chain_id.to_owned()];
+            rename(&mut self.sources, &from, &to);
+            rename(&mut self.templates, &from, &to);
+        }
+    }
+
+    /// Attribute every value of `doc` that no step has recorded yet to
+    /// `source`, called with the value's path.
+    pub fn attribute_new(
+        &mut self,
+        doc: &BTreeMap<String, toml::Value>,
+        source: impl Fn(&[String]) -> Source,
+    ) {
+        let mut new = Vec::new();
+        let mut path = Vec::new();
+        for (key, value) in doc {
+            path.push(key.clone());
+            self.collect_new(value, &mut path, &mut new);
+            path.pop();
+        }
+        for path in new {
+            let source = source(&path);
+            self.set(path, source);
+        }
+    }
+
+    fn collect_new(&self, value: &toml::Value, path: &mut Vec<String>, new: &mut Vec<Vec<String>>) {
+        let recurse = match self.sources.get(path.as_slice()) {
+            // A table from a file: later steps may have added keys to it.
+            Some(entry) => !entry.subtree,
+            None if self.lookup(path).is_some() => false,
+            None if self.descendants(path).next().is_none() => {
+                new.push(path.clone());
+                false
+            }
+            None => true,
+        };
+        if !recurse {
+            return;
+        }
+        match value {
+            toml::Value::Table(table) => {
+                for (key, item) in table {
+                    path.push(key.clone());
+                    self.collect_new(item, path, new);
+                    path.pop();
+                }
+            }
+            toml::Value::Array(array) => {
+                for (index, item) in array.iter().enumerate() {
+                    path.push(index.to_string());
+                    self.collect_new(item, path, new);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns `true` if nothing has recorded the value at `path`.
+    pub fn is_unset(&self, path: &[String]) -> bool {
+        self.lookup(path).is_none() && self.descendants(path).next().is_none()
+    }
+
+    /// Returns where the value at `path` came from.
+    #[must_use]
+    pub fn source(&self, path: &[String]) -> &Source {
+        self.lookup(path).unwrap_or(&DEFAULT)
+    }
+
+    /// Returns the text of the value at `path` before env-var references were
+    /// expanded, if it had any.
+    #[must_use]
+    pub fn template(&self, path: &[String]) -> Option<&str> {
+        (0..=path.len())
+            .rev()
+            .find_map(|len| self.templates.get(&path[..len]))
+            .map(String::as_str)
+    }
+
+    /// The source recorded for `path` itself, or for an enclosing value that
+    /// covers it.
+    fn lookup(&self, path: &[String]) -> Option<&Source> {
+        if let Some(entry) = self.sources.get(path) {
+            return Some(&entry.source);
+        }
+        (0..path.len())
+            .rev()
+            .filter_map(|len| self.sources.get(&path[..len]))
+            .find(|entry| entry.subtree)
+            .map(|entry| &entry.source)
+    }
+
+    fn descendants<'a>(&'a self, path: &'a [String]) -> impl Iterator<Item = &'a Vec<String>> {
+        self.sources
+            .range(path.to_vec()..)
+            .map(|(key, _)| key)
+            .skip_while(move |key| key.as_slice() == path)
+            .take_while(move |key| key.starts_with(path))
+    }
+}
+
+/// Move every key of `map` under `from` to the same place under `to`.
+fn rename<V>(map: &mut BTreeMap<Vec<String>, V>, from: &[String], to: &[String]) {
+    let keys: Vec<Vec<String>> = map
+        .range(from.to_vec()..)
+        .map(|(key, _)| key.clone())
+        .take_while(|key| key.starts_with(from))
+        .collect();
+    for key in keys {
+        if let Some(value) = map.remove(&key) {
+            let mut renamed = to.to_vec();
+            renamed.extend_from_slice(&key[from.len()..]);
+            map.insert(renamed, value);
+        }
+    }
+}
+
+/// The value at `path` in `doc`, if any.
+fn value_at<'a>(
+    doc: &'a BTreeMap<String, toml::Value>,
+    path: &[String],
+) -> Option<&'a toml::Value> {
+    let (first, rest) = path.split_first()?;
+    rest.iter()
+        .try_fold(doc.get(first)?, |value, key| match value {
+            toml::Value::Table(table) => table.get(key),
+            toml::Value::Array(array) => array.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn path(dotted: &str) -> Vec<String> {
+        dotted.split('.').map(str::to_owned).collect()
+    }
+
+    fn file(line: usize) -> Source {
+        Source::File(Origin {
+            file: PathBuf::from("config.toml"),
+            line,
+        })
+    }
+
+    #[test]
+    fn later_steps_are_attributed_below_file_tables() {
+        let mut doc: BTreeMap<String, toml::Value> =
+            toml::from_str("[chains.x]\nrequired = false\n").unwrap();
+        let mut provenance = Provenance::default();
+        provenance.sources.insert(
+            path("chains.x"),
+            Entry {
+                source: file(1),
+                subtree: false,
+            },
+        );
+        provenance.sources.insert(
+            path("chains.x.required"),
+            Entry {
+                source: file(2),
+                subtree: true,
+            },
+        );
+        let chain = doc.get_mut("chains").unwrap()["x"].as_table_mut().unwrap();
+        chain.insert("signers".to_owned(), toml::Value::Array(vec!["k".into()]));
+        provenance.attribute_new(&doc, |_| Source::Signers);
+
+        assert_eq!(*provenance.source(&path("chains.x.required")), file(2));
+        assert_eq!(
+            *provenance.source(&path("chains.x.signers.0")),
+            Source::Signers
+        );
+        assert_eq!(
+            *provenance.source(&path("chains.x.eip1559")),
+            Source::Default
+        );
+    }
+
+    #[test]
+    fn overrides_replace_and_renames_move() {
+        let mut provenance = Provenance::default();
+        provenance.set(path("chains.base.rpc.0.http"), file(3));
+        provenance.set_template(path("chains.base.rpc"), "${RPC}".to_owned());
+        provenance.set(
+            path("chains.base.rpc"),
+            Source::Override("FACILITATOR__X".to_owned()),
+        );
+        provenance.rename_chains(&[(
+            "base".to_owned(),
+            crate::chain::catalog::network("base").unwrap(),
+        )]);
+
+        let http = path("chains.eip155:8453.rpc.0.http");
+        assert_eq!(
+            *provenance.source(&http),
+            Source::Override("FACILITATOR__X".to_owned())
+        );
+        assert_eq!(provenance.template(&http), Some("${RPC}"));
+        assert!(provenance.is_unset(&path("port")));
+    }
+}
@@ -0,0 +1,97 @@
+//! JSON Schema of the configuration file.
+//!
+//! The schema is derived from the same serde types the loader deserialises
+//! into, plus the keys the loader consumes before deserialisation
+//! (`include`, `[signers]`, `[defaults]`), so it cannot drift from what the server
+//! accepts.  Editors use it for completion and validation (e.g. taplo's
+//! `#:schema` directive), and CI can validate configs against it.
+
+use schemars::{JsonSchema, Schema};
+
+use super::Config;
+use super::defaults::ChainDefaults;
+use super::files::Include;
+use crate::signers::GlobalSigners;
+
+/// A configuration file, as written on disk.
+#[derive(JsonSchema)]
+#[schemars(deny_unknown_fields)]
+#[schemars(title = "Facilitator configuration")]
+#[allow(dead_code)]
+struct ConfigFile {
+    /// Further config files to merge, relative to this file; file names may
+    /// use `*` and `?` wildcards.
+    include: Option<Include>,
+    /// Global signers, shared by every chain of the same family.
+    signers: Option<GlobalSigners>,
+    /// Per-family chain settings, used by every chain that does not set them.
+    defaults: Option<ChainDefaults>,
+    #[serde(flatten)]
+    config: Config,
+}
+
+/// Returns the JSON Schema of the configuration file.
+#[must_use]
+pub fn config_schema() -> Schema {
+    schemars::schema_for!(ConfigFile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_describes_file_keys() {
+        let schema = config_schema().to_value();
+        let properties = schema["properties"].as_object().unwrap();
+        for key in [
+            "include",
+            "signers",
+            "defaults",
+            "host",
+            "chains",
+            "schemes",
+            "unknown_keys",
+        ] {
+            assert!(properties.contains_key(key), "missing {key}");
+        }
+        assert!(!properties.contains_key("warnings"));
+        assert_eq!(schema["additionalProperties"], false);
+    }
+
+    #[cfg(feature = "chain-eip155")]
+    #[test]
+    fn chain_schemas_are_derived_from_chain_configs() {
+        let schema = config_schema().to_value();
+        assert_eq!(
+            schema["properties"]["chains"]["$ref"],
+            "#/$defs/ChainsConfig"
+        );
+        let chains = &schema["$defs"]["ChainsConfig"];
+        assert!(chains["properties"].get("base-sepolia").is_some());
+        assert!(chains["patternProperties"].get("^eip155:").is_some());
+        let evm = &schema["$defs"]["Eip155ChainConfigInner"];
+        assert!(evm["properties"].get("receipt_timeout_secs").is_some());
+        assert_eq!(evm["additionalProperties"], false);
+
+        let defaults = &schema["$defs"]["ChainDefaults"]["properties"]["eip155"];
+        assert!(defaults.get("required").is_none());
+        let endpoint = &defaults["properties"]["rpc"]["properties"];
+        assert!(endpoint.get("rate_limit").is_some());
+        assert!(endpoint.get("http").is_none());
+    }
+
+    #[test]
+    fn every_known_family_is_accepted() {
+        let schema = config_schema().to_value();
+        let chains = &schema["$defs"]["ChainsConfig"];
+        let defaults = &schema["$defs"]["ChainDefaults"];
+        for namespace in crate::config::known_namespaces() {
+            let pattern = format!("^{namespace}:");
+            assert!(chains["patternProperties"].get(&pattern).is_some());
+            assert!(defaults["properties"].get(namespace).is_some());
+        }
+        assert_eq!(chains["additionalProperties"], false);
+        assert!(chains["properties"].get("solana-devnet").is_some());
+    }
+}
@@ -0,0 +1,243 @@
+//! Rendering of the effective configuration.
+//!
+//! The loaded [`Config`] is printed with every value annotated with its
+//! [`Source`](super::Source): as trailing comments in TOML, or as a
+//! `sources` map alongside the config in JSON.
+//!
+//! Secrets never leave the process: signer keys, RPC and OTLP header values
+//! and authentication secrets are replaced with `<redacted>`, and any other value
+//! expanded from env-var references is shown as written (`${ALCHEMY_KEY}`),
+//! not as what it expanded to.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use super::{Config, Provenance};
+use crate::error::Error;
+
+/// Replacement text for secret values.
+const REDACTED: &str = "<redacted>";
+
+/// Render the effective configuration as TOML, annotating each value with
+/// its source.
+///
+/// # Errors
+///
+/// Returns an error if the configuration cannot be serialised.
+pub fn render_toml(config: &Config, provenance: &Provenance) -> Result<String, Error> {
+    let table = prepare(config, provenance)?;
+    let mut out = String::from("# Effective configuration; secrets are redacted.\n");
+    for warning in config.warnings() {
+        let _ = writeln!(out, "# warning: {warning}");
+    }
+    out.push('\n');
+    write_table(
+        &mut out,
+        &mut Vec::new(),
+        &mut Vec::new(),
+        &table,
+        provenance,
+    );
+    Ok(out)
+}
+
+/// Render the effective configuration as JSON: the config itself, the source
+/// of each value keyed by dotted path, and any load warnings.
+///
+/// # Errors
+///
+/// Returns an error if the configuration cannot be serialised.
+pub fn render_json(config: &Config, provenance: &Provenance) -> Result<String, Error> {
+    let table = prepare(config, provenance)?;
+    let mut sources = BTreeMap::new();
+    collect_sources(&mut Vec::new(), &table, provenance, &mut sources);
+    let document = serde_json::json!({
+        "config": table,
+        "sources": sources,
+        "warnings": config.warnings(),
+    });
+    let mut json = serde_json::to_string_pretty(&document)
+        .map_err(|e| Error::config_with("failed to serialize config", e))?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// Serialise `config` to a TOML table with secrets redacted and expanded
+/// values replaced by their templates.
+fn prepare(config: &Config, provenance: &Provenance) -> Result<toml::Table, Error> {
+    let mut table = toml::Table::try_from(config)
+        .map_err(|e| Error::config_with("failed to serialize config", e))?;
+    let mut path = Vec::new();
+    for (key, value) in &mut table {
+        path.push(key.clone());
+        redact(value, &mut path, provenance);
+        path.pop();
+    }
+    Ok(table)
+}
+
+fn redact(value: &mut toml::Value, path: &mut Vec<String>, provenance: &Provenance) {
+    match value {
+        toml::Value::String(s) => {
+            if is_secret(path) {
+                REDACTED.clone_into(s);
+            } else if let Some(template) = provenance.template(path) {
+                template.clone_into(s);
+            }
+        }
+        toml::Value::Array(array) => {
+            for (index, item) in array.iter_mut().enumerate() {
+                path.push(index.to_string());
+                redact(item, path, provenance);
+                path.pop();
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                path.push(key.clone());
+                redact(item, path, provenance);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether the string at `path` is a secret that is never shown.
+fn is_secret(path: &[String]) -> bool {
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    match path.as_slice() {
+        ["chains", _, "signers" | "signer", ..]
+        | ["chains", _, "rpc", _, "headers", _]
+        | ["telemetry", "headers", _]
+        | ["privacy", "payer_hash_key"] => true,
+        ["chains", _, "rpc", _, "auth", .., last] => *last != "username",
+        _ => false,
+    }
+}
+
+/// Whether `value` is written as its own `[table]` or `[[array]]` section.
+fn is_section(value: &toml::Value) -> bool {
+    match value {
+        toml::Value::Table(_) => true,
+        toml::Value::Array(items) => !items.is_empty() && items.iter().all(toml::Value::is_table),
+        _ => false,
+    }
+}
+
+/// Write `table`, at `path`, with `header` holding the keys of its section.
+fn write_table(
+    out: &mut String,
+    path: &mut Vec<String>,
+    header: &mut Vec<String>,
+    table: &toml::Table,
+    provenance: &Provenance,
+) {
+    for (key, value) in table.iter().filter(|(_, value)| !is_section(value)) {
+        path.push(key.clone());
+        let _ = writeln!(
+            out,
+            "{} = {value}  # {}",
+            format_key(key),
+            provenance.source(path)
+        );
+        path.pop();
+    }
+    for (key, value) in table.iter().filter(|(_, value)| is_section(value)) {
+        path.push(key.clone());
+        header.push(format_key(key));
+        match value {
+            toml::Value::Table(inner) => {
+                // Tables holding only sub-tables are implied by their headers.
+                if inner.is_empty() || inner.values().any(|value| !is_section(value)) {
+                    let _ = writeln!(out, "\n[{}]", header.join("."));
+                }
+                write_table(out, path, header, inner, provenance);
+            }
+            toml::Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if let toml::Value::Table(inner) = item {
+                        let _ = writeln!(out, "\n[[{}]]", header.join("."));
+                        path.push(index.to_string());
+                        write_table(out, path, header, inner, provenance);
+                        path.pop();
+                    }
+                }
+            }
+            _ => {}
+        }
+        header.pop();
+        path.pop();
+    }
+}
+
+/// Record the source of every value written on its own line.
+fn collect_sources(
+    path: &mut Vec<String>,
+    table: &toml::Table,
+    provenance: &Provenance,
+    sources: &mut BTreeMap<String, String>,
+) {
+    for (key, value) in table {
+        path.push(key.clone());
+        match value {
+            toml::Value::Table(inner) => collect_sources(path, inner, provenance, sources),
+            toml::Value::Array(items) if is_section(value) => {
+                for (index, item) in items.iter().enumerate() {
+                    if let toml::Value::Table(inner) = item {
+                        path.push(index.to_string());
+                        collect_sources(path, inner, provenance, sources);
+                        path.pop();
+                    }
+                }
+            }
+            _ => {
+                sources.insert(path.join("."), provenance.source(path).to_string());
+            }
+        }
+        path.pop();
+    }
+}
+
+/// A key as written in TOML: bare when possible, quoted otherwise.
+fn format_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+    if bare {
+        key.to_owned()
+    } else {
+        toml::Value::String(key.to_owned()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_recognised() {
+        let path = |dotted: &str| -> Vec<String> { dotted.split('.').map(str::to_owned).collect() };
+        assert!(is_secret(&path("chains.eip155:1.signers.0")));
+        assert!(is_secret(&path("chains.solana:x.signer")));
+        assert!(is_secret(&path("chains.eip155:1.rpc.0.headers.x-api-key")));
+        assert!(is_secret(&path("chains.eip155:1.rpc.0.auth.bearer")));
+        assert!(is_secret(&path(
+            "chains.eip155:1.rpc.0.auth.basic.password"
+        )));
+        assert!(!is_secret(&path(
+            "chains.eip155:1.rpc.0.auth.basic.username"
+        )));
+        assert!(is_secret(&path("telemetry.headers.authorization")));
+        assert!(is_secret(&path("privacy.payer_hash_key")));
+        assert!(!is_secret(&path("chains.eip155:1.rpc.0.http")));
+        assert!(!is_secret(&path("log_level")));
+    }
+
+    #[test]
+    fn keys_are_quoted_when_needed() {
+        assert_eq!(format_key("rpc_timeout_secs"), "rpc_timeout_secs");
+        assert_eq!(format_key("eip155:8453"), "\"eip155:8453\"");
+    }
+}
@@ -0,0 +1,149 @@
+//! The `[telemetry]` section: OTLP export settings.
+//!
+//! Every setting has a standard `OTEL_*` environment variable, which takes
+//! precedence when set, so deployments that configure `OpenTelemetry` through
+//! the environment keep working.  The section is parsed in every build, but
+//! only builds with the `telemetry` feature export anything.
+
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// OTLP transport protocol.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpProtocol {
+    /// OTLP over HTTP with protobuf payloads (default).
+    #[default]
+    Http,
+    /// OTLP over gRPC.
+    Grpc,
+}
+
+/// OTLP export settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct TelemetryConfig {
+    /// Collector endpoint, e.g. `"http://localhost:4318"`; setting it enables
+    /// export (`OTEL_EXPORTER_OTLP_ENDPOINT`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Transport protocol (default: http; `OTEL_EXPORTER_OTLP_PROTOCOL`).
+    #[serde(default)]
+    pub protocol: OtlpProtocol,
+    /// Headers sent with every export, e.g. an API key
+    /// (`OTEL_EXPORTER_OTLP_HEADERS`, merged over these).
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Fraction of traces sampled, from 0.0 to 1.0 (default: 1.0;
+    /// `OTEL_TRACES_SAMPLER_ARG`).
+    #[serde(default = "default_sampling_ratio")]
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub sampling_ratio: f64,
+    /// Metrics export interval in seconds (default: 30;
+    /// `OTEL_METRIC_EXPORT_INTERVAL`, in milliseconds).
+    #[serde(default = "default_export_interval")]
+    pub export_interval_secs: u64,
+    /// Resource attributes, e.g. `"deployment.environment.name"`
+    /// (`OTEL_RESOURCE_ATTRIBUTES` wins per key).
+    #[serde(default)]
+    pub resource: BTreeMap<String, String>,
+    /// Also print metrics to stdout while exporting them (default: true).
+    #[serde(default = "default_stdout_metrics")]
+    pub stdout_metrics: bool,
+}
+
+const fn default_sampling_ratio() -> f64 {
+    1.0
+}
+
+const fn default_export_interval() -> u64 {
+    30
+}
+
+const fn default_stdout_metrics() -> bool {
+    true
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            protocol: OtlpProtocol::default(),
+            headers: BTreeMap::new(),
+            sampling_ratio: default_sampling_ratio(),
+            export_interval_secs: default_export_interval(),
+            resource: BTreeMap::new(),
+            stdout_metrics: default_stdout_metrics(),
+        }
+    }
+}
+
+impl TelemetryConfig {
+    /// Check that the sampling ratio is a fraction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `sampling_ratio` is outside 0.0 to 1.0.
+    pub fn validate(&self) -> Result<(), Error> {
+        if !(0.0..=1.0).contains(&self.sampling_ratio) {
+            return Err(Error::config(format!(
+                "telemetry.sampling_ratio must be between 0.0 and 1.0, got {}",
+                self.sampling_ratio
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_section_uses_defaults() {
+        let config: TelemetryConfig = toml::from_str("").unwrap();
+        assert_eq!(config, TelemetryConfig::default());
+        assert!((config.sampling_ratio - 1.0).abs() < f64::EPSILON);
+        assert!(config.stdout_metrics);
+    }
+
+    #[test]
+    fn section_is_parsed() {
+        let config: TelemetryConfig = toml::from_str(
+            r#"
+            endpoint = "http://collector:4317"
+            protocol = "grpc"
+            sampling_ratio = 0.25
+            export_interval_secs = 10
+            stdout_metrics = false
+            headers = { "x-api-key" = "secret" }
+            resource = { "deployment.environment.name" = "staging" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.protocol, OtlpProtocol::Grpc);
+        assert_eq!(config.headers["x-api-key"], "secret");
+        assert_eq!(config.resource["deployment.environment.name"], "staging");
+        assert_eq!(config.export_interval_secs, 10);
+        assert!(!config.stdout_metrics);
+    }
+
+    #[test]
+    fn sampling_ratio_must_be_a_fraction() {
+        for ratio in ["0.0", "1.0", "0.5"] {
+            let config: TelemetryConfig =
+                toml::from_str(&format!("sampling_ratio = {ratio}")).unwrap();
+            assert!(config.validate().is_ok());
+        }
+        for ratio in ["-0.1", "1.5", "nan"] {
+            let config: TelemetryConfig =
+                toml::from_str(&format!("sampling_ratio = {ratio}")).unwrap();
+            let err = config.validate().unwrap_err();
+            assert!(err.to_string().contains("sampling_ratio"));
+        }
+    }
+}
@@ -22,16 +22,6 @@ pub enum Error {
         source: Option<BoxError>,
     },
 
-    /// Signer key resolution or derivation failed.
-    #[error("signer: {context}")]
-    Signer {
-        /// What went wrong, in plain English.
-        context: String,
-        /// The underlying error, if any.
-        #[source]
-        source: Option<BoxError>,
-    },
-
     /// Chain provider initialisation failed.
     #[error("chain: {context}")]
     Chain {
@@ -73,14 +63,6 @@ pub(crate) fn config_with(
         }
     }
 
-    /// Create a signer error with context only.
-    pub(crate) fn signer(context: impl Into<String>) -> Self {
-        Self::Signer {
-            context: context.into(),
-            source: None,
-        }
-    }
-
     /// Create a chain error with context only.
     pub(crate) fn chain(context: impl Into<String>) -> Self {
         Self::Chain {
@@ -0,0 +1,438 @@
+//! Machine-readable event formats: JSON and logfmt.
+//!
+//! Both write one line per event with the timestamp, level, target, the
+//! event's fields, and the fields of every span the event is in (outermost
+//! first).  With the `telemetry` feature, events inside a span that
+//! `OpenTelemetry` tracks also carry its `trace_id` and `span_id`, so log
+//! lines can be joined with traces.
+//!
+//! [`Json`] is `tracing-subscriber`'s JSON format with the trace context
+//! appended.  For logfmt, span fields are kept by the fmt layer as the
+//! `key=value` pairs [`LogfmtFields`] produces, ready to be copied into each
+//! line; a field recorded again replaces its earlier value.
+
+use std::fmt::{self, Write as _};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::{self, Format, Writer};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// One JSON object per line in `tracing-subscriber`'s JSON format —
+/// `timestamp`, `level`, `fields`, `target`, `span` (the innermost span) and
+/// `spans` (all of them, outermost first) — followed by `trace_id` and
+/// `span_id` when there is a trace context.
+#[derive(Debug, Clone)]
+pub struct Json(Format<format::Json>);
+
+impl Default for Json {
+    fn default() -> Self {
+        Self(
+            format::format()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true),
+        )
+    }
+}
+
+/// One line of `key=value` pairs per event: `ts`, `level`, `target`, `msg`,
+/// the event's fields, `span` (the innermost span's name) and the fields of
+/// every span.
+#[derive(Debug, Clone, Copy)]
+pub struct Logfmt;
+
+/// Span field formatter for [`Logfmt`].
+#[derive(Debug, Clone, Copy)]
+pub struct LogfmtFields;
+
+impl<S, N> FormatEvent<S, N> for Json
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'w> FormatFields<'w> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let Some((trace_id, span_id)) = trace_context(ctx) else {
+            return self.0.format_event(ctx, writer, event);
+        };
+        let mut line = String::new();
+        self.0.format_event(ctx, Writer::new(&mut line), event)?;
+        let object = line.trim_end().strip_suffix('}').ok_or(fmt::Error)?;
+        writeln!(
+            writer,
+            "{object},\"trace_id\":\"{trace_id}\",\"span_id\":\"{span_id}\"}}"
+        )
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for Logfmt
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'w> FormatFields<'w> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let meta = event.metadata();
+        let mut line = String::new();
+        let _ = write!(
+            line,
+            "ts={} level={} target={}",
+            timestamp(),
+            meta.level().as_str().to_ascii_lowercase(),
+            logfmt_value(meta.target())
+        );
+        event.record(&mut LogfmtVisitor(&mut line));
+        if let Some(scope) = ctx.event_scope() {
+            let mut innermost = None;
+            let mut span_fields = String::new();
+            for span in scope.from_root() {
+                if let Some(fields) = span.extensions().get::<FormattedFields<N>>()
+                    && !fields.fields.is_empty()
+                {
+                    span_fields.push(' ');
+                    span_fields.push_str(&fields.fields);
+                }
+                innermost = Some(span.name());
+            }
+            if let Some(name) = innermost {
+                let _ = write!(line, " span={}", logfmt_value(name));
+            }
+            line.push_str(&span_fields);
+        }
+        if let Some((trace_id, span_id)) = trace_context(ctx) {
+            let _ = write!(line, " trace_id={trace_id} span_id={span_id}");
+        }
+        writeln!(writer, "{line}")
+    }
+}
+
+impl<'w> FormatFields<'w> for LogfmtFields {
+    fn format_fields<R: RecordFields>(&self, mut writer: Writer<'w>, fields: R) -> fmt::Result {
+        let mut pairs = String::new();
+        fields.record(&mut LogfmtVisitor(&mut pairs));
+        writer.write_str(pairs.trim_start())
+    }
+
+    fn add_fields(
+        &self,
+        current: &'w mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        let mut added = String::new();
+        fields.record(&mut LogfmtVisitor(&mut added));
+        let added = logfmt_pairs(&added);
+        let mut merged: Vec<(&str, &str)> = logfmt_pairs(&current.fields)
+            .into_iter()
+            .filter(|(key, _)| !added.iter().any(|(added, _)| added == key))
+            .collect();
+        merged.extend(added);
+        current.fields = merged
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Ok(())
+    }
+}
+
+/// Split a line of logfmt pairs into keys and (still quoted) values.
+fn logfmt_pairs(line: &str) -> Vec<(&str, &str)> {
+    let mut pairs = Vec::new();
+    let mut rest = line.trim_start();
+    while let Some((key, tail)) = rest.split_once('=') {
+        let end = if tail.starts_with('"') {
+            // The closing quote is the first one not escaped by a backslash.
+            let mut escaped = false;
+            tail.char_indices()
+                .skip(1)
+                .find(|&(_, c)| {
+                    let closing = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    closing
+                })
+                .map_or(tail.len(), |(index, _)| index + 1)
+        } else {
+            tail.find(' ').unwrap_or(tail.len())
+        };
+        pairs.push((key, &tail[..end]));
+        rest = tail[end..].trim_start();
+    }
+    pairs
+}
+
+/// Appends fields as ` key=value` pairs; the message becomes `msg`.
+struct LogfmtVisitor<'a>(&'a mut String);
+
+impl LogfmtVisitor<'_> {
+    fn pair(&mut self, field: &Field, value: &str) {
+        let key = match field.name() {
+            "message" => "msg",
+            name => name,
+        };
+        let _ = write!(self.0, " {key}={}", logfmt_value(value));
+    }
+}
+
+impl Visit for LogfmtVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.pair(field, value);
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.pair(field, &value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.pair(field, &format!("{value:?}"));
+    }
+}
+
+/// A logfmt value, quoted when it is empty or contains spaces, `=` or quotes.
+fn logfmt_value(value: &str) -> String {
+    let bare = !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '=' || c == '"');
+    if bare {
+        value.to_owned()
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// The current time as RFC 3339.
+fn timestamp() -> String {
+    format_timestamp(SystemTime::now())
+}
+
+/// `time` as RFC 3339 in UTC with microseconds, e.g.
+/// `2026-10-18T16:43:39.123456Z`; times before the epoch format as the epoch.
+pub fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:06}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_micros()
+    )
+}
+
+/// The trace and span ID of the event's innermost `OpenTelemetry` span.
+#[cfg(feature = "telemetry")]
+fn trace_context<S, N>(ctx: &FmtContext<'_, S, N>) -> Option<(String, String)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'w> FormatFields<'w> + 'static,
+{
+    use opentelemetry::trace::{SpanId, TraceId};
+    use tracing_opentelemetry::OtelData;
+
+    let span = ctx.event_scope()?.next()?;
+    let extensions = span.extensions();
+    let data = extensions.get::<OtelData>()?;
+    let trace_id = data.trace_id().filter(|id| *id != TraceId::INVALID)?;
+    let span_id = data.span_id().filter(|id| *id != SpanId::INVALID)?;
+    Some((trace_id.to_string(), span_id.to_string()))
+}
+
+/// Trace context needs the `telemetry` feature.
+#[cfg(not(feature = "telemetry"))]
+#[allow(clippy::unnecessary_wraps)]
+const fn trace_context<S, N>(_ctx: &FmtContext<'_, S, N>) -> Option<(String, String)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'w> FormatFields<'w> + 'static,
+{
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    use tracing_subscriber::fmt::MakeWriter;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::logging::{LogFormat, layer};
+
+    /// Collects everything written to it.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0
+                .lock()
+                .map_err(|_| io::Error::other("poisoned"))?
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// Log one event inside a span with `format` and return the output.
+    fn capture(format: LogFormat) -> String {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry().with(layer(format, buffer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("http_request", method = "POST");
+            let _entered = span.enter();
+            tracing::info!(chain = "eip155:8453", ok = true, "settlement succeeded");
+        });
+        let bytes = buffer.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn json_lines_carry_event_and_span_fields() {
+        let output = capture(LogFormat::Json);
+        let line: Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["fields"]["message"], "settlement succeeded");
+        assert_eq!(line["fields"]["chain"], "eip155:8453");
+        assert_eq!(line["fields"]["ok"], true);
+        assert_eq!(line["span"]["name"], "http_request");
+        assert_eq!(line["span"]["method"], "POST");
+        assert_eq!(line["spans"].as_array().unwrap().len(), 1);
+        assert!(line["timestamp"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[cfg(feature = "telemetry")]
+    #[test]
+    fn json_lines_carry_trace_ids() {
+        use opentelemetry::trace::TracerProvider as _;
+
+        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder().build();
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(layer(LogFormat::Json, buffer.clone()))
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        tracing::subscriber::with_default(subscriber, || {
+            let _entered = tracing::info_span!("http_request").entered();
+            tracing::info!("traced");
+        });
+        let bytes = buffer.0.lock().unwrap().clone();
+        let line: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(line["trace_id"].as_str().unwrap().len(), 32);
+        assert_eq!(line["span_id"].as_str().unwrap().len(), 16);
+    }
+
+    #[test]
+    fn logfmt_lines_are_key_value_pairs() {
+        let output = capture(LogFormat::Logfmt);
+        assert!(output.starts_with("ts="), "{output}");
+        assert!(output.contains(" level=info "), "{output}");
+        assert!(output.contains(" msg=\"settlement succeeded\""), "{output}");
+        assert!(output.contains(" chain=eip155:8453 ok=true"), "{output}");
+        assert!(
+            output.contains(" span=http_request method=POST"),
+            "{output}"
+        );
+        assert_eq!(output.lines().count(), 1);
+    }
+
+    #[test]
+    fn logfmt_rerecorded_span_fields_replace_earlier_values() {
+        let buffer = Buffer::default();
+        let subscriber =
+            tracing_subscriber::registry().with(layer(LogFormat::Logfmt, buffer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "http_request",
+                x402.payer = tracing::field::Empty,
+                x402.outcome = tracing::field::Empty
+            );
+            let _entered = span.enter();
+            span.record("x402.payer", "0xabc");
+            span.record("x402.payer", "0xdef");
+            span.record("x402.outcome", "no \"luck\" here");
+            span.record("x402.outcome", "success");
+            tracing::info!("settled");
+        });
+        let bytes = buffer.0.lock().unwrap().clone();
+        let output = String::from_utf8(bytes).unwrap();
+        assert!(
+            output.contains(" span=http_request x402.payer=0xdef x402.outcome=success"),
+            "{output}"
+        );
+        assert_eq!(output.matches("x402.payer=").count(), 1, "{output}");
+    }
+
+    #[test]
+    fn logfmt_pairs_respect_quotes() {
+        assert_eq!(
+            logfmt_pairs(r#"a=1 b="x y=z" c="say \"hi\"" d="#),
+            [
+                ("a", "1"),
+                ("b", "\"x y=z\""),
+                ("c", r#""say \"hi\"""#),
+                ("d", "")
+            ]
+        );
+    }
+
+    #[test]
+    fn logfmt_values_are_quoted_when_needed() {
+        assert_eq!(logfmt_value("info"), "info");
+        assert_eq!(logfmt_value(""), "\"\"");
+        assert_eq!(logfmt_value("a b"), "\"a b\"");
+        assert_eq!(logfmt_value("say \"hi\""), "\"say \\\"hi\\\"\"");
+    }
+
+    #[test]
+    fn timestamps_are_rfc3339_utc() {
+        use std::time::Duration;
+
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000000Z");
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_micros(1_709_164_800_000_001)),
+            "2024-02-29T00:00:00.000001Z"
+        );
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(4_102_444_799)),
+            "2099-12-31T23:59:59.000000Z"
+        );
+    }
+}
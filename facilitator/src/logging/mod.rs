@@ -0,0 +1,115 @@
+//! Console logging.
+//!
+//! Always compiled in, so builds without the `telemetry` feature still log
+//! startup, errors and settlements.  The `telemetry` feature only adds OTLP
+//! export on top of the same filter and console output (see
+//! `telemetry::Telemetry::register`).
+//!
+//! Log lines are written to stdout or stderr ([`LogOutput`]) in one of
+//! several formats ([`LogFormat`]); the JSON and logfmt formats are meant for
+//! log pipelines and carry span fields and, with the `telemetry` feature,
+//! trace and span IDs.
+
+mod format;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::format::JsonFields;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer};
+
+pub use self::format::format_timestamp;
+use self::format::{Json, Logfmt, LogfmtFields};
+
+/// Format of console log lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines with span context (default).
+    #[default]
+    Full,
+    /// Shorter human-readable lines.
+    Compact,
+    /// Multi-line, human-readable output for local development.
+    Pretty,
+    /// One JSON object per line.
+    Json,
+    /// One line of `key=value` pairs per event.
+    Logfmt,
+}
+
+/// Stream console logs are written to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogOutput {
+    /// Standard output (default).
+    #[default]
+    Stdout,
+    /// Standard error.
+    Stderr,
+}
+
+impl LogOutput {
+    fn make_writer(self) -> BoxMakeWriter {
+        match self {
+            Self::Stdout => BoxMakeWriter::new(std::io::stdout),
+            Self::Stderr => BoxMakeWriter::new(std::io::stderr),
+        }
+    }
+}
+
+/// Level filter read from `RUST_LOG`, falling back to `log_level` when the
+/// variable is unset or invalid.
+pub fn filter(log_level: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| log_level.into())
+}
+
+/// Layer writing log lines in `format` to `output`.
+pub fn console<S>(format: LogFormat, output: LogOutput) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    layer(format, output.make_writer())
+}
+
+/// Layer writing log lines in `format` to `writer`.
+fn layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+    match format {
+        LogFormat::Full => layer.boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Json => layer
+            .with_ansi(false)
+            .event_format(Json::default())
+            .fmt_fields(JsonFields::new())
+            .boxed(),
+        LogFormat::Logfmt => layer
+            .with_ansi(false)
+            .event_format(Logfmt)
+            .fmt_fields(LogfmtFields)
+            .boxed(),
+    }
+}
+
+/// Install console logging as the global subscriber.
+///
+/// Used when the `telemetry` feature is disabled; otherwise
+/// `telemetry::Telemetry::register` installs the subscriber.
+#[cfg(not(feature = "telemetry"))]
+pub fn init(log_level: &str, format: LogFormat, output: LogOutput) {
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    tracing_subscriber::registry()
+        .with(filter(log_level))
+        .with(console(format, output))
+        .init();
+}
@@ -6,19 +6,31 @@
 //! ```sh
 //! facilitator init            # Generate default config.toml
 //! facilitator serve           # Start the server
+//! facilitator config show     # Print the effective config and its sources
+//! facilitator config schema   # Print the config file's JSON Schema
 //! ```
 
+mod audit;
+mod breaker;
+mod budget;
 mod chain;
 mod cmd;
 mod config;
 mod error;
+mod logging;
+#[cfg(feature = "telemetry")]
+mod metrics;
+mod rejection;
+mod request_id;
 mod routes;
 mod signers;
 #[cfg(feature = "telemetry")]
 mod telemetry;
+#[cfg(test)]
+mod test_util;
 
 use clap::Parser;
-use cmd::{Cli, Commands};
+use cmd::{Cli, Commands, ConfigCommand};
 use error::Error;
 
 #[tokio::main]
@@ -27,8 +39,22 @@ async fn main() {
     let cli = Cli::parse();
 
     let result: Result<(), Error> = match cli.command {
-        Commands::Init { output, force } => cmd::init::run(&output, force),
-        Commands::Serve { config } => cmd::serve::run(&config).await,
+        Commands::Init {
+            output,
+            force,
+            networks,
+        } => cmd::init::run(&output, force, networks.as_deref()),
+        Commands::Serve { source } => {
+            cmd::serve::run(source.config.as_deref(), source.config_dir.as_deref()).await
+        }
+        Commands::Config { command } => match command {
+            ConfigCommand::Schema => cmd::config::schema(),
+            ConfigCommand::Show { source, format } => cmd::config::show(
+                source.config.as_deref(),
+                source.config_dir.as_deref(),
+                format,
+            ),
+        },
     };
 
     if let Err(ref e) = result {
@@ -0,0 +1,374 @@
+//! `OpenTelemetry` metrics for verification and settlement outcomes.
+//!
+//! Every `/verify` and `/settle` response is HTTP 200, so status-code metrics
+//! cannot tell a failing chain from a healthy one.  [`MetricsFacilitator`]
+//! records the protocol-level outcome instead:
+//!
+//! | Instrument | Kind | Attributes |
+//! | --- | --- | --- |
+//! | `x402.verify.results` | counter | `network`, `scheme`, `result`, `reason` |
+//! | `x402.settle.results` | counter | `network`, `scheme`, `result`, `reason` |
+//! | `x402.settle.duration` | histogram (s) | `network` |
+//! | `x402.settle.gas_used` | histogram | `network` |
+//! | `x402.settle.in_flight` | up-down counter | `network` |
+//! | `facilitator.circuit_breaker.rejected` | counter | `network` |
+//! | `facilitator.circuit_breaker.open` | up-down counter | `network` |
+//!
+//! `result` is `valid`/`invalid` for verification and `success`/`error` for
+//! settlement; `reason` is the machine-readable reason code of a failure and
+//! is omitted on success.  `network` is the request's chain if it is
+//! configured, and `unknown` otherwise, so clients cannot create new series.
+//! Gas used is the EVM receipt's `gas_used`, or the compute units consumed on
+//! Solana, recorded by [`Metrics::fee_observer`] from the fee the gas budget
+//! layer reads after a successful settlement.  Circuit breaker rejections and
+//! open breakers are recorded by [`Metrics::circuit_observer`].  Only
+//! available with the `telemetry` feature.
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Counter, Histogram, Meter, UpDownCounter};
+use r402::chain::ChainId;
+use r402::facilitator::{Facilitator, FacilitatorError};
+use r402::proto;
+use r402::scheme::SchemeSlug;
+
+use crate::breaker::{CircuitEvent, CircuitObserver};
+use crate::budget::FeeObserver;
+
+/// Attribute value used when a request names no recognisable network or
+/// scheme.
+const UNKNOWN: &str = "unknown";
+
+/// Instruments recorded by [`MetricsFacilitator`].
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    verify_results: Counter<u64>,
+    settle_results: Counter<u64>,
+    settle_duration: Histogram<f64>,
+    settle_gas_used: Histogram<u64>,
+    settle_in_flight: UpDownCounter<i64>,
+    circuit_rejected: Counter<u64>,
+    circuit_open: UpDownCounter<i64>,
+}
+
+impl Metrics {
+    /// Create the instruments on `meter`.
+    #[must_use]
+    pub fn new(meter: &Meter) -> Self {
+        Self {
+            verify_results: meter
+                .u64_counter("x402.verify.results")
+                .with_description("Verification results by network, scheme and reason")
+                .build(),
+            settle_results: meter
+                .u64_counter("x402.settle.results")
+                .with_description("Settlement results by network, scheme and reason")
+                .build(),
+            settle_duration: meter
+                .f64_histogram("x402.settle.duration")
+                .with_description("Time taken to settle a payment")
+                .with_unit("s")
+                .build(),
+            settle_gas_used: meter
+                .u64_histogram("x402.settle.gas_used")
+                .with_description("Gas (EVM) or compute units (Solana) used per settlement")
+                .build(),
+            settle_in_flight: meter
+                .i64_up_down_counter("x402.settle.in_flight")
+                .with_description("Settlements currently in progress")
+                .build(),
+            circuit_rejected: meter
+                .u64_counter("facilitator.circuit_breaker.rejected")
+                .with_description("Calls rejected by an open circuit breaker")
+                .build(),
+            circuit_open: meter
+                .i64_up_down_counter("facilitator.circuit_breaker.open")
+                .with_description("Chains whose circuit breaker is open")
+                .build(),
+        }
+    }
+
+    /// Observer recording the gas used by each settlement, for
+    /// [`GasBudgetFacilitator::with_fee_observer`](crate::budget::GasBudgetFacilitator::with_fee_observer).
+    #[must_use]
+    pub fn fee_observer(&self) -> FeeObserver {
+        let histogram = self.settle_gas_used.clone();
+        Arc::new(move |chain, fee| {
+            histogram.record(fee.gas_used, &[KeyValue::new("network", chain.to_string())]);
+        })
+    }
+
+    /// Observer recording circuit breaker events, for
+    /// [`CircuitBreakerFacilitator::with_observer`](crate::breaker::CircuitBreakerFacilitator::with_observer).
+    #[must_use]
+    pub fn circuit_observer(&self) -> CircuitObserver {
+        let rejected = self.circuit_rejected.clone();
+        let open = self.circuit_open.clone();
+        Arc::new(move |chain, event| {
+            let network = [KeyValue::new("network", chain.to_string())];
+            match event {
+                CircuitEvent::Rejected => rejected.add(1, &network),
+                CircuitEvent::Opened => open.add(1, &network),
+                CircuitEvent::Closed => open.add(-1, &network),
+            }
+        })
+    }
+}
+
+/// Network and scheme attributes of a request.
+///
+/// The chain is taken from the payment payload, or from the requirements'
+/// `network` if the payload names no scheme.  Chains that are not in
+/// `networks` are labelled [`UNKNOWN`], along with their scheme.
+fn request_labels(
+    networks: &HashSet<ChainId>,
+    slug: Option<SchemeSlug>,
+    network: &str,
+) -> [KeyValue; 2] {
+    let (chain, scheme) = slug.map_or_else(
+        || (ChainId::from_str(network).ok(), None),
+        |slug| (Some(slug.chain_id), Some(slug.name)),
+    );
+    let (network, scheme) = chain.filter(|chain| networks.contains(chain)).map_or_else(
+        || (UNKNOWN.to_owned(), UNKNOWN.to_owned()),
+        |chain| {
+            (
+                chain.to_string(),
+                scheme.unwrap_or_else(|| UNKNOWN.to_owned()),
+            )
+        },
+    );
+    [
+        KeyValue::new("network", network),
+        KeyValue::new("scheme", scheme),
+    ]
+}
+
+/// `result` and optional `reason` of a verification.
+fn verify_outcome(
+    result: &Result<proto::VerifyResponse, FacilitatorError>,
+) -> (&'static str, Option<String>) {
+    let converted;
+    let response = match result {
+        Ok(response) => response,
+        Err(error) => {
+            converted = proto::VerifyResponse::from_facilitator_error(error);
+            &converted
+        }
+    };
+    match response {
+        proto::VerifyResponse::Invalid { reason, .. } => ("invalid", Some(reason.clone())),
+        _ => ("valid", None),
+    }
+}
+
+/// `result` and optional `reason` of a settlement.
+fn settle_outcome(
+    result: &Result<proto::SettleResponse, FacilitatorError>,
+) -> (&'static str, Option<String>) {
+    let converted;
+    let response = match result {
+        Ok(response) => response,
+        Err(error) => {
+            converted = proto::SettleResponse::from_facilitator_error(error, String::new());
+            &converted
+        }
+    };
+    match response {
+        proto::SettleResponse::Error { reason, .. } => ("error", Some(reason.clone())),
+        _ => ("success", None),
+    }
+}
+
+/// Attribute set for a result counter.
+fn result_attributes(
+    labels: &[KeyValue; 2],
+    (result, reason): (&'static str, Option<String>),
+) -> Vec<KeyValue> {
+    let mut attributes = labels.to_vec();
+    attributes.push(KeyValue::new("result", result));
+    if let Some(reason) = reason {
+        attributes.push(KeyValue::new("reason", reason));
+    }
+    attributes
+}
+
+/// Decrements the in-flight gauge when a settlement finishes or is dropped.
+struct InFlight<'a> {
+    gauge: &'a UpDownCounter<i64>,
+    network: [KeyValue; 1],
+}
+
+impl<'a> InFlight<'a> {
+    fn start(gauge: &'a UpDownCounter<i64>, network: KeyValue) -> Self {
+        let network = [network];
+        gauge.add(1, &network);
+        Self { gauge, network }
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.gauge.add(-1, &self.network);
+    }
+}
+
+/// [`Facilitator`] decorator recording [`Metrics`] for every call.
+#[derive(Debug)]
+pub struct MetricsFacilitator<F> {
+    inner: F,
+    metrics: Metrics,
+    networks: HashSet<ChainId>,
+}
+
+impl<F> MetricsFacilitator<F> {
+    /// Wrap `inner`, labelling requests for the configured `networks` by
+    /// chain.
+    #[must_use]
+    pub const fn new(inner: F, metrics: Metrics, networks: HashSet<ChainId>) -> Self {
+        Self {
+            inner,
+            metrics,
+            networks,
+        }
+    }
+
+    fn labels(&self, request: &proto::SettleRequest) -> [KeyValue; 2] {
+        request_labels(&self.networks, request.scheme_slug(), request.network())
+    }
+}
+
+impl<F> Facilitator for MetricsFacilitator<F>
+where
+    F: Facilitator,
+{
+    fn verify(
+        &self,
+        request: proto::VerifyRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::VerifyResponse, FacilitatorError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            // Verify and settle requests share their wire format.
+            let request = proto::SettleRequest::from(request);
+            let labels = self.labels(&request);
+            let result = self
+                .inner
+                .verify(proto::VerifyRequest::from(request.into_json()))
+                .await;
+            self.metrics
+                .verify_results
+                .add(1, &result_attributes(&labels, verify_outcome(&result)));
+            result
+        })
+    }
+
+    fn settle(
+        &self,
+        request: proto::SettleRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::SettleResponse, FacilitatorError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let labels = self.labels(&request);
+            let network = labels[0].clone();
+
+            let started = Instant::now();
+            let result = {
+                let _in_flight = InFlight::start(&self.metrics.settle_in_flight, network.clone());
+                self.inner.settle(request).await
+            };
+            self.metrics.settle_duration.record(
+                started.elapsed().as_secs_f64(),
+                std::slice::from_ref(&network),
+            );
+            self.metrics
+                .settle_results
+                .add(1, &result_attributes(&labels, settle_outcome(&result)));
+            result
+        })
+    }
+
+    fn supported(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<proto::SupportedResponse, FacilitatorError>> + Send + '_>>
+    {
+        self.inner.supported()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_fall_back_to_unknown() {
+        let base = ChainId::new("eip155", "8453");
+        let networks = HashSet::from([base.clone()]);
+        let labels = request_labels(&networks, None, "");
+        assert_eq!(labels[0].value.as_str(), UNKNOWN);
+        assert_eq!(labels[1].value.as_str(), UNKNOWN);
+
+        let labels = request_labels(&networks, None, "eip155:8453");
+        assert_eq!(labels[0].value.as_str(), "eip155:8453");
+        assert_eq!(labels[1].value.as_str(), UNKNOWN);
+
+        let slug = SchemeSlug::new(base, "exact".into());
+        let labels = request_labels(&networks, Some(slug), "");
+        assert_eq!(labels[0].value.as_str(), "eip155:8453");
+        assert_eq!(labels[1].value.as_str(), "exact");
+    }
+
+    #[test]
+    fn unconfigured_networks_are_unknown() {
+        let networks = HashSet::from([ChainId::new("eip155", "8453")]);
+        let labels = request_labels(&networks, None, "eip155:999999");
+        assert_eq!(labels[0].value.as_str(), UNKNOWN);
+
+        let slug = SchemeSlug::new(ChainId::new("eip155", "1337"), "made-up".into());
+        let labels = request_labels(&networks, Some(slug), "eip155:8453");
+        assert_eq!(labels[0].value.as_str(), UNKNOWN);
+        assert_eq!(labels[1].value.as_str(), UNKNOWN);
+    }
+
+    #[test]
+    fn outcomes_carry_reason_codes() {
+        let valid = Ok(proto::VerifyResponse::valid("0xpayer".into()));
+        assert_eq!(verify_outcome(&valid), ("valid", None));
+        let invalid = Ok(proto::VerifyResponse::invalid(
+            None,
+            "insufficient_funds".into(),
+        ));
+        assert_eq!(
+            verify_outcome(&invalid),
+            ("invalid", Some("insufficient_funds".into()))
+        );
+
+        let success = Ok(proto::SettleResponse::Success {
+            payer: "0xpayer".into(),
+            transaction: "0xtx".into(),
+            network: "eip155:8453".into(),
+            extensions: None,
+        });
+        assert_eq!(settle_outcome(&success), ("success", None));
+        let error = Ok(proto::SettleResponse::Error {
+            reason: "circuit_open".into(),
+            message: None,
+            payer: None,
+            network: "eip155:8453".into(),
+        });
+        assert_eq!(
+            settle_outcome(&error),
+            ("error", Some("circuit_open".into()))
+        );
+
+        let labels = request_labels(&HashSet::new(), None, "");
+        let attributes = result_attributes(&labels, settle_outcome(&error));
+        assert_eq!(attributes.len(), 4);
+        assert_eq!(attributes[3].value.as_str(), "circuit_open");
+    }
+}
@@ -0,0 +1,223 @@
+//! Structured errors for malformed `/verify` and `/settle` request bodies.
+//!
+//! A body that never reaches the facilitator is answered with a JSON error
+//! instead of an x402 response, so integrators can see what to fix:
+//!
+//! ```json
+//! { "error": "data", "path": "paymentRequirements.network", "message": "..." }
+//! ```
+//!
+//! | `error`                  | Status | Cause                                      |
+//! |--------------------------|--------|--------------------------------------------|
+//! | `unsupported_media_type` | 415    | `Content-Type` is not `application/json`   |
+//! | `syntax`                 | 400    | the body is not valid JSON                 |
+//! | `data`                   | 400    | a field is missing or has the wrong type   |
+//! | `invalid_version`        | 400    | `x402Version` is missing or not supported  |
+//! | `body`                   | 400    | the body could not be read (413 if large)  |
+//!
+//! `path` is the serde path to the offending field, or `null` when the error
+//! is not tied to one.
+
+use axum::Json;
+use axum::extract::rejection::JsonRejection;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use r402::proto::v2;
+use serde::Serialize;
+use serde_json::Value;
+
+/// x402 versions accepted by `/verify` and `/settle`.
+const SUPPORTED_VERSIONS: [u64; 1] = [2];
+
+/// Shape of a v2 verify or settle request; the signed payload itself is
+/// scheme-specific and left to the facilitator.
+type PaymentRequest =
+    v2::VerifyRequest<v2::PaymentPayload<v2::PaymentRequirements, Value>, v2::PaymentRequirements>;
+
+/// A rejected request body, rendered as a JSON error response.
+#[derive(Debug, Serialize)]
+pub struct BodyRejection {
+    #[serde(skip)]
+    status: StatusCode,
+    #[serde(rename = "error")]
+    kind: &'static str,
+    path: Option<String>,
+    message: String,
+}
+
+impl BodyRejection {
+    const fn bad_request(kind: &'static str, path: Option<String>, message: String) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            kind,
+            path,
+            message,
+        }
+    }
+}
+
+impl From<JsonRejection> for BodyRejection {
+    fn from(rejection: JsonRejection) -> Self {
+        let kind = match rejection {
+            JsonRejection::MissingJsonContentType(_) => "unsupported_media_type",
+            JsonRejection::JsonSyntaxError(_) => "syntax",
+            JsonRejection::JsonDataError(_) => "data",
+            _ => "body",
+        };
+        Self {
+            status: rejection.status(),
+            kind,
+            path: None,
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl IntoResponse for BodyRejection {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+/// Accepts a verify or settle body, or explains why it was rejected.
+///
+/// Checks the `x402Version` first, then that the payload and requirements
+/// have the fields and types the protocol requires.
+pub fn payment_body(body: Result<Json<Value>, JsonRejection>) -> Result<Value, BodyRejection> {
+    let Json(body) = body?;
+    check_version(&body)?;
+    if let Err(error) = serde_path_to_error::deserialize::<_, PaymentRequest>(&body) {
+        return Err(BodyRejection::bad_request(
+            "data",
+            Some(error.path().to_string()),
+            error.inner().to_string(),
+        ));
+    }
+    Ok(body)
+}
+
+/// Rejects bodies without an `x402Version` this facilitator supports.
+fn check_version(body: &Value) -> Result<(), BodyRejection> {
+    let invalid = |message: String| {
+        BodyRejection::bad_request("invalid_version", Some("x402Version".to_owned()), message)
+    };
+    let Some(version) = body.get("x402Version") else {
+        return Err(invalid("missing field `x402Version`".to_owned()));
+    };
+    match version.as_u64() {
+        Some(version) if SUPPORTED_VERSIONS.contains(&version) => Ok(()),
+        Some(version) => Err(invalid(format!(
+            "unsupported x402 version {version}, expected one of {SUPPORTED_VERSIONS:?}"
+        ))),
+        None => Err(invalid(format!(
+            "`x402Version` must be an integer, got {version}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::routing::post;
+    use axum::{Router, body};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn echo(body: Result<Json<Value>, JsonRejection>) -> Result<Json<Value>, BodyRejection> {
+        payment_body(body).map(Json)
+    }
+
+    async fn send(content_type: &str, body: String) -> (StatusCode, Value) {
+        let app = Router::new().route("/", post(echo));
+        let request = Request::post("/")
+            .header("content-type", content_type)
+            .body(Body::from(body))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = body::to_bytes(response.into_body(), 4096).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    fn request() -> Value {
+        let requirements = json!({
+            "scheme": "exact",
+            "network": "eip155:8453",
+            "amount": "1000",
+            "payTo": "0xdef",
+            "maxTimeoutSeconds": 60,
+            "asset": "0xusdc"
+        });
+        json!({
+            "x402Version": 2,
+            "paymentPayload": {
+                "accepted": requirements,
+                "payload": { "signature": "0xsignature" },
+                "resource": null,
+                "x402Version": 2
+            },
+            "paymentRequirements": requirements
+        })
+    }
+
+    #[tokio::test]
+    async fn well_formed_body_is_accepted() {
+        let (status, body) = send("application/json", request().to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, request());
+    }
+
+    #[tokio::test]
+    async fn wrong_content_type_is_unsupported_media_type() {
+        let (status, body) = send("text/plain", request().to_string()).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["error"], "unsupported_media_type");
+        assert_eq!(body["path"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn invalid_json_is_a_syntax_error() {
+        let (status, body) = send("application/json", "{\"x402Version\":".to_owned()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "syntax");
+        assert!(body["message"].as_str().unwrap().contains("line 1"));
+    }
+
+    #[tokio::test]
+    async fn failing_field_is_reported_by_path() {
+        let mut request = request();
+        request["paymentRequirements"]["maxTimeoutSeconds"] = json!("soon");
+        let (status, body) = send("application/json", request.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "data");
+        assert_eq!(body["path"], "paymentRequirements.maxTimeoutSeconds");
+
+        let mut request = self::request();
+        request["paymentPayload"]["accepted"]
+            .as_object_mut()
+            .unwrap()
+            .remove("network");
+        let (_, body) = send("application/json", request.to_string()).await;
+        assert_eq!(body["path"], "paymentPayload.accepted");
+        assert!(body["message"].as_str().unwrap().contains("`network`"));
+    }
+
+    #[tokio::test]
+    async fn version_is_checked_explicitly() {
+        let mut request = request();
+        request["x402Version"] = json!(1);
+        let (status, body) = send("application/json", request.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "invalid_version");
+        assert_eq!(body["path"], "x402Version");
+        assert!(body["message"].as_str().unwrap().contains("[2]"));
+
+        request.as_object_mut().unwrap().remove("x402Version");
+        let (_, body) = send("application/json", request.to_string()).await;
+        assert_eq!(body["error"], "invalid_version");
+        assert_eq!(body["message"], "missing field `x402Version`");
+    }
+}
@@ -0,0 +1,170 @@
+//! `x-request-id` generation and propagation.
+//!
+//! Every request gets an `x-request-id`: the one sent by the client, or a
+//! fresh UUID v4.  The ID is echoed in the response and recorded as the
+//! `request_id` field of the `http_request` span, so every log line written
+//! while handling the request carries it.
+//!
+//! A client ID ends up in logs and the audit log, so it is only kept if it is
+//! a single header of at most 128 characters from `[A-Za-z0-9._-]`; anything
+//! else is replaced by a UUID.
+
+use axum::Router;
+use axum::extract;
+use axum::http::{HeaderName, Request};
+use axum::middleware::map_request;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+
+/// Header carrying the request ID.
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client request ID that is kept.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Returns the request ID of `request`, or `""` if it has none.
+pub fn request_id<B>(request: &Request<B>) -> &str {
+    request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+/// Assign missing request IDs and echo them in responses.
+///
+/// Apply after every other layer, so the ID is set before tracing sees the
+/// request and is echoed on responses produced by other layers (e.g.
+/// timeouts).
+pub fn layer<S>(router: Router<S>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router
+        .layer(PropagateRequestIdLayer::new(X_REQUEST_ID))
+        .layer(SetRequestIdLayer::new(X_REQUEST_ID, MakeRequestUuid))
+        .layer(map_request(drop_invalid_request_id))
+}
+
+/// Remove a client request ID that is not acceptable, so a UUID is assigned
+/// instead.
+async fn drop_invalid_request_id(mut request: extract::Request) -> extract::Request {
+    let mut ids = request.headers().get_all(&X_REQUEST_ID).iter();
+    let valid = match (ids.next(), ids.next()) {
+        (Some(id), None) => is_valid_request_id(id.as_bytes()),
+        (None, _) => true,
+        (Some(_), Some(_)) => false,
+    };
+    if !valid {
+        request.headers_mut().remove(&X_REQUEST_ID);
+    }
+    request
+}
+
+/// Whether `id` is 1 to 128 characters from `[A-Za-z0-9._-]`.
+fn is_valid_request_id(id: &[u8]) -> bool {
+    (1..=MAX_REQUEST_ID_LEN).contains(&id.len())
+        && id
+            .iter()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'_' | b'-'))
+}
+
+/// Span maker used when the `telemetry` feature is disabled, so log lines
+/// still carry the request ID (see `telemetry::HttpMakeSpan` otherwise).
+#[cfg(not(feature = "telemetry"))]
+#[derive(Clone, Copy, Debug)]
+pub struct RequestSpan;
+
+#[cfg(not(feature = "telemetry"))]
+impl<B> tower_http::trace::MakeSpan<B> for RequestSpan {
+    fn make_span(&mut self, request: &Request<B>) -> tracing::Span {
+        tracing::info_span!(
+            "http_request",
+            method = %request.method(),
+            uri = %request.uri(),
+            request_id = request_id(request),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn app() -> Router {
+        layer(Router::new().route(
+            "/",
+            get(|request: Request<Body>| async move { request_id(&request).to_owned() }),
+        ))
+    }
+
+    async fn send(request: Request<Body>) -> (String, String) {
+        let response = app().oneshot(request).await.unwrap();
+        let header = response.headers()[&X_REQUEST_ID]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        (header, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn missing_request_id_is_generated() {
+        let (header, seen) = send(Request::get("/").body(Body::empty()).unwrap()).await;
+        assert_eq!(header.len(), 36);
+        assert_eq!(header, seen);
+    }
+
+    #[tokio::test]
+    async fn client_request_id_is_kept() {
+        let request = Request::get("/")
+            .header(X_REQUEST_ID, "support-1234")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(
+            send(request).await,
+            ("support-1234".to_owned(), "support-1234".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_request_id_is_replaced() {
+        let long = "a".repeat(MAX_REQUEST_ID_LEN + 1);
+        for id in ["bad id", "a/b", "<script>", long.as_str()] {
+            let request = Request::get("/")
+                .header(X_REQUEST_ID, id)
+                .body(Body::empty())
+                .unwrap();
+            let (header, seen) = send(request).await;
+            assert_ne!(header, id);
+            assert_eq!(header.len(), 36, "{id:?} replaced by a UUID");
+            assert_eq!(header, seen);
+        }
+
+        let request = Request::get("/")
+            .header(X_REQUEST_ID, "one")
+            .header(X_REQUEST_ID, "two")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(request).await.0.len(), 36);
+    }
+
+    #[test]
+    fn request_id_charset_and_length() {
+        assert!(is_valid_request_id(b"abc-DEF_1.2"));
+        assert!(is_valid_request_id(
+            "a".repeat(MAX_REQUEST_ID_LEN).as_bytes()
+        ));
+        assert!(!is_valid_request_id(b""));
+        assert!(!is_valid_request_id(b"a b"));
+        assert!(!is_valid_request_id(b"a/b"));
+        assert!(!is_valid_request_id(
+            "a".repeat(MAX_REQUEST_ID_LEN + 1).as_bytes()
+        ));
+    }
+}
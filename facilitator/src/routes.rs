@@ -1,31 +1,272 @@
 //! HTTP route handlers for the x402 facilitator.
 //!
 //! Protocol endpoints (`/verify`, `/settle`, `/supported`, `/health`).
+//! `/health` also reports chains whose circuit breaker is open.
 //! All payloads use JSON, compatible with official x402 client SDKs.
 //!
 //! Error handling follows the x402 wire protocol:
 //! - **Verification failures** → HTTP 200 + `VerifyResponse::Invalid`
 //! - **Settlement failures** → HTTP 200 + `SettleResponse::Error`
 //! - **Infrastructure errors** (only `/supported`) → HTTP 500
+//! - **Malformed request bodies** → HTTP 400 (415 for a wrong content type)
+//!   with a JSON error naming the failing field (see [`crate::rejection`])
+//!
+//! The `/verify` and `/settle` spans record the payment's network, scheme,
+//! x402 version, payer, `payTo`, asset and amount, plus the outcome, reason
+//! code and transaction hash.  Signatures and the rest of the signed payload
+//! are never recorded.  When `[audit]` is configured, the same fields, the
+//! request ID and the client address are also appended to the audit log (see
+//! [`crate::audit`]).  With `privacy.hash_payers`, payer addresses are
+//! replaced by keyed hashes in both.
+//!
+//! The payer is read from the request only for EVM payments, whose signed
+//! authorization names it.  A Solana payer is only known from the signed
+//! transaction, so it is recorded once the facilitator's response names it.
 
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
-use axum::extract::State;
 use axum::extract::rejection::JsonRejection;
+use axum::extract::{ConnectInfo, FromRef, FromRequestParts, State};
 use axum::http::StatusCode;
+use axum::http::request::Parts;
 use axum::routing::{get, post};
 use axum::{Json, Router, response::IntoResponse};
+use hmac::{Hmac, Mac};
 use r402::facilitator::Facilitator;
 use r402::proto;
-use serde_json::json;
-#[cfg(feature = "telemetry")]
-use tracing::instrument;
+use serde_json::{Value, json};
+use sha2::Sha256;
+use tracing::field::Empty;
+use tracing::{Span, instrument};
+
+use crate::audit::{AuditEntry, AuditLog, hex_encode};
+use crate::breaker::CircuitBreakers;
+use crate::rejection::payment_body;
+use crate::request_id::X_REQUEST_ID;
 
 /// Type alias for the shared facilitator state used by Axum route handlers.
 pub type FacilitatorState = Arc<dyn Facilitator>;
 
+/// Shared application state for the Axum router.
+#[derive(Clone)]
+pub struct AppState {
+    /// Facilitator serving the protocol endpoints.
+    pub facilitator: FacilitatorState,
+    /// Per-chain circuit breakers, reported by `/health`.
+    pub breakers: Arc<CircuitBreakers>,
+    /// How payer addresses are recorded on request spans and in the audit log.
+    pub payers: PayerRecording,
+    /// Audit log of verify and settle calls, if configured.
+    pub audit: Option<Arc<AuditLog>>,
+}
+
+/// How payer addresses are recorded on request spans and in the audit log.
+#[derive(Clone, Default)]
+pub enum PayerRecording {
+    /// The address as sent by the client.
+    #[default]
+    Plain,
+    /// Hex-encoded HMAC-SHA256 of the address, so records can still be
+    /// correlated by payer without storing the address itself.  The key
+    /// keeps the small space of addresses from being hashed and matched by
+    /// anyone reading the records.
+    Hashed(Hmac<Sha256>),
+}
+
+impl PayerRecording {
+    /// Hash payer addresses with `key`.
+    #[must_use]
+    pub fn hashed(key: &str) -> Self {
+        Self::Hashed(Hmac::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length"))
+    }
+
+    /// The form in which `payer` is recorded.
+    fn value(&self, payer: &str) -> String {
+        match self {
+            Self::Plain => payer.to_owned(),
+            Self::Hashed(mac) => hash_payer(mac.clone(), payer),
+        }
+    }
+}
+
+impl std::fmt::Debug for PayerRecording {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Plain => "Plain",
+            Self::Hashed(_) => "Hashed",
+        })
+    }
+}
+
+/// Hex-encoded HMAC-SHA256 of a payer address.
+///
+/// EVM addresses are lowercased first so checksummed and plain spellings hash
+/// alike; other addresses (e.g. base58 Solana keys) are case-sensitive.
+fn hash_payer(mut mac: Hmac<Sha256>, payer: &str) -> String {
+    if payer.starts_with("0x") {
+        mac.update(payer.to_ascii_lowercase().as_bytes());
+    } else {
+        mac.update(payer.as_bytes());
+    }
+    hex_encode(&mac.finalize().into_bytes())
+}
+
+/// Identity of the caller, for the audit log.
+#[derive(Debug)]
+struct Client {
+    /// `x-request-id` of the request.
+    request_id: String,
+    /// Peer address of the connection.
+    address: Option<IpAddr>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Client {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            request_id: parts
+                .headers
+                .get(X_REQUEST_ID)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_owned(),
+            address: parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(address)| address.ip()),
+        })
+    }
+}
+
+/// The payment fields of a verify or settle request, as an audit entry
+/// still lacking the outcome.
+///
+/// Only the payment requirements and the authorizing address are read; the
+/// signed payload itself is left alone.  The authorizing address is only
+/// present in EVM payloads (`authorization.from`); Solana payloads carry the
+/// payer inside the signed transaction, so it is left unset here.
+fn payment_fields(
+    body: &Value,
+    endpoint: &'static str,
+    client: Client,
+    payers: &PayerRecording,
+) -> AuditEntry {
+    let requirement = |name: &str| {
+        body.get("paymentRequirements")?
+            .get(name)?
+            .as_str()
+            .map(str::to_owned)
+    };
+    AuditEntry {
+        request_id: client.request_id,
+        client: client.address.map(|address| address.to_string()),
+        endpoint,
+        network: requirement("network"),
+        payer: body
+            .pointer("/paymentPayload/payload/authorization/from")
+            .and_then(Value::as_str)
+            .map(|payer| payers.value(payer)),
+        pay_to: requirement("payTo"),
+        asset: requirement("asset"),
+        amount: requirement("amount").or_else(|| requirement("maxAmountRequired")),
+        ..AuditEntry::default()
+    }
+}
+
+/// Record the payment fields of a verify or settle request on the current
+/// span.
+///
+/// The payer in `payment` is already in its recorded form.
+fn record_request(body: &Value, payment: &AuditEntry) {
+    let span = Span::current();
+    if let Some(version) = body.get("x402Version").and_then(Value::as_u64) {
+        span.record("x402.version", version);
+    }
+    if let Some(scheme) = body
+        .pointer("/paymentRequirements/scheme")
+        .and_then(Value::as_str)
+    {
+        span.record("x402.scheme", scheme);
+    }
+    for (attribute, value) in [
+        ("x402.network", &payment.network),
+        ("x402.pay_to", &payment.pay_to),
+        ("x402.asset", &payment.asset),
+        ("x402.amount", &payment.amount),
+    ] {
+        if let Some(value) = value {
+            span.record(attribute, value.as_str());
+        }
+    }
+    if let Some(payer) = &payment.payer {
+        span.record("x402.payer", payer.as_str());
+    }
+}
+
+/// Record the outcome of a call on the current span and in `entry`.
+fn record_outcome(
+    entry: &mut AuditEntry,
+    payers: &PayerRecording,
+    outcome: &'static str,
+    reason: Option<&str>,
+    payer: Option<&str>,
+    transaction: Option<&str>,
+) {
+    let span = Span::current();
+    span.record("x402.outcome", outcome);
+    entry.outcome = outcome;
+    if let Some(reason) = reason {
+        span.record("x402.reason", reason);
+        entry.reason = Some(reason.to_owned());
+    }
+    if let Some(payer) = payer {
+        let payer = payers.value(payer);
+        span.record("x402.payer", payer.as_str());
+        entry.payer = Some(payer);
+    }
+    if let Some(transaction) = transaction {
+        span.record("x402.transaction", transaction);
+        entry.transaction = Some(transaction.to_owned());
+    }
+}
+
+impl std::fmt::Debug for AppState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppState")
+            .field("breakers", &self.breakers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FromRef<AppState> for FacilitatorState {
+    fn from_ref(state: &AppState) -> Self {
+        Arc::clone(&state.facilitator)
+    }
+}
+
+impl FromRef<AppState> for PayerRecording {
+    fn from_ref(state: &AppState) -> Self {
+        state.payers.clone()
+    }
+}
+
+impl FromRef<AppState> for Option<Arc<AuditLog>> {
+    fn from_ref(state: &AppState) -> Self {
+        state.audit.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<CircuitBreakers> {
+    fn from_ref(state: &AppState) -> Self {
+        Self::clone(&state.breakers)
+    }
+}
+
 /// Creates the Axum router with all x402 facilitator endpoints.
-pub fn routes() -> Router<FacilitatorState> {
+pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", get(get_root))
         .route("/verify", post(post_verify))
@@ -35,7 +276,7 @@ pub fn routes() -> Router<FacilitatorState> {
 }
 
 /// `GET /` — simple greeting.
-#[cfg_attr(feature = "telemetry", instrument(skip_all))]
+#[instrument(skip_all)]
 async fn get_root() -> impl IntoResponse {
     (
         StatusCode::OK,
@@ -44,18 +285,33 @@ async fn get_root() -> impl IntoResponse {
 }
 
 /// `GET /health` — lightweight liveness check.
-#[cfg_attr(feature = "telemetry", instrument(skip_all))]
-async fn get_health() -> impl IntoResponse {
-    (StatusCode::OK, Json(json!({ "status": "ok" })))
+///
+/// Reports `"degraded"` with the affected chains while any circuit breaker is
+/// open; the process itself is still live, so the status code stays 200.
+#[instrument(skip_all)]
+async fn get_health(State(breakers): State<Arc<CircuitBreakers>>) -> impl IntoResponse {
+    let open_circuits: Vec<String> = breakers
+        .open_chains()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let status = if open_circuits.is_empty() {
+        "ok"
+    } else {
+        "degraded"
+    };
+    (
+        StatusCode::OK,
+        Json(json!({ "status": status, "open_circuits": open_circuits })),
+    )
 }
 
 /// `GET /supported` — lists supported payment schemes and networks.
-#[cfg_attr(feature = "telemetry", instrument(skip_all))]
+#[instrument(skip_all)]
 async fn get_supported(State(facilitator): State<FacilitatorState>) -> impl IntoResponse {
     match facilitator.supported().await {
         Ok(supported) => (StatusCode::OK, Json(json!(supported))).into_response(),
         Err(error) => {
-            #[cfg(feature = "telemetry")]
             tracing::error!(error = ?error, "Failed to query supported schemes");
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -70,26 +326,54 @@ async fn get_supported(State(facilitator): State<FacilitatorState>) -> impl Into
 ///
 /// All errors are converted to `VerifyResponse::Invalid` (HTTP 200) to preserve
 /// structured reason codes on the wire.
-#[cfg_attr(feature = "telemetry", instrument(skip_all))]
+#[instrument(skip_all, fields(
+    x402.network = Empty,
+    x402.scheme = Empty,
+    x402.version = Empty,
+    x402.payer = Empty,
+    x402.pay_to = Empty,
+    x402.asset = Empty,
+    x402.amount = Empty,
+    x402.outcome = Empty,
+    x402.reason = Empty,
+))]
 async fn post_verify(
     State(facilitator): State<FacilitatorState>,
-    body: Result<Json<proto::VerifyRequest>, JsonRejection>,
+    State(payers): State<PayerRecording>,
+    State(audit): State<Option<Arc<AuditLog>>>,
+    client: Client,
+    body: Result<Json<Value>, JsonRejection>,
 ) -> impl IntoResponse {
-    let Ok(Json(request)) = body else {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "invalid request body" })),
-        )
-            .into_response();
+    let body = match payment_body(body) {
+        Ok(body) => body,
+        Err(rejection) => return rejection.into_response(),
     };
-    let response = match facilitator.verify(request).await {
+    let mut entry = payment_fields(&body, "verify", client, &payers);
+    record_request(&body, &entry);
+    let response = match facilitator.verify(body.into()).await {
         Ok(resp) => resp,
         Err(ref error) => {
-            #[cfg(feature = "telemetry")]
             tracing::warn!(?error, "verification failed");
             proto::VerifyResponse::from_facilitator_error(error)
         }
     };
+    match &response {
+        proto::VerifyResponse::Valid { payer } => {
+            record_outcome(&mut entry, &payers, "valid", None, Some(payer), None);
+        }
+        proto::VerifyResponse::Invalid { reason, payer, .. } => record_outcome(
+            &mut entry,
+            &payers,
+            "invalid",
+            Some(reason),
+            payer.as_deref(),
+            None,
+        ),
+        _ => {}
+    }
+    if let Some(audit) = audit {
+        audit.record(entry);
+    }
     (StatusCode::OK, Json(json!(response))).into_response()
 }
 
@@ -97,26 +381,247 @@ async fn post_verify(
 ///
 /// All errors are converted to `SettleResponse::Error` (HTTP 200) to preserve
 /// structured reason codes on the wire.
-#[cfg_attr(feature = "telemetry", instrument(skip_all))]
+#[instrument(skip_all, fields(
+    x402.network = Empty,
+    x402.scheme = Empty,
+    x402.version = Empty,
+    x402.payer = Empty,
+    x402.pay_to = Empty,
+    x402.asset = Empty,
+    x402.amount = Empty,
+    x402.outcome = Empty,
+    x402.reason = Empty,
+    x402.transaction = Empty,
+))]
 async fn post_settle(
     State(facilitator): State<FacilitatorState>,
-    body: Result<Json<proto::SettleRequest>, JsonRejection>,
+    State(payers): State<PayerRecording>,
+    State(audit): State<Option<Arc<AuditLog>>>,
+    client: Client,
+    body: Result<Json<Value>, JsonRejection>,
 ) -> impl IntoResponse {
-    let Ok(Json(request)) = body else {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "invalid request body" })),
-        )
-            .into_response();
+    let body = match payment_body(body) {
+        Ok(body) => body,
+        Err(rejection) => return rejection.into_response(),
     };
+    let mut entry = payment_fields(&body, "settle", client, &payers);
+    record_request(&body, &entry);
+    // Recorded up front, so a settlement cut off by the request timeout or a
+    // client disconnect still leaves a trace.
+    if let Some(audit) = &audit {
+        audit.record(AuditEntry {
+            outcome: "attempted",
+            ..entry.clone()
+        });
+    }
+    let request = proto::SettleRequest::from(body);
     let network = request.network().to_owned();
     let response = match facilitator.settle(request).await {
-        Ok(resp) => resp,
+        Ok(resp) => {
+            match &resp {
+                proto::SettleResponse::Success {
+                    transaction,
+                    network,
+                    ..
+                } => tracing::info!(%network, %transaction, "settlement succeeded"),
+                proto::SettleResponse::Error {
+                    reason, network, ..
+                } => tracing::warn!(%network, %reason, "settlement rejected"),
+                _ => {}
+            }
+            resp
+        }
         Err(ref error) => {
-            #[cfg(feature = "telemetry")]
             tracing::warn!(?error, "settlement failed");
             proto::SettleResponse::from_facilitator_error(error, network)
         }
     };
+    match &response {
+        proto::SettleResponse::Success {
+            payer, transaction, ..
+        } => record_outcome(
+            &mut entry,
+            &payers,
+            "success",
+            None,
+            Some(payer),
+            Some(transaction),
+        ),
+        proto::SettleResponse::Error { reason, payer, .. } => record_outcome(
+            &mut entry,
+            &payers,
+            "error",
+            Some(reason),
+            payer.as_deref(),
+            None,
+        ),
+        _ => {}
+    }
+    if let Some(audit) = audit {
+        audit.record(entry);
+    }
     (StatusCode::OK, Json(json!(response))).into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+
+    use super::*;
+
+    /// Collects `name=value` pairs recorded on spans.
+    #[derive(Debug, Clone, Default)]
+    struct Recorded(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Recorded {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{}={value:?}", field.name()));
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for Recorded {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            attrs.record(&mut self.clone());
+        }
+
+        fn on_record(&self, _: &Id, values: &Record<'_>, _: Context<'_, S>) {
+            values.record(&mut self.clone());
+        }
+    }
+
+    fn recorded_fields(body: &Value, payers: &PayerRecording) -> Vec<String> {
+        let recorded = Recorded::default();
+        let subscriber = tracing_subscriber::registry().with(recorded.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "request",
+                x402.network = Empty,
+                x402.scheme = Empty,
+                x402.version = Empty,
+                x402.payer = Empty,
+                x402.pay_to = Empty,
+                x402.asset = Empty,
+                x402.amount = Empty,
+            );
+            let payment = payment_fields(body, "verify", client(), payers);
+            span.in_scope(|| record_request(body, &payment));
+        });
+        recorded.0.lock().unwrap().clone()
+    }
+
+    fn client() -> Client {
+        Client {
+            request_id: "req-1".to_owned(),
+            address: Some(IpAddr::from([10, 0, 0, 1])),
+        }
+    }
+
+    fn body() -> Value {
+        json!({
+            "x402Version": 2,
+            "paymentPayload": {
+                "payload": {
+                    "signature": "0xsignature",
+                    "authorization": { "from": "0xAbC", "to": "0xdef" }
+                }
+            },
+            "paymentRequirements": {
+                "scheme": "exact",
+                "network": "eip155:8453",
+                "amount": "1000",
+                "asset": "0xusdc",
+                "payTo": "0xdef"
+            }
+        })
+    }
+
+    #[test]
+    fn request_fields_are_recorded_without_signature() {
+        let fields = recorded_fields(&body(), &PayerRecording::Plain);
+        for expected in [
+            "x402.version=2",
+            "x402.network=\"eip155:8453\"",
+            "x402.scheme=\"exact\"",
+            "x402.pay_to=\"0xdef\"",
+            "x402.asset=\"0xusdc\"",
+            "x402.amount=\"1000\"",
+            "x402.payer=\"0xAbC\"",
+        ] {
+            assert!(
+                fields.iter().any(|f| f == expected),
+                "{expected} in {fields:?}"
+            );
+        }
+        assert!(!fields.iter().any(|f| f.contains("0xsignature")));
+    }
+
+    #[test]
+    fn audit_entry_takes_payment_fields_and_outcome() {
+        let mut entry = payment_fields(&body(), "settle", client(), &PayerRecording::Plain);
+        record_outcome(
+            &mut entry,
+            &PayerRecording::Plain,
+            "success",
+            None,
+            Some("0xAbC"),
+            Some("0xtx"),
+        );
+        assert_eq!(
+            entry,
+            AuditEntry {
+                request_id: "req-1".to_owned(),
+                client: Some("10.0.0.1".to_owned()),
+                endpoint: "settle",
+                network: Some("eip155:8453".to_owned()),
+                payer: Some("0xAbC".to_owned()),
+                pay_to: Some("0xdef".to_owned()),
+                asset: Some("0xusdc".to_owned()),
+                amount: Some("1000".to_owned()),
+                outcome: "success",
+                reason: None,
+                transaction: Some("0xtx".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn payers_can_be_hashed() {
+        let payers = PayerRecording::hashed("secret");
+        let hash = |payer: &str| payers.value(payer);
+        let fields = recorded_fields(&body(), &payers);
+        let payer = fields
+            .iter()
+            .find(|f| f.starts_with("x402.payer="))
+            .unwrap();
+        assert_eq!(payer, &format!("x402.payer={:?}", hash("0xabc")));
+        assert!(!payer.contains("0xAbC"));
+
+        assert_eq!(hash("0xABC"), hash("0xabc"));
+        assert_ne!(hash("SoLAddr"), hash("soladdr"));
+        assert_eq!(hash("x").len(), 64);
+        assert_ne!(hash("x"), PayerRecording::hashed("other").value("x"));
+        // HMAC-SHA256("secret", "x"), not a bare SHA-256 of the address.
+        assert_eq!(
+            hash("x"),
+            "117eca332f7e13ccb8e4574e4f33daa212a9231353670c2b8b4797df0bb77afa"
+        );
+    }
+
+    #[test]
+    fn audit_entry_payer_is_hashed() {
+        let payers = PayerRecording::hashed("secret");
+        let mut entry = payment_fields(&body(), "verify", client(), &payers);
+        assert_eq!(entry.payer, Some(payers.value("0xabc")));
+        record_outcome(&mut entry, &payers, "valid", None, Some("SoLAddr"), None);
+        assert_eq!(entry.payer, Some(payers.value("SoLAddr")));
+        assert!(!entry.payer.unwrap().contains("SoLAddr"));
+    }
+}
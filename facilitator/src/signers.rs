@@ -1,4 +1,4 @@
-//! Global signer configuration with environment variable resolution.
+//! Global signer configuration.
 //!
 //! This module handles the `[signers]` section of the TOML config, providing:
 //!
@@ -13,96 +13,66 @@
 
 use std::collections::BTreeMap;
 
-use crate::error::Error;
+use schemars::JsonSchema;
+use serde::Deserialize;
 
-/// Resolve an environment-variable reference (`$VAR` or `${VAR}`), returning
-/// the literal string unchanged if it does not match either pattern.
-fn resolve_env(value: &str) -> Result<String, Error> {
-    // ${VAR} syntax — safe pattern-based extraction without byte indexing.
-    if let Some(var_name) = value.strip_prefix("${").and_then(|s| s.strip_suffix('}')) {
-        return lookup_env(var_name, value);
-    }
-    // $VAR syntax — only valid when the remainder is a well-formed identifier.
-    if let Some(var_name) = value.strip_prefix('$')
-        && !var_name.is_empty()
-        && var_name
-            .bytes()
-            .all(|b| b.is_ascii_alphanumeric() || b == b'_')
-    {
-        return lookup_env(var_name, value);
-    }
-    // Literal value — no env-var reference detected.
-    Ok(value.to_owned())
-}
-
-/// Look up an environment variable by name, returning a contextual error on
-/// failure that includes both the resolved variable name and the original
-/// reference string from the config file.
-fn lookup_env(var_name: &str, original: &str) -> Result<String, Error> {
-    std::env::var(var_name).map_err(|_| {
-        Error::signer(format!(
-            "env var '{var_name}' not found (referenced as '{original}')"
-        ))
-    })
-}
+use crate::error::Error;
 
-/// Resolve a signer value: if it is a string, resolve env vars; if it is an
-/// array, resolve each element.
-fn resolve_signer_value(val: &toml::Value) -> Result<toml::Value, Error> {
-    match val {
-        toml::Value::String(s) => Ok(toml::Value::String(resolve_env(s)?)),
-        toml::Value::Array(arr) => {
-            let resolved: Result<Vec<_>, _> = arr
-                .iter()
-                .map(|v| {
-                    if let toml::Value::String(s) = v {
-                        Ok(toml::Value::String(resolve_env(s)?))
-                    } else {
-                        Ok(v.clone())
-                    }
-                })
-                .collect();
-            Ok(toml::Value::Array(resolved?))
-        }
-        other => Ok(other.clone()),
-    }
+/// The `[signers]` table.
+///
+/// Also describes the table for the config schema and the unknown-key check.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct GlobalSigners {
+    /// EVM signer private keys (hex, 0x-prefixed), used by every `eip155:` chain
+    /// without its own `signers`.
+    #[serde(default)]
+    pub evm: Option<Vec<String>>,
+    /// Solana signer keypair (base58), used by every `solana:` chain without
+    /// its own `signer`.
+    #[serde(default)]
+    pub solana: Option<String>,
 }
 
-/// Pre-process raw TOML: extract `[signers]`, resolve env vars, and inject
-/// signers into each chain entry.
+/// Pre-process raw TOML: extract `[signers]` and inject signers into each
+/// chain entry.
 ///
-/// Returns the TOML document (as a `BTreeMap`) ready for scheme generation and
+/// Env-var references have already been expanded by the config loader at
+/// this point.
+///
+/// Leaves the TOML document (as a `BTreeMap`) ready for scheme generation and
 /// final deserialization.
 ///
 /// # Errors
 ///
-/// Returns an error if environment variable resolution fails.
+/// Returns an error if the `[signers]` table has values of the wrong type.
 pub fn preprocess_signers(doc: &mut BTreeMap<String, toml::Value>) -> Result<(), Error> {
-    let signers_table = doc.remove("signers");
-
-    let mut evm_signers: Option<toml::Value> = None;
-    let mut solana_signer: Option<toml::Value> = None;
-
-    if let Some(toml::Value::Table(signers)) = &signers_table {
-        if let Some(evm_val) = signers.get("evm") {
-            evm_signers = Some(resolve_signer_value(evm_val)?);
-        }
-        if let Some(sol_val) = signers.get("solana") {
-            solana_signer = Some(resolve_signer_value(sol_val)?);
-        }
-    }
-
-    // Inject global signers into chain entries that don't have their own
+    let global: GlobalSigners = doc
+        .remove("signers")
+        .map(toml::Value::try_into)
+        .transpose()
+        .map_err(|e| Error::config_with("invalid [signers] table", e))?
+        .unwrap_or_default();
+    let evm_signers = global
+        .evm
+        .map(|keys| toml::Value::Array(keys.into_iter().map(toml::Value::String).collect()));
+    let solana_signer = global.solana.map(toml::Value::String);
+
+    // Inject global signers into chain entries that don't have their own,
+    // leaving families this build does not include alone.
+    let evm_enabled = cfg!(feature = "chain-eip155");
+    let solana_enabled = cfg!(feature = "chain-solana");
     if let Some(toml::Value::Table(chains)) = doc.get_mut("chains") {
         for (chain_id, chain_val) in chains.iter_mut() {
             if let toml::Value::Table(chain_table) = chain_val {
-                if chain_id.starts_with("eip155:") {
+                if evm_enabled && chain_id.starts_with("eip155:") {
                     if !chain_table.contains_key("signers")
                         && let Some(ref signers_val) = evm_signers
                     {
                         chain_table.insert("signers".to_owned(), signers_val.clone());
                     }
-                } else if chain_id.starts_with("solana:")
+                } else if solana_enabled
+                    && chain_id.starts_with("solana:")
                     && !chain_table.contains_key("signer")
                     && let Some(ref signer_val) = solana_signer
                 {
@@ -111,7 +81,6 @@ pub fn preprocess_signers(doc: &mut BTreeMap<String, toml::Value>) -> Result<(),
             }
         }
     }
-
     Ok(())
 }
 
@@ -119,86 +88,7 @@ pub fn preprocess_signers(doc: &mut BTreeMap<String, toml::Value>) -> Result<(),
 mod tests {
     use super::*;
 
-    // Helper to set an env var in test context.
-    //
-    // SAFETY: only called from single-threaded test functions.
-    #[allow(unsafe_code, clippy::disallowed_methods)]
-    fn set_test_env(key: &str, value: &str) {
-        unsafe { std::env::set_var(key, value) };
-    }
-
-    #[allow(unsafe_code, clippy::disallowed_methods)]
-    fn remove_test_env(key: &str) {
-        unsafe { std::env::remove_var(key) };
-    }
-
-    #[test]
-    fn literal_value_unchanged() {
-        assert_eq!(resolve_env("0x1234abcd").unwrap(), "0x1234abcd");
-        assert_eq!(resolve_env("plain-text").unwrap(), "plain-text");
-        assert_eq!(resolve_env("").unwrap(), "");
-    }
-
-    #[test]
-    fn bare_dollar_is_literal() {
-        assert_eq!(resolve_env("$").unwrap(), "$");
-    }
-
-    #[test]
-    fn dollar_with_special_chars_is_literal() {
-        assert_eq!(resolve_env("$not-a-var!").unwrap(), "$not-a-var!");
-        assert_eq!(resolve_env("$has spaces").unwrap(), "$has spaces");
-    }
-
-    #[test]
-    fn dollar_brace_syntax_resolves() {
-        set_test_env("_FACILITATOR_TEST_A", "resolved_a");
-        let result = resolve_env("${_FACILITATOR_TEST_A}");
-        remove_test_env("_FACILITATOR_TEST_A");
-        assert_eq!(result.unwrap(), "resolved_a");
-    }
-
-    #[test]
-    fn dollar_syntax_resolves() {
-        set_test_env("_FACILITATOR_TEST_B", "resolved_b");
-        let result = resolve_env("$_FACILITATOR_TEST_B");
-        remove_test_env("_FACILITATOR_TEST_B");
-        assert_eq!(result.unwrap(), "resolved_b");
-    }
-
-    #[test]
-    fn missing_env_var_returns_error() {
-        assert!(resolve_env("${_FACILITATOR_NONEXISTENT}").is_err());
-        assert!(resolve_env("$_FACILITATOR_NONEXISTENT").is_err());
-    }
-
-    #[test]
-    fn resolve_string_literal() {
-        let val = toml::Value::String("0xkey".into());
-        let resolved = resolve_signer_value(&val).unwrap();
-        assert_eq!(resolved.as_str(), Some("0xkey"));
-    }
-
-    #[test]
-    fn resolve_array_of_literals() {
-        let val = toml::Value::Array(vec![
-            toml::Value::String("k1".into()),
-            toml::Value::String("k2".into()),
-        ]);
-        let resolved = resolve_signer_value(&val).unwrap();
-        let arr = resolved.as_array().unwrap();
-        assert_eq!(arr.len(), 2);
-        assert_eq!(arr[0].as_str(), Some("k1"));
-        assert_eq!(arr[1].as_str(), Some("k2"));
-    }
-
-    #[test]
-    fn resolve_non_string_passthrough() {
-        let val = toml::Value::Integer(42);
-        let resolved = resolve_signer_value(&val).unwrap();
-        assert_eq!(resolved.as_integer(), Some(42));
-    }
-
+    #[cfg(feature = "chain-eip155")]
     #[test]
     fn global_evm_signers_injected() {
         let toml_str = r#"
@@ -241,6 +131,7 @@ fn per_chain_signer_not_overridden() {
         assert_eq!(signers[0].as_str(), Some("0xlocal"));
     }
 
+    #[cfg(feature = "chain-solana")]
     #[test]
     fn global_solana_signer_injected() {
         let toml_str = r#"
@@ -268,7 +159,11 @@ fn no_signers_section_is_ok() {
 signers = ["0xlocal"]
 "#;
         let mut doc: BTreeMap<String, toml::Value> = toml::from_str(toml_str).unwrap();
-        assert!(preprocess_signers(&mut doc).is_ok());
+        preprocess_signers(&mut doc).unwrap();
+        assert_eq!(
+            doc["chains"]["eip155:84532"]["signers"][0].as_str(),
+            Some("0xlocal")
+        );
     }
 
     #[test]
@@ -278,8 +173,16 @@ fn empty_chains_section_is_ok() {
 evm = ["0xkey"]
 "#;
         let mut doc: BTreeMap<String, toml::Value> = toml::from_str(toml_str).unwrap();
-        assert!(preprocess_signers(&mut doc).is_ok());
+        preprocess_signers(&mut doc).unwrap();
         // [signers] should still be removed
         assert!(!doc.contains_key("signers"));
     }
+
+    #[test]
+    fn mistyped_signers_error() {
+        let mut doc: BTreeMap<String, toml::Value> =
+            toml::from_str("[signers]\nevm = \"0xkey\"\n").unwrap();
+        let err = preprocess_signers(&mut doc).unwrap_err();
+        assert!(err.to_string().contains("[signers]"));
+    }
 }
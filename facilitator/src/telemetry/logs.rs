@@ -0,0 +1,232 @@
+//! Bridge from `tracing` events to `OpenTelemetry` log records.
+//!
+//! [`LogBridge`] turns every event that passes the log filter into an OTLP
+//! log record: the `message` field becomes the body, the remaining fields
+//! become attributes, and events inside an `OpenTelemetry` span carry its
+//! trace and span ID so logs line up with traces in the collector.
+//!
+//! Events emitted by the exporter stack itself are skipped, since exporting
+//! them would produce more of them.
+
+use std::time::SystemTime;
+
+use opentelemetry::logs::{AnyValue, LogRecord, Logger, Severity};
+use opentelemetry::trace::{SpanId, TraceId};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Targets of the crates that export telemetry; their events are not
+/// bridged.
+const EXPORTER_TARGETS: [&str; 10] = [
+    "opentelemetry",
+    "opentelemetry_sdk",
+    "opentelemetry_otlp",
+    "opentelemetry_http",
+    "tonic",
+    "h2",
+    "hyper",
+    "hyper_util",
+    "reqwest",
+    "tower",
+];
+
+/// Layer emitting `tracing` events as `OpenTelemetry` log records.
+#[derive(Debug)]
+pub struct LogBridge<L> {
+    logger: L,
+}
+
+impl<L> LogBridge<L> {
+    /// Emit records through `logger`.
+    pub const fn new(logger: L) -> Self {
+        Self { logger }
+    }
+}
+
+/// Severity number of a `tracing` level.
+const fn severity(level: Level) -> Severity {
+    match level {
+        Level::TRACE => Severity::Trace,
+        Level::DEBUG => Severity::Debug,
+        Level::INFO => Severity::Info,
+        Level::WARN => Severity::Warn,
+        Level::ERROR => Severity::Error,
+    }
+}
+
+/// Returns `true` if `target` belongs to the exporter stack.
+fn is_exporter_target(target: &str) -> bool {
+    EXPORTER_TARGETS.iter().any(|crate_name| {
+        target
+            .strip_prefix(crate_name)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    })
+}
+
+/// Copies event fields into a log record.
+struct RecordVisitor<'a, R>(&'a mut R);
+
+impl<R: LogRecord> RecordVisitor<'_, R> {
+    fn record(&mut self, field: &Field, value: AnyValue) {
+        if field.name() == "message" {
+            self.0.set_body(value);
+        } else {
+            self.0.add_attribute(field.name(), value);
+        }
+    }
+}
+
+impl<R: LogRecord> Visit for RecordVisitor<'_, R> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.record(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match i64::try_from(value) {
+            Ok(value) => self.record(field, value.into()),
+            Err(_) => self.record(field, value.to_string().into()),
+        }
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.to_owned().into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record(field, format!("{value:?}").into());
+    }
+}
+
+impl<S, L> Layer<S> for LogBridge<L>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    L: Logger + Send + Sync + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if is_exporter_target(metadata.target()) {
+            return;
+        }
+
+        let mut record = self.logger.create_log_record();
+        record.set_target(metadata.target());
+        record.set_event_name(metadata.name());
+        record.set_timestamp(SystemTime::now());
+        record.set_severity_number(severity(*metadata.level()));
+        record.set_severity_text(metadata.level().as_str());
+        event.record(&mut RecordVisitor(&mut record));
+
+        if let Some(span) = ctx.event_span(event) {
+            let extensions = span.extensions();
+            if let Some(data) = extensions.get::<OtelData>()
+                && let Some(trace_id) = data.trace_id().filter(|id| *id != TraceId::INVALID)
+                && let Some(span_id) = data.span_id().filter(|id| *id != SpanId::INVALID)
+            {
+                record.set_trace_context(trace_id, span_id, None);
+            }
+        }
+
+        self.logger.emit(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use opentelemetry::InstrumentationScope;
+    use opentelemetry::logs::LoggerProvider;
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::logs::{LogProcessor, SdkLogRecord, SdkLoggerProvider};
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    /// Keeps every emitted record.
+    #[derive(Debug, Clone, Default)]
+    struct Collect(Arc<Mutex<Vec<SdkLogRecord>>>);
+
+    impl LogProcessor for Collect {
+        fn emit(&self, record: &mut SdkLogRecord, _: &InstrumentationScope) {
+            self.0.lock().unwrap().push(record.clone());
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn events_become_log_records_with_trace_context() {
+        let collect = Collect::default();
+        let logger_provider = SdkLoggerProvider::builder()
+            .with_log_processor(collect.clone())
+            .build();
+        let tracer_provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer("test")))
+            .with(LogBridge::new(logger_provider.logger("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("request").in_scope(|| {
+                tracing::warn!(
+                    network = "eip155:8453",
+                    attempts = 3_u64,
+                    "settlement rejected"
+                );
+            });
+            tracing::info!(target: "opentelemetry_sdk", "exporter noise");
+        });
+
+        let records = collect.0.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(
+            record.body(),
+            Some(&AnyValue::from("settlement rejected".to_owned()))
+        );
+        assert_eq!(record.severity_number(), Some(Severity::Warn));
+        let attributes: Vec<_> = record
+            .attributes_iter()
+            .map(|(key, value)| (key.as_str().to_owned(), value.clone()))
+            .collect();
+        assert!(attributes.contains(&(
+            "network".to_owned(),
+            AnyValue::from("eip155:8453".to_owned())
+        )));
+        assert!(attributes.contains(&("attempts".to_owned(), AnyValue::Int(3))));
+        assert!(record.trace_context().is_some());
+    }
+
+    #[test]
+    fn exporter_targets_are_matched_by_crate() {
+        assert!(is_exporter_target("opentelemetry_sdk"));
+        assert!(is_exporter_target("opentelemetry"));
+        assert!(is_exporter_target("hyper::proto::h1"));
+        assert!(is_exporter_target("h2"));
+        assert!(!is_exporter_target("facilitator::routes"));
+        assert!(!is_exporter_target("h2o"));
+        assert!(!is_exporter_target("towers"));
+    }
+
+    #[test]
+    fn levels_map_to_severities() {
+        assert_eq!(severity(Level::TRACE), Severity::Trace);
+        assert_eq!(severity(Level::INFO), Severity::Info);
+        assert_eq!(severity(Level::ERROR), Severity::Error);
+    }
+}
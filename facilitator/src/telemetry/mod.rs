@@ -0,0 +1,618 @@
+//! `OpenTelemetry` tracing, metrics and logs setup.
+//!
+//! Provides [`Telemetry`] for configuring distributed tracing, metrics
+//! collection and log export via OTLP exporters; `tracing` events reach the
+//! logs pipeline through [`LogBridge`].  Only available with the `telemetry`
+//! feature; console logging itself lives in [`logging`](crate::logging).
+//!
+//! Export is configured by the `[telemetry]` config section
+//! ([`TelemetryConfig`]) and the standard `OTEL_*` environment variables;
+//! a variable that is set wins over the matching config key.
+//!
+//! Incoming requests carrying a W3C `traceparent` header continue the
+//! caller's trace: the `http_request` span is parented to the extracted
+//! context, so a resource server's trace shows verification and settlement
+//! as child spans.  Outgoing RPC requests carry the current span's context
+//! the same way (see [`inject_trace_context`]), so RPC providers that trace
+//! requests can join the facilitator's trace.
+
+mod logs;
+
+use std::collections::HashMap;
+use std::env;
+use std::time::Duration;
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Request, Response};
+use opentelemetry::logs::LoggerProvider;
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::trace::{Status, TracerProvider};
+use opentelemetry::{KeyValue, Value, global};
+use opentelemetry_otlp::tonic_types::metadata::MetadataMap;
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig, WithTonicConfig};
+use opentelemetry_sdk::{
+    Resource,
+    logs::SdkLoggerProvider,
+    metrics::{MeterProviderBuilder, PeriodicReader, SdkMeterProvider},
+    propagation::TraceContextPropagator,
+    trace::{RandomIdGenerator, Sampler, SdkTracerProvider},
+};
+use opentelemetry_semantic_conventions::{
+    SCHEMA_URL,
+    attribute::{DEPLOYMENT_ENVIRONMENT_NAME, SERVICE_VERSION},
+};
+use tower_http::trace::{MakeSpan, OnResponse, TraceLayer};
+use tracing::Span;
+use tracing_opentelemetry::{MetricsLayer, OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::config::{OtlpProtocol, TelemetryConfig};
+use crate::error::Error;
+use crate::logging::{self, LogFormat, LogOutput};
+use crate::request_id::request_id;
+
+use self::logs::LogBridge;
+
+/// Returns the value of `env_key` if it is set to something non-blank.
+fn env_value(env_key: &str) -> Option<String> {
+    env::var(env_key).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// Resolve an env var with a programmatic fallback.
+fn resolve_env(env_key: &str, fallback: Option<&Value>) -> Option<Value> {
+    env_value(env_key)
+        .map(Value::from)
+        .or_else(|| fallback.cloned())
+}
+
+/// Keys set by `OTEL_RESOURCE_ATTRIBUTES` (`key=value,key=value`).
+fn env_resource_keys() -> Vec<String> {
+    env_value("OTEL_RESOURCE_ATTRIBUTES")
+        .map(|attributes| {
+            attributes
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(key, _)| key.trim().to_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Service identity for telemetry resources.
+///
+/// Values can be set programmatically or overridden via `OTEL_SERVICE_NAME`,
+/// `OTEL_SERVICE_VERSION`, `OTEL_SERVICE_DEPLOYMENT` environment variables.
+#[derive(Debug, Default)]
+pub struct Telemetry {
+    name: Option<Value>,
+    version: Option<Value>,
+    deployment: Option<Value>,
+    log_level: Option<String>,
+    log_format: LogFormat,
+    log_output: LogOutput,
+    config: TelemetryConfig,
+}
+
+impl Telemetry {
+    /// Creates a new, empty [`Telemetry`] instance.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the service name.
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<Value>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the service version.
+    #[must_use]
+    pub fn with_version(mut self, version: impl Into<Value>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Sets the log level filter used when `RUST_LOG` is not set.
+    ///
+    /// Accepts any valid [`EnvFilter`](tracing_subscriber::EnvFilter) directive string
+    /// (e.g. `"debug"`, `"facilitator=debug,r402=trace"`).
+    #[must_use]
+    pub fn with_log_level(mut self, level: impl Into<String>) -> Self {
+        self.log_level = Some(level.into());
+        self
+    }
+
+    /// Sets the format and output stream of console log lines.
+    #[must_use]
+    pub const fn with_log_format(mut self, format: LogFormat, output: LogOutput) -> Self {
+        self.log_format = format;
+        self.log_output = output;
+        self
+    }
+
+    /// Sets the OTLP export settings from the `[telemetry]` config section.
+    #[must_use]
+    pub fn with_config(mut self, config: TelemetryConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The OTLP protocol to export with, or `None` if export is not
+    /// configured by either the environment or the config.
+    fn protocol(&self) -> Option<OtlpProtocol> {
+        let from_env = ["ENDPOINT", "HEADERS", "PROTOCOL"]
+            .iter()
+            .any(|var| env::var(format!("OTEL_EXPORTER_OTLP_{var}")).is_ok());
+        (from_env || self.config.endpoint.is_some()).then(|| {
+            env::var("OTEL_EXPORTER_OTLP_PROTOCOL")
+                .ok()
+                .map_or(self.config.protocol, |s| match s.as_str() {
+                    "grpc" => OtlpProtocol::Grpc,
+                    _ => OtlpProtocol::Http,
+                })
+        })
+    }
+
+    /// The configured collector endpoint for one signal, unless the
+    /// environment sets one (the exporter then reads it itself).
+    ///
+    /// HTTP endpoints get the signal's path appended, as the exporter does
+    /// for `OTEL_EXPORTER_OTLP_ENDPOINT`.
+    fn endpoint(&self, protocol: OtlpProtocol, signal: &str, path: &str) -> Option<String> {
+        if env_value("OTEL_EXPORTER_OTLP_ENDPOINT").is_some()
+            || env_value(&format!("OTEL_EXPORTER_OTLP_{signal}_ENDPOINT")).is_some()
+        {
+            return None;
+        }
+        let endpoint = self.config.endpoint.as_deref()?;
+        Some(match protocol {
+            OtlpProtocol::Http => format!("{}{path}", endpoint.trim_end_matches('/')),
+            OtlpProtocol::Grpc => endpoint.to_owned(),
+        })
+    }
+
+    /// The configured export headers, validated.
+    ///
+    /// The exporters merge `OTEL_EXPORTER_OTLP_HEADERS` over these.
+    fn headers(&self) -> Result<HeaderMap, Error> {
+        self.config
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                    Error::config_with(format!("invalid telemetry header name '{name}'"), e)
+                })?;
+                let header_value = HeaderValue::from_str(value).map_err(|e| {
+                    Error::config_with(format!("invalid value for telemetry header '{name}'"), e)
+                })?;
+                Ok((header_name, header_value))
+            })
+            .collect()
+    }
+
+    /// The trace sampler, unless `OTEL_TRACES_SAMPLER` selects one (the
+    /// provider then reads it itself).
+    fn sampler(&self) -> Option<Sampler> {
+        if env_value("OTEL_TRACES_SAMPLER").is_some() {
+            return None;
+        }
+        let ratio = env_value("OTEL_TRACES_SAMPLER_ARG")
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(self.config.sampling_ratio);
+        Some(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            ratio,
+        ))))
+    }
+
+    /// Builds an `OpenTelemetry` [`Resource`] from the resolved service identity.
+    fn resource(&self) -> Resource {
+        let name = resolve_env("OTEL_SERVICE_NAME", self.name.as_ref());
+        let version = resolve_env("OTEL_SERVICE_VERSION", self.version.as_ref());
+        let deployment = resolve_env("OTEL_SERVICE_DEPLOYMENT", self.deployment.as_ref());
+
+        let mut builder = Resource::builder();
+        if let Some(name) = name {
+            builder = builder.with_service_name(name);
+        }
+        let mut attributes = Vec::<KeyValue>::with_capacity(2);
+        if let Some(version) = version {
+            attributes.push(KeyValue::new(SERVICE_VERSION, version));
+        }
+        if let Some(deployment) = deployment {
+            attributes.push(KeyValue::new(DEPLOYMENT_ENVIRONMENT_NAME, deployment));
+        }
+        if !attributes.is_empty() {
+            builder = builder.with_schema_url(attributes, SCHEMA_URL);
+        }
+
+        // Configured attributes, except those the environment sets.
+        let env_keys = env_resource_keys();
+        let configured = self
+            .config
+            .resource
+            .iter()
+            .filter(|(key, _)| !env_keys.contains(key))
+            .filter(|(key, _)| *key != "service.name" || env_value("OTEL_SERVICE_NAME").is_none())
+            .map(|(key, value)| KeyValue::new(key.clone(), value.clone()));
+        builder.with_attributes(configured).build()
+    }
+
+    /// Initializes the tracer provider.
+    fn init_tracer(
+        &self,
+        protocol: OtlpProtocol,
+        headers: &HeaderMap,
+    ) -> Option<SdkTracerProvider> {
+        let endpoint = self.endpoint(protocol, "TRACES", "/v1/traces");
+        let exporter = match protocol {
+            OtlpProtocol::Http => {
+                let builder = opentelemetry_otlp::SpanExporter::builder()
+                    .with_http()
+                    .with_headers(http_headers(headers));
+                match endpoint {
+                    Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                    None => builder.build(),
+                }
+            }
+            OtlpProtocol::Grpc => {
+                let builder = opentelemetry_otlp::SpanExporter::builder()
+                    .with_tonic()
+                    .with_metadata(MetadataMap::from_headers(headers.clone()));
+                match endpoint {
+                    Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                    None => builder.build(),
+                }
+            }
+        };
+        let exporter = exporter.ok()?;
+
+        let mut builder = SdkTracerProvider::builder()
+            .with_id_generator(RandomIdGenerator::default())
+            .with_resource(self.resource())
+            .with_batch_exporter(exporter);
+        if let Some(sampler) = self.sampler() {
+            builder = builder.with_sampler(sampler);
+        }
+        Some(builder.build())
+    }
+
+    /// Initializes the metrics provider.
+    fn init_meter(&self, protocol: OtlpProtocol, headers: &HeaderMap) -> Option<SdkMeterProvider> {
+        let endpoint = self.endpoint(protocol, "METRICS", "/v1/metrics");
+        let temporality = opentelemetry_sdk::metrics::Temporality::default();
+        let exporter = match protocol {
+            OtlpProtocol::Http => {
+                let builder = opentelemetry_otlp::MetricExporter::builder()
+                    .with_http()
+                    .with_temporality(temporality)
+                    .with_headers(http_headers(headers));
+                match endpoint {
+                    Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                    None => builder.build(),
+                }
+            }
+            OtlpProtocol::Grpc => {
+                let builder = opentelemetry_otlp::MetricExporter::builder()
+                    .with_tonic()
+                    .with_temporality(temporality)
+                    .with_metadata(MetadataMap::from_headers(headers.clone()));
+                match endpoint {
+                    Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                    None => builder.build(),
+                }
+            }
+        };
+        let exporter = exporter.ok()?;
+
+        let mut reader = PeriodicReader::builder(exporter);
+        if env_value("OTEL_METRIC_EXPORT_INTERVAL").is_none() {
+            reader = reader.with_interval(Duration::from_secs(self.config.export_interval_secs));
+        }
+
+        let mut provider = MeterProviderBuilder::default()
+            .with_resource(self.resource())
+            .with_reader(reader.build());
+        if self.config.stdout_metrics {
+            provider = provider.with_reader(
+                PeriodicReader::builder(opentelemetry_stdout::MetricExporter::default()).build(),
+            );
+        }
+        let provider = provider.build();
+        global::set_meter_provider(provider.clone());
+        Some(provider)
+    }
+
+    /// Initializes the logger provider.
+    fn init_logger(
+        &self,
+        protocol: OtlpProtocol,
+        headers: &HeaderMap,
+    ) -> Option<SdkLoggerProvider> {
+        let endpoint = self.endpoint(protocol, "LOGS", "/v1/logs");
+        let exporter = match protocol {
+            OtlpProtocol::Http => {
+                let builder = opentelemetry_otlp::LogExporter::builder()
+                    .with_http()
+                    .with_headers(http_headers(headers));
+                match endpoint {
+                    Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                    None => builder.build(),
+                }
+            }
+            OtlpProtocol::Grpc => {
+                let builder = opentelemetry_otlp::LogExporter::builder()
+                    .with_tonic()
+                    .with_metadata(MetadataMap::from_headers(headers.clone()));
+                match endpoint {
+                    Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                    None => builder.build(),
+                }
+            }
+        };
+        let exporter = exporter.ok()?;
+
+        Some(
+            SdkLoggerProvider::builder()
+                .with_resource(self.resource())
+                .with_batch_exporter(exporter)
+                .build(),
+        )
+    }
+
+    /// Registers tracing, metrics and logs exporters.
+    ///
+    /// When the `[telemetry]` section sets an endpoint or `OTEL_EXPORTER_OTLP_*`
+    /// env vars are present, enables OTLP export. Otherwise falls back to
+    /// console logging.
+    ///
+    /// Returns [`TelemetryGuard`] that flushes exporters on drop.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a configured export header is not a valid HTTP
+    /// header.
+    pub fn register(self) -> Result<TelemetryGuard, Error> {
+        let headers = self.headers()?;
+        let protocol = self.protocol();
+        let (tracer_provider, meter_provider, logger_provider) = protocol.map_or_else(
+            || (None, None, None),
+            |p| {
+                (
+                    self.init_tracer(p, &headers),
+                    self.init_meter(p, &headers),
+                    self.init_logger(p, &headers),
+                )
+            },
+        );
+
+        // Build subscriber: Option<Layer> is a no-op when None
+        let otel_layer = tracer_provider
+            .as_ref()
+            .map(|tp| OpenTelemetryLayer::new(tp.tracer("tracing-otel-subscriber")));
+        let metrics_layer = meter_provider
+            .as_ref()
+            .map(|mp| MetricsLayer::new(mp.clone()));
+        let logs_layer = logger_provider
+            .as_ref()
+            .map(|lp| LogBridge::new(lp.logger("tracing-otel-subscriber")));
+
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let fallback = self.log_level.as_deref().unwrap_or("info");
+        tracing_subscriber::registry()
+            .with(logging::filter(fallback))
+            .with(logging::console(self.log_format, self.log_output))
+            .with(metrics_layer)
+            .with(otel_layer)
+            .with(logs_layer)
+            .init();
+
+        if protocol.is_some() {
+            tracing::info!("OpenTelemetry exporters registered");
+        } else {
+            tracing::info!("OpenTelemetry is not configured, console logging only");
+        }
+
+        Ok(TelemetryGuard {
+            tracer_provider,
+            meter_provider,
+            logger_provider,
+        })
+    }
+}
+
+/// Headers as the HTTP exporter takes them.
+fn http_headers(headers: &HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+/// Owns the tracer, meter and logger providers; performs graceful shutdown
+/// on drop.
+#[derive(Debug)]
+pub struct TelemetryGuard {
+    tracer_provider: Option<SdkTracerProvider>,
+    meter_provider: Option<SdkMeterProvider>,
+    logger_provider: Option<SdkLoggerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(ref tp) = self.tracer_provider
+            && let Err(err) = tp.shutdown()
+        {
+            tracing::error!(?err, "tracer provider shutdown error");
+        }
+        if let Some(ref mp) = self.meter_provider
+            && let Err(err) = mp.shutdown()
+        {
+            tracing::error!(?err, "meter provider shutdown error");
+        }
+        if let Some(ref lp) = self.logger_provider
+            && let Err(err) = lp.shutdown()
+        {
+            tracing::error!(?err, "logger provider shutdown error");
+        }
+    }
+}
+
+impl TelemetryGuard {
+    /// Returns `true` if metrics are being exported.
+    #[must_use]
+    pub const fn metrics_enabled(&self) -> bool {
+        self.meter_provider.is_some()
+    }
+
+    /// Creates an HTTP tracing layer for axum applications.
+    #[must_use]
+    #[allow(clippy::unused_self)]
+    pub fn http_tracing(
+        &self,
+    ) -> TraceLayer<
+        tower_http::classify::SharedClassifier<tower_http::classify::ServerErrorsAsFailures>,
+        HttpMakeSpan,
+        tower_http::trace::DefaultOnRequest,
+        HttpOnResponse,
+    > {
+        TraceLayer::new_for_http()
+            .make_span_with(HttpMakeSpan)
+            .on_response(HttpOnResponse)
+    }
+}
+
+/// Reads W3C trace context fields from request headers.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+/// Writes W3C trace context fields to request headers.
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// Add the current span's trace context to the headers of an outgoing
+/// request, using the global propagator (`traceparent` and `tracestate`).
+#[cfg(any(feature = "chain-eip155", feature = "chain-solana"))]
+pub fn inject_trace_context(headers: &mut HeaderMap) {
+    let context = Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(headers));
+    });
+}
+
+/// Custom span maker for HTTP requests.
+///
+/// The span is parented to the trace context in the request's `traceparent`
+/// and `tracestate` headers, if any; otherwise it starts a new trace.  It
+/// records the `x-request-id` assigned by [`request_id::layer`](crate::request_id::layer).
+#[derive(Clone, Copy, Debug)]
+pub struct HttpMakeSpan;
+
+impl<A> MakeSpan<A> for HttpMakeSpan {
+    fn make_span(&mut self, request: &Request<A>) -> Span {
+        let span = tracing::info_span!(
+            "http_request",
+            otel.kind = "server",
+            otel.name = %format!("{} {}", request.method(), request.uri()),
+            method = %request.method(),
+            uri = %request.uri(),
+            version = ?request.version(),
+            request_id = request_id(request),
+        );
+        let parent = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        // Fails only when no OpenTelemetry layer is installed.
+        let _ = span.set_parent(parent);
+        span
+    }
+}
+
+/// Custom response handler for HTTP tracing.
+#[derive(Clone, Copy, Debug)]
+pub struct HttpOnResponse;
+
+impl<A> OnResponse<A> for HttpOnResponse {
+    fn on_response(self, response: &Response<A>, latency: Duration, span: &Span) {
+        span.record("status", tracing::field::display(response.status()));
+        span.record(
+            "http.status_code",
+            tracing::field::display(response.status().as_u16()),
+        );
+
+        if response.status().is_success() {
+            span.set_status(Status::Ok);
+        } else {
+            span.set_status(Status::error(
+                response
+                    .status()
+                    .canonical_reason()
+                    .unwrap_or("unknown")
+                    .to_string(),
+            ));
+        }
+
+        tracing::info!(
+            "status={} elapsed={}ms",
+            response.status().as_u16(),
+            latency.as_millis()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_subscriber::registry::Registry;
+
+    use super::*;
+
+    #[test]
+    fn incoming_trace_context_parents_the_request_span() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = Registry::default()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let request = Request::builder()
+            .uri("/verify")
+            .header(
+                "traceparent",
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            )
+            .body(())
+            .unwrap();
+        let trace_id = tracing::subscriber::with_default(subscriber, || {
+            let span = HttpMakeSpan.make_span(&request);
+            span.context().span().span_context().trace_id()
+        });
+        assert_eq!(trace_id.to_string(), "4bf92f3577b34da6a3ce929d0e0e4736");
+    }
+}
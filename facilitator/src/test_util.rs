@@ -0,0 +1,71 @@
+//! Helpers shared by unit tests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A scratch directory, unique to the test process and call, removed on drop.
+#[derive(Debug)]
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    /// Create an empty scratch directory whose name starts with `name`.
+    pub fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "facilitator-test-{name}-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create scratch dir");
+        Self(dir)
+    }
+
+    /// Path of the directory.
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Write `content` to the file `name`, creating parent directories.
+    pub fn write(&self, name: &str, content: &str) -> PathBuf {
+        let path = self.0.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("create scratch subdir");
+        }
+        fs::write(&path, content).expect("write scratch file");
+        path
+    }
+
+    /// Paths of the entries of the directory, sorted.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<_> = fs::read_dir(&self.0)
+            .expect("list scratch dir")
+            .map(|entry| entry.expect("scratch dir entry").path())
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Set an environment variable for a test.
+///
+/// Tests using this must pick variable names no other test reads.
+#[allow(unsafe_code, clippy::disallowed_methods)]
+pub fn set_test_env(key: &str, value: &str) {
+    // SAFETY: each variable is touched by a single test only.
+    unsafe { std::env::set_var(key, value) };
+}
+
+/// Remove an environment variable set by [`set_test_env`].
+#[allow(unsafe_code, clippy::disallowed_methods)]
+pub fn remove_test_env(key: &str) {
+    // SAFETY: as for `set_test_env`.
+    unsafe { std::env::remove_var(key) };
+}